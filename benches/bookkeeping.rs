@@ -0,0 +1,115 @@
+//! The per-panel work of a frame that doesn't need a server: acceptor distances, capture
+//! decisions, the orphan watchdog, grab smoothing and wall tiling, at 10, 50 and 200 panels.
+//! `PanelItemUIHandler::frame` itself calls straight into fusion nodes, so it isn't covered.
+
+use std::{
+	hint::black_box,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use mint::Vector2;
+use orbit::{
+	acceptor_link::{closest_acceptor, CaptureGate, Proximity, MAX_ACCEPT_DISTANCE},
+	acceptor_registry::AcceptorRegistry,
+	one_euro::OneEuroFilter,
+	wall::{TilingAlgorithm, WallConfig, WallTiling},
+};
+use stardust_xr_fusion::items::panel::PanelItem;
+
+const PANELS: [usize; 3] = [10, 50, 200];
+/// Acceptors each panel measures its distance to
+const ACCEPTORS: usize = 8;
+
+/// Distances that spread panels in and out of range, with a failed and a NaN measurement mixed in.
+fn distances(panel: usize) -> Vec<(String, Option<f32>)> {
+	(0..ACCEPTORS)
+		.map(|acceptor| {
+			let distance = match (panel + acceptor) % 10 {
+				0 => None,
+				1 => Some(f32::NAN),
+				n => Some(n as f32 * 0.01),
+			};
+			(format!("acceptor{acceptor}"), distance)
+		})
+		.collect()
+}
+
+fn acceptor_bookkeeping(c: &mut Criterion) {
+	let mut group = c.benchmark_group("acceptor_bookkeeping");
+	let policy = Proximity::new(MAX_ACCEPT_DISTANCE);
+	let registry = AcceptorRegistry::new(Arc::new(Proximity::new(MAX_ACCEPT_DISTANCE)));
+	for panels in PANELS {
+		let measured = (0..panels).map(distances).collect::<Vec<_>>();
+		let mut gates = (0..panels)
+			.map(|_| CaptureGate::default())
+			.collect::<Vec<_>>();
+		let start = Instant::now();
+		let mut frame = 0_u32;
+		group.bench_with_input(BenchmarkId::from_parameter(panels), &panels, |b, _| {
+			b.iter(|| {
+				frame += 1;
+				let now = start + Duration::from_millis(11) * frame;
+				for (distances, gate) in measured.iter().zip(&mut gates) {
+					let closest = closest_acceptor(distances.iter().cloned());
+					black_box(gate.decide(&policy, closest.as_ref(), false, true, now));
+					black_box(gate.unanswered(now));
+					let uid = closest.as_ref().map_or("acceptor0", |c| c.uid.as_str());
+					black_box(registry.contains::<PanelItem>(uid));
+				}
+			})
+		});
+	}
+	group.finish();
+}
+
+fn grab_smoothing(c: &mut Criterion) {
+	let mut group = c.benchmark_group("grab_smoothing");
+	for panels in PANELS {
+		let mut filters = vec![OneEuroFilter::<3>::new(1.0, 0.007); panels];
+		let mut frame = 0_u32;
+		group.bench_with_input(BenchmarkId::from_parameter(panels), &panels, |b, _| {
+			b.iter(|| {
+				frame += 1;
+				let now = frame as f32 / 90.0;
+				for (i, filter) in filters.iter_mut().enumerate() {
+					let wobble = (now * 7.0 + i as f32).sin() * 0.001;
+					black_box(filter.filter_array(now, [i as f32 * 0.1 + wobble, 1.5, -0.5]));
+				}
+			})
+		});
+	}
+	group.finish();
+}
+
+fn wall_tiling(c: &mut Criterion) {
+	for (name, algorithm) in [
+		("shelf", TilingAlgorithm::Shelf),
+		("master_stack", TilingAlgorithm::MasterStack),
+	] {
+		let mut group = c.benchmark_group(format!("wall_tiling/{name}"));
+		let wall = WallConfig {
+			position: [0.0; 3],
+			rotation: [0.0, 0.0, 0.0, 1.0],
+			width: 4.0,
+			height: 2.0,
+			tiling: WallTiling {
+				algorithm,
+				..Default::default()
+			},
+		};
+		for panels in PANELS {
+			let sizes = (0..panels)
+				.map(|i| [0.2 + (i % 3) as f32 * 0.05, 0.15].into())
+				.collect::<Vec<Vector2<f32>>>();
+			group.bench_with_input(BenchmarkId::from_parameter(panels), &sizes, |b, sizes| {
+				b.iter(|| black_box(wall.tile(sizes)))
+			});
+		}
+		group.finish();
+	}
+}
+
+criterion_group!(benches, acceptor_bookkeeping, grab_smoothing, wall_tiling);
+criterion_main!(benches);
@@ -0,0 +1,63 @@
+use image::RgbaImage;
+use stardust_xr_fusion::{core::values::rgba_linear, drawable::MaterialParameter};
+use tokio::sync::oneshot;
+
+use crate::desktop_entry::DesktopEntry;
+
+/// Hue buckets pixels are sorted into, the fullest one wins
+const HUE_BUCKETS: usize = 12;
+/// Icons are shrunk to this before counting, plenty for finding the main color
+const SAMPLE_SIZE: u32 = 64;
+
+/// An app's own color, to tell its panels apart at a glance across the room.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accent([f32; 3]);
+impl Accent {
+	/// Looked up off the frame loop, since it means searching desktop entries and decoding an image.
+	pub fn from_icon(app_id: String) -> oneshot::Receiver<Option<Accent>> {
+		let (tx, rx) = oneshot::channel();
+		tokio::task::spawn_blocking(move || {
+			let _ = tx.send(Self::load(&app_id));
+		});
+		rx
+	}
+	fn load(app_id: &str) -> Option<Accent> {
+		let path = DesktopEntry::find(app_id)?.icon_path()?;
+		let icon = image::open(path).ok()?;
+		dominant_color(&icon.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).into_rgba8()).map(Accent)
+	}
+
+	pub fn edge(self) -> MaterialParameter {
+		let [r, g, b] = self.0;
+		MaterialParameter::Color(rgba_linear!(r, g, b, 1.0))
+	}
+}
+
+/// Average of the most common hue among opaque, saturated pixels, in linear RGB.
+/// Greys and outlines are left out so a dark border doesn't win over the icon's actual color.
+fn dominant_color(icon: &RgbaImage) -> Option<[f32; 3]> {
+	let mut buckets = [([0.0; 3], 0u32); HUE_BUCKETS];
+	for pixel in icon.pixels() {
+		let [r, g, b, a] = pixel.0.map(|c| c as f32 / 255.0);
+		let max = r.max(g).max(b);
+		let min = r.min(g).min(b);
+		if a < 0.5 || max < 0.2 || (max - min) / max < 0.25 {
+			continue;
+		}
+		let hue = if max == r {
+			(g - b) / (max - min)
+		} else if max == g {
+			2.0 + (b - r) / (max - min)
+		} else {
+			4.0 + (r - g) / (max - min)
+		};
+		let bucket = ((hue.rem_euclid(6.0) / 6.0) * HUE_BUCKETS as f32) as usize % HUE_BUCKETS;
+		let (sum, count) = &mut buckets[bucket];
+		for (sum, c) in sum.iter_mut().zip([r, g, b]) {
+			*sum += c;
+		}
+		*count += 1;
+	}
+	let (sum, count) = buckets.into_iter().max_by_key(|(_, count)| *count)?;
+	(count > 0).then(|| sum.map(|c| (c / count as f32).powf(2.2)))
+}
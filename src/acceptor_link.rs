@@ -0,0 +1,571 @@
+use std::{
+	sync::{
+		atomic::{AtomicU32, Ordering},
+		Arc, Mutex,
+	},
+	time::{Duration, Instant},
+};
+
+use map_range::MapRange;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::MaterialParameter,
+	fields::{FieldAspect, UnknownField},
+	items::{Item, ItemAcceptor},
+	node::NodeType,
+	spatial::SpatialAspect,
+};
+use stardust_xr_molecules::multi::multi_node_call;
+use tokio::sync::watch;
+
+use crate::{acceptor_registry::AcceptorMetrics, event_log};
+
+pub type Acceptors<I> = FxHashMap<String, (ItemAcceptor<I>, UnknownField)>;
+
+pub const MAX_ACCEPT_DISTANCE: f32 = 0.05;
+/// How long an item waits to ask again after asking to be captured, while the acceptor gets around to it
+pub const CAPTURE_COOLDOWN: Duration = Duration::from_millis(500);
+/// How long an item has to be held within range of an acceptor to be captured as a preview
+pub const PREVIEW_DWELL: Duration = Duration::from_secs(1);
+/// How long an acceptor has to capture an item it was asked to take before it's asked once more,
+/// and then again before it counts as turning the item down
+pub const CAPTURE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptorDistance {
+	pub uid: String,
+	pub distance: f32,
+}
+
+/// Edge tint for an item, white when no acceptor is around.
+pub fn proximity_color(closest: Option<&AcceptorDistance>) -> MaterialParameter {
+	let Some(closest) = closest else {
+		return MaterialParameter::Color(rgba_linear!(1.0, 1.0, 1.0, 1.0));
+	};
+	let gradient = colorgrad::magma();
+	let color = gradient.at(closest
+		.distance
+		.map_range(0.25..MAX_ACCEPT_DISTANCE, 0.0..1.0) as f64);
+	MaterialParameter::Color(rgba_linear!(
+		color.r as f32,
+		color.g as f32,
+		color.b as f32,
+		color.a as f32
+	))
+}
+
+/// Decides when a free panel gets handed to an acceptor.
+pub trait CapturePolicy: Send + Sync {
+	/// `released` is true once the panel has been let go of or thrown.
+	fn should_capture(&self, closest: &AcceptorDistance, released: bool) -> bool;
+	/// For policies with a range, called when the accept distance is changed live.
+	fn set_max_distance(&self, _max_distance: f32) {}
+}
+
+/// Capture into the closest acceptor when released within range.
+pub struct Proximity {
+	/// `f32` bits, so it can be changed while links hold the policy
+	max_distance: AtomicU32,
+}
+impl Proximity {
+	pub fn new(max_distance: f32) -> Self {
+		Proximity {
+			max_distance: AtomicU32::new(max_distance.to_bits()),
+		}
+	}
+}
+impl CapturePolicy for Proximity {
+	fn should_capture(&self, closest: &AcceptorDistance, released: bool) -> bool {
+		released && closest.distance < f32::from_bits(self.max_distance.load(Ordering::Relaxed))
+	}
+	fn set_max_distance(&self, max_distance: f32) {
+		self.max_distance
+			.store(max_distance.to_bits(), Ordering::Relaxed);
+	}
+}
+
+/// Acceptor distances are still shown, but nothing is ever captured.
+pub struct Disabled;
+impl CapturePolicy for Disabled {
+	fn should_capture(&self, _closest: &AcceptorDistance, _released: bool) -> bool {
+		false
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CapturePolicyKind {
+	#[default]
+	Proximity,
+	Disabled,
+}
+impl CapturePolicyKind {
+	pub fn create(self, max_distance: f32) -> Arc<dyn CapturePolicy> {
+		match self {
+			CapturePolicyKind::Proximity => Arc::new(Proximity::new(max_distance)),
+			CapturePolicyKind::Disabled => Arc::new(Disabled),
+		}
+	}
+}
+
+/// Whether an item counts as let go, thrown and still flying or dropped this frame.
+pub fn released(grabbed: bool, flying: bool, just_dropped: bool) -> bool {
+	!grabbed && flying || just_dropped
+}
+
+/// The closest of the measured acceptors, leaving out failed and NaN measurements so they never win.
+pub fn closest_acceptor(
+	distances: impl IntoIterator<Item = (String, Option<f32>)>,
+) -> Option<AcceptorDistance> {
+	distances
+		.into_iter()
+		.filter_map(|(uid, distance)| {
+			let distance = distance?.abs();
+			(!distance.is_nan()).then_some(AcceptorDistance { uid, distance })
+		})
+		.reduce(|a, b| if b.distance < a.distance { b } else { a })
+}
+
+/// The capture decision for one item, apart from measuring so it only needs numbers and a clock.
+#[derive(Debug, Default)]
+pub struct CaptureGate {
+	last_request: Option<Instant>,
+	/// Capture items held still in range for a while, without waiting for them to be let go
+	pub preview: bool,
+	held_in_range_since: Option<Instant>,
+	awaiting: Option<Awaiting>,
+	/// A request couldn't even be sent
+	failed: bool,
+}
+/// The capture request still waiting for an answer.
+#[derive(Debug)]
+struct Awaiting {
+	acceptor: String,
+	since: Instant,
+	retried: bool,
+}
+/// What to do about a capture request that went unanswered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Unanswered {
+	/// Ask this acceptor once more
+	Retry(String),
+	TurnedDown,
+}
+impl CaptureGate {
+	pub fn requested(&mut self, acceptor: &str, sent: bool, now: Instant) {
+		if !sent {
+			self.awaiting = None;
+			self.failed = true;
+		} else if self.awaiting.is_none() {
+			self.awaiting = Some(Awaiting {
+				acceptor: acceptor.to_string(),
+				since: now,
+				retried: false,
+			});
+		}
+	}
+	pub fn captured(&mut self) {
+		self.awaiting = None;
+		self.failed = false;
+	}
+	/// Reported once per request that couldn't be sent or went unanswered for [`CAPTURE_TIMEOUT`],
+	/// retrying the first time it went unanswered.
+	pub fn unanswered(&mut self, now: Instant) -> Option<Unanswered> {
+		if std::mem::take(&mut self.failed) {
+			return Some(Unanswered::TurnedDown);
+		}
+		let awaiting = self.awaiting.as_mut()?;
+		if now.saturating_duration_since(awaiting.since) < CAPTURE_TIMEOUT {
+			return None;
+		}
+		if awaiting.retried {
+			self.awaiting = None;
+			return Some(Unanswered::TurnedDown);
+		}
+		awaiting.retried = true;
+		awaiting.since = now;
+		Some(Unanswered::Retry(awaiting.acceptor.clone()))
+	}
+
+	/// The acceptor to ask for a capture right now, if any.
+	pub fn decide<'a>(
+		&mut self,
+		policy: &dyn CapturePolicy,
+		closest: Option<&'a AcceptorDistance>,
+		grabbed: bool,
+		released: bool,
+		now: Instant,
+	) -> Option<&'a AcceptorDistance> {
+		let in_range = closest.is_some_and(|closest| policy.should_capture(closest, true));
+		self.held_in_range_since =
+			(self.preview && grabbed && in_range).then(|| self.held_in_range_since.unwrap_or(now));
+		let closest = closest?;
+		let previewing = self
+			.held_in_range_since
+			.is_some_and(|since| now.saturating_duration_since(since) >= PREVIEW_DWELL);
+		if !previewing && !policy.should_capture(closest, released) {
+			return None;
+		}
+		if self
+			.last_request
+			.is_some_and(|last| now.saturating_duration_since(last) < CAPTURE_COOLDOWN)
+		{
+			return None;
+		}
+		self.last_request = Some(now);
+		Some(closest)
+	}
+}
+
+/// Tracks the closest acceptor to one item and captures it according to a policy.
+pub struct AcceptorLink<I: Item> {
+	acceptors: watch::Receiver<Acceptors<I>>,
+	policy: Arc<dyn CapturePolicy>,
+	metrics: Arc<AcceptorMetrics>,
+	closest_tx: Arc<watch::Sender<Option<AcceptorDistance>>>,
+	closest_rx: watch::Receiver<Option<AcceptorDistance>>,
+	gate: Arc<Mutex<CaptureGate>>,
+}
+impl<I: Item + SpatialAspect + NodeType + Send + Sync + 'static> AcceptorLink<I> {
+	pub fn new(
+		acceptors: watch::Receiver<Acceptors<I>>,
+		policy: Arc<dyn CapturePolicy>,
+		metrics: Arc<AcceptorMetrics>,
+	) -> Self {
+		let (closest_tx, closest_rx) = watch::channel(None);
+		AcceptorLink {
+			acceptors,
+			policy,
+			metrics,
+			closest_tx: Arc::new(closest_tx),
+			closest_rx,
+			gate: Arc::default(),
+		}
+	}
+
+	/// Items held in range for [`PREVIEW_DWELL`] get captured while still grabbed, for the UI to hand back if pulled away.
+	pub fn with_preview(self, preview: bool) -> Self {
+		self.gate.lock().unwrap().preview = preview;
+		self
+	}
+
+	/// Called once the item was actually captured, so the request isn't counted as turned down.
+	pub fn captured(&self) {
+		self.gate.lock().unwrap().captured();
+	}
+	/// True once per capture request an acceptor failed or ignored, after asking it a second time.
+	pub fn take_rejection(&self, item: &I) -> bool {
+		let unanswered = self.gate.lock().unwrap().unanswered(Instant::now());
+		let Some(Unanswered::Retry(uid)) = unanswered else {
+			return unanswered.is_some();
+		};
+		let Some(acceptor) = self.acceptors.borrow().get(&uid).map(|(a, _)| a.alias()) else {
+			self.gate.lock().unwrap().captured();
+			return true;
+		};
+		self.metrics
+			.capture_requests
+			.fetch_add(1, Ordering::Relaxed);
+		let result = acceptor.capture(item);
+		event_log::record(
+			None,
+			match &result {
+				Ok(()) => format!("Asked {uid} again to capture an item it didn't take"),
+				Err(e) => format!("Couldn't ask {uid} again to capture an item: {e}"),
+			},
+		);
+		if result.is_err() {
+			self.gate.lock().unwrap().captured();
+		}
+		result.is_err()
+	}
+
+	/// Updated asynchronously after each `update`.
+	pub fn closest(&mut self) -> &mut watch::Receiver<Option<AcceptorDistance>> {
+		&mut self.closest_rx
+	}
+
+	pub fn update(&self, item: &I, grabbed: bool, released: bool) {
+		if self.acceptors.borrow().is_empty() {
+			self.closest_tx.send_if_modified(|c| c.take().is_some());
+			return;
+		}
+		let (keys, fields): (Vec<String>, Vec<UnknownField>) = self
+			.acceptors
+			.borrow()
+			.iter()
+			.map(|(uid, (_, field))| (uid.clone(), field.alias()))
+			.unzip();
+		let acceptors = self.acceptors.clone();
+		let policy = self.policy.clone();
+		let metrics = self.metrics.clone();
+		let closest_tx = self.closest_tx.clone();
+		let gate = self.gate.clone();
+		let item = item.alias();
+		tokio::spawn(async move {
+			let started = Instant::now();
+			let distances = multi_node_call(fields.into_iter(), |f| {
+				let item = item.alias();
+				Ok(async move { f.distance(&item, [0.0; 3]).await })
+			})
+			.await;
+			metrics.distance_query.observe(started.elapsed());
+			let closest = closest_acceptor(
+				keys.into_iter()
+					.zip(distances.into_iter().map(|distance| distance.ok())),
+			);
+			// Static panels measure the same distance every frame, and every change
+			// means a material update, so only wake receivers when it actually moved.
+			closest_tx.send_if_modified(|c| {
+				let changed = *c != closest;
+				if changed {
+					c.clone_from(&closest);
+				}
+				changed
+			});
+
+			let decision = gate.lock().unwrap().decide(
+				policy.as_ref(),
+				closest.as_ref(),
+				grabbed,
+				released,
+				Instant::now(),
+			);
+			if let Some(closest) = decision {
+				let Some(acceptor) = acceptors.borrow().get(&closest.uid).map(|(a, _)| a.alias())
+				else {
+					return;
+				};
+				metrics.capture_requests.fetch_add(1, Ordering::Relaxed);
+				let result = acceptor.capture(&item);
+				gate.lock()
+					.unwrap()
+					.requested(&closest.uid, result.is_ok(), Instant::now());
+				event_log::record(
+					None,
+					match result {
+						Ok(()) => format!(
+							"Asked {} to capture an item {:.1} cm away",
+							closest.uid,
+							closest.distance * 100.0
+						),
+						Err(e) => format!("Couldn't ask {} to capture an item: {e}", closest.uid),
+					},
+				);
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::prelude::*;
+
+	use super::*;
+
+	fn distance() -> impl Strategy<Value = Option<f32>> {
+		prop_oneof![
+			Just(None),
+			Just(Some(f32::NAN)),
+			Just(Some(-f32::NAN)),
+			any::<f32>().prop_map(Some),
+			(-1.0_f32..1.0).prop_map(Some),
+		]
+	}
+	fn measured(distances: &[Option<f32>]) -> Vec<(String, Option<f32>)> {
+		distances
+			.iter()
+			.enumerate()
+			.map(|(i, distance)| (format!("acceptor{i}"), *distance))
+			.collect()
+	}
+	fn in_range() -> AcceptorDistance {
+		AcceptorDistance {
+			uid: "acceptor".to_string(),
+			distance: 0.0,
+		}
+	}
+
+	proptest! {
+		#[test]
+		fn nan_and_failed_distances_never_win(distances in prop::collection::vec(distance(), 0..16)) {
+			let measured = measured(&distances);
+			match closest_acceptor(measured.clone()) {
+				Some(closest) => {
+					prop_assert!(!closest.distance.is_nan());
+					let (_, distance) = measured.iter().find(|(uid, _)| *uid == closest.uid).unwrap();
+					prop_assert!(distance.is_some_and(|d| !d.is_nan()));
+				}
+				None => prop_assert!(distances.iter().all(|d| d.map_or(true, f32::is_nan))),
+			}
+		}
+
+		#[test]
+		fn closest_is_the_true_minimum(distances in prop::collection::vec(distance(), 0..16)) {
+			let closest = closest_acceptor(measured(&distances));
+			let minimum = distances
+				.iter()
+				.enumerate()
+				.filter_map(|(i, d)| Some((i, (*d)?.abs())).filter(|(_, d)| !d.is_nan()))
+				.fold(None::<(usize, f32)>, |min, (i, d)| match min {
+					Some((_, min_d)) if min_d <= d => min,
+					_ => Some((i, d)),
+				});
+			prop_assert_eq!(
+				closest.map(|c| (c.uid, c.distance)),
+				minimum.map(|(i, d)| (format!("acceptor{i}"), d))
+			);
+		}
+
+		#[test]
+		fn cooldown_is_honoured_whatever_the_clock_does(offsets in prop::collection::vec(0_u64..3000, 1..32)) {
+			let start = Instant::now();
+			let policy = Proximity::new(MAX_ACCEPT_DISTANCE);
+			let closest = in_range();
+			let mut gate = CaptureGate::default();
+			let cooldown = CAPTURE_COOLDOWN.as_millis() as u64;
+			let mut last_request = None::<u64>;
+			for offset in offsets {
+				let now = start + Duration::from_millis(offset);
+				let asked = gate.decide(&policy, Some(&closest), false, true, now).is_some();
+				let expected = last_request.map_or(true, |last| offset.saturating_sub(last) >= cooldown);
+				prop_assert_eq!(asked, expected, "at {}ms, last asked at {:?}ms", offset, last_request);
+				if asked {
+					last_request = Some(offset);
+				}
+			}
+		}
+
+		#[test]
+		fn proximity_respects_max_distance(
+			distance in any::<f32>(),
+			max_distance in 0.0_f32..1.0,
+			changed_max_distance in 0.0_f32..1.0,
+			released in any::<bool>(),
+		) {
+			let closest = AcceptorDistance { uid: "acceptor".to_string(), distance };
+			let policy = Proximity::new(max_distance);
+			prop_assert_eq!(policy.should_capture(&closest, released), released && distance < max_distance);
+			policy.set_max_distance(changed_max_distance);
+			prop_assert_eq!(
+				policy.should_capture(&closest, released),
+				released && distance < changed_max_distance
+			);
+			prop_assert!(!Disabled.should_capture(&closest, released));
+		}
+
+		#[test]
+		fn unanswered_requests_retry_once_then_turn_down(mut polls in prop::collection::vec(0_u64..5000, 0..32)) {
+			polls.sort_unstable();
+			let start = Instant::now();
+			let mut gate = CaptureGate::default();
+			gate.requested("acceptor", true, start);
+			let timeout = CAPTURE_TIMEOUT.as_millis() as u64;
+			let mut reports = Vec::new();
+			for poll in polls {
+				if let Some(unanswered) = gate.unanswered(start + Duration::from_millis(poll)) {
+					reports.push((poll, unanswered));
+				}
+			}
+			prop_assert!(reports.len() <= 2);
+			if let Some((retried_at, first)) = reports.first() {
+				prop_assert_eq!(first, &Unanswered::Retry("acceptor".to_string()));
+				prop_assert!(*retried_at >= timeout);
+				if let Some((turned_down_at, second)) = reports.get(1) {
+					prop_assert_eq!(second, &Unanswered::TurnedDown);
+					prop_assert!(turned_down_at - retried_at >= timeout);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn released_means_let_go_while_flying_or_just_dropped() {
+		assert!(released(false, true, false));
+		assert!(released(true, false, true));
+		assert!(released(false, false, true));
+		assert!(!released(true, true, false));
+		assert!(!released(false, false, false));
+	}
+
+	#[test]
+	fn nothing_out_of_range_is_asked_for() {
+		let policy = Proximity::new(MAX_ACCEPT_DISTANCE);
+		let far = AcceptorDistance {
+			uid: "acceptor".to_string(),
+			distance: MAX_ACCEPT_DISTANCE * 2.0,
+		};
+		let mut gate = CaptureGate::default();
+		let now = Instant::now();
+		assert_eq!(gate.decide(&policy, Some(&far), false, true, now), None);
+		assert_eq!(gate.decide(&policy, None, false, true, now), None);
+		assert_eq!(
+			gate.decide(&policy, Some(&in_range()), true, false, now),
+			None
+		);
+	}
+
+	#[test]
+	fn previews_capture_after_dwelling_in_range() {
+		let policy = Proximity::new(MAX_ACCEPT_DISTANCE);
+		let closest = in_range();
+		let mut gate = CaptureGate {
+			preview: true,
+			..Default::default()
+		};
+		let start = Instant::now();
+		assert_eq!(
+			gate.decide(&policy, Some(&closest), true, false, start),
+			None
+		);
+		assert_eq!(
+			gate.decide(
+				&policy,
+				Some(&closest),
+				true,
+				false,
+				start + PREVIEW_DWELL / 2
+			),
+			None
+		);
+		assert_eq!(
+			gate.decide(&policy, Some(&closest), true, false, start + PREVIEW_DWELL),
+			Some(&closest)
+		);
+	}
+
+	#[test]
+	fn unanswered_retries_once_then_turns_down() {
+		let start = Instant::now();
+		let mut gate = CaptureGate::default();
+		gate.requested("acceptor", true, start);
+		// A second request while waiting doesn't restart the wait
+		gate.requested("acceptor", true, start + CAPTURE_TIMEOUT / 2);
+		assert_eq!(gate.unanswered(start + CAPTURE_TIMEOUT / 2), None);
+		assert_eq!(
+			gate.unanswered(start + CAPTURE_TIMEOUT),
+			Some(Unanswered::Retry("acceptor".to_string()))
+		);
+		assert_eq!(gate.unanswered(start + CAPTURE_TIMEOUT * 3 / 2), None);
+		assert_eq!(
+			gate.unanswered(start + CAPTURE_TIMEOUT * 2),
+			Some(Unanswered::TurnedDown)
+		);
+		assert_eq!(gate.unanswered(start + CAPTURE_TIMEOUT * 10), None);
+	}
+
+	#[test]
+	fn captures_and_send_failures_settle_requests() {
+		let start = Instant::now();
+		let mut gate = CaptureGate::default();
+		gate.requested("acceptor", true, start);
+		gate.captured();
+		assert_eq!(gate.unanswered(start + CAPTURE_TIMEOUT * 3), None);
+
+		gate.requested("acceptor", false, start);
+		assert_eq!(gate.unanswered(start), Some(Unanswered::TurnedDown));
+		assert_eq!(gate.unanswered(start + CAPTURE_TIMEOUT * 3), None);
+	}
+}
@@ -0,0 +1,197 @@
+use std::{
+	any::{Any, TypeId},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	fields::UnknownField,
+	items::{Item, ItemAcceptor},
+	node::NodeType,
+	spatial::SpatialAspect,
+};
+use tokio::sync::watch;
+
+use crate::{
+	acceptor_link::{AcceptorLink, Acceptors, CapturePolicy},
+	metrics::Histogram,
+};
+
+#[derive(Debug, Default)]
+pub struct AcceptorMetrics {
+	pub capture_requests: AtomicU64,
+	pub captures: AtomicU64,
+	pub releases: AtomicU64,
+	pub distance_query: Histogram,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptorMetricsSnapshot {
+	pub item_type: String,
+	pub acceptors: usize,
+	pub capture_requests: u64,
+	pub captures: u64,
+	pub releases: u64,
+}
+
+trait ErasedChannel: Send + Sync {
+	fn item_type(&self) -> String;
+	fn raw_metrics(&self) -> Arc<AcceptorMetrics>;
+	fn snapshot(&self) -> AcceptorMetricsSnapshot;
+	fn as_any(&self) -> &dyn Any;
+}
+
+struct Channel<I: Item> {
+	acceptors: watch::Sender<Acceptors<I>>,
+	metrics: Arc<AcceptorMetrics>,
+}
+impl<I: Item + Send + Sync + 'static> ErasedChannel for Channel<I> {
+	fn item_type(&self) -> String {
+		let item_type = std::any::type_name::<I>();
+		item_type
+			.rsplit("::")
+			.next()
+			.unwrap_or(item_type)
+			.to_string()
+	}
+	fn raw_metrics(&self) -> Arc<AcceptorMetrics> {
+		self.metrics.clone()
+	}
+	fn snapshot(&self) -> AcceptorMetricsSnapshot {
+		AcceptorMetricsSnapshot {
+			item_type: self.item_type(),
+			acceptors: self.acceptors.borrow().len(),
+			capture_requests: self.metrics.capture_requests.load(Ordering::Relaxed),
+			captures: self.metrics.captures.load(Ordering::Relaxed),
+			releases: self.metrics.releases.load(Ordering::Relaxed),
+		}
+	}
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+/// Acceptor bookkeeping for every item type, shared by all the item UI handlers.
+#[derive(Clone)]
+pub struct AcceptorRegistry {
+	channels: Arc<Mutex<FxHashMap<TypeId, Box<dyn ErasedChannel>>>>,
+	policy: Arc<dyn CapturePolicy>,
+}
+impl AcceptorRegistry {
+	pub fn new(policy: Arc<dyn CapturePolicy>) -> Self {
+		AcceptorRegistry {
+			channels: Default::default(),
+			policy,
+		}
+	}
+
+	pub fn policy(&self) -> &Arc<dyn CapturePolicy> {
+		&self.policy
+	}
+
+	fn with_channel<I: Item + Send + Sync + 'static, O>(
+		&self,
+		f: impl FnOnce(&Channel<I>) -> O,
+	) -> O {
+		let mut channels = self.channels.lock().unwrap();
+		let channel = channels.entry(TypeId::of::<I>()).or_insert_with(|| {
+			Box::new(Channel::<I> {
+				acceptors: watch::channel(FxHashMap::default()).0,
+				metrics: Default::default(),
+			})
+		});
+		f(channel.as_any().downcast_ref::<Channel<I>>().unwrap())
+	}
+
+	pub fn link<I: Item + SpatialAspect + NodeType + Send + Sync + 'static>(
+		&self,
+	) -> AcceptorLink<I> {
+		self.with_channel::<I, _>(|c| {
+			AcceptorLink::new(
+				c.acceptors.subscribe(),
+				self.policy.clone(),
+				c.metrics.clone(),
+			)
+		})
+	}
+
+	pub fn acceptor_created<I: Item + Send + Sync + 'static>(
+		&self,
+		uid: String,
+		acceptor: ItemAcceptor<I>,
+		field: UnknownField,
+	) {
+		self.with_channel::<I, _>(|c| {
+			c.acceptors.send_modify(|a| {
+				a.insert(uid, (acceptor, field));
+			})
+		});
+	}
+	pub fn acceptor_destroyed<I: Item + Send + Sync + 'static>(&self, uid: &str) {
+		self.with_channel::<I, _>(|c| {
+			c.acceptors.send_modify(|a| {
+				a.remove(uid);
+			})
+		});
+	}
+
+	/// Hand an item straight to a known acceptor, returning false if it isn't around.
+	pub fn capture<I: Item + SpatialAspect + NodeType + Send + Sync + 'static>(
+		&self,
+		acceptor_uid: &str,
+		item: &I,
+	) -> bool {
+		self.with_channel::<I, _>(|c| {
+			let acceptors = c.acceptors.borrow();
+			let Some((acceptor, _)) = acceptors.get(acceptor_uid) else {
+				return false;
+			};
+			c.metrics.capture_requests.fetch_add(1, Ordering::Relaxed);
+			acceptor.capture(item).is_ok()
+		})
+	}
+
+	pub fn contains<I: Item + Send + Sync + 'static>(&self, acceptor_uid: &str) -> bool {
+		self.with_channel::<I, _>(|c| c.acceptors.borrow().contains_key(acceptor_uid))
+	}
+
+	/// Fields of every acceptor for this item type, for drawing where they are.
+	pub fn fields<I: Item + Send + Sync + 'static>(&self) -> Vec<UnknownField> {
+		self.with_channel::<I, _>(|c| {
+			c.acceptors
+				.borrow()
+				.values()
+				.map(|(_, field)| field.alias())
+				.collect()
+		})
+	}
+
+	pub fn captured<I: Item + Send + Sync + 'static>(&self) {
+		self.with_channel::<I, _>(|c| c.metrics.captures.fetch_add(1, Ordering::Relaxed));
+	}
+	pub fn released<I: Item + Send + Sync + 'static>(&self) {
+		self.with_channel::<I, _>(|c| c.metrics.releases.fetch_add(1, Ordering::Relaxed));
+	}
+
+	/// The live counters by item type, for histograms that don't fit in a snapshot.
+	pub fn raw_metrics(&self) -> Vec<(String, Arc<AcceptorMetrics>)> {
+		self.channels
+			.lock()
+			.unwrap()
+			.values()
+			.map(|c| (c.item_type(), c.raw_metrics()))
+			.collect()
+	}
+	pub fn metrics(&self) -> Vec<AcceptorMetricsSnapshot> {
+		self.channels
+			.lock()
+			.unwrap()
+			.values()
+			.map(|c| c.snapshot())
+			.collect()
+	}
+}
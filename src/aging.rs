@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgingConfig {
+	/// Seconds without focus before a panel counts as stale, never when unset
+	pub after: Option<f32>,
+	/// Share of its edge brightness a stale panel keeps
+	pub brightness: f32,
+}
+impl Default for AgingConfig {
+	fn default() -> Self {
+		AgingConfig {
+			after: None,
+			brightness: 0.35,
+		}
+	}
+}
+
+/// How long a panel has gone without focus, so stale clutter can fade out of the way.
+pub struct Aging {
+	after: f32,
+	brightness: f32,
+	last_focused: Option<f32>,
+	stale: bool,
+}
+impl Aging {
+	pub fn new(config: &AgingConfig) -> Option<Self> {
+		Some(Aging {
+			after: config.after?,
+			brightness: config.brightness,
+			last_focused: None,
+			stale: false,
+		})
+	}
+
+	/// Whether the panel went stale or fresh this frame, counting from its first frame when never focused.
+	pub fn update(&mut self, focused: bool, now: f32) -> bool {
+		let last_focused = self.last_focused.get_or_insert(now);
+		if focused {
+			*last_focused = now;
+		}
+		let stale = now - *last_focused >= self.after;
+		std::mem::replace(&mut self.stale, stale) != stale
+	}
+	pub fn stale(&self) -> bool {
+		self.stale
+	}
+	/// Factor for the edge brightness, 1 while fresh.
+	pub fn brightness(&self) -> f32 {
+		if self.stale {
+			self.brightness
+		} else {
+			1.0
+		}
+	}
+}
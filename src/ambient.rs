@@ -0,0 +1,94 @@
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+/// Seconds between checks of the clock or the last ambient level
+const CHECK_INTERVAL: f32 = 30.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientConfig {
+	/// Edge brightness from 0 to 1 in a dark room, always full when unset
+	pub dark_brightness: Option<f32>,
+	/// Local `HH:MM` the room counts as dark from, until an ambient level comes in over IPC
+	pub dusk: String,
+	/// Local `HH:MM` the room counts as light again
+	pub dawn: String,
+}
+impl Default for AmbientConfig {
+	fn default() -> Self {
+		AmbientConfig {
+			dark_brightness: None,
+			dusk: "20:00".to_string(),
+			dawn: "07:00".to_string(),
+		}
+	}
+}
+impl AmbientConfig {
+	pub fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+		let parse = |key: &str, time: &str| {
+			NaiveTime::parse_from_str(time, "%H:%M")
+				.map_err(|_| format!("ambient.{key} {time:?} isn't HH:MM"))
+		};
+		Ok((parse("dusk", &self.dusk)?, parse("dawn", &self.dawn)?))
+	}
+}
+
+/// How bright panel frames should be so they don't glare in the dark or wash out in daylight passthrough.
+pub struct Ambient {
+	config: AmbientConfig,
+	/// Measured light from 0 for dark to 1 for daylight, overriding the clock
+	level: Option<f32>,
+	brightness: f32,
+	last_check: f32,
+}
+impl Ambient {
+	pub fn new(config: AmbientConfig) -> Self {
+		Ambient {
+			config,
+			level: None,
+			brightness: 1.0,
+			last_check: f32::NEG_INFINITY,
+		}
+	}
+	pub fn brightness(&self) -> f32 {
+		self.brightness
+	}
+	/// Like passthrough camera brightness, going back to the clock when `None`.
+	pub fn set_level(&mut self, level: Option<f32>) {
+		self.level = level.map(|level| level.clamp(0.0, 1.0));
+		self.last_check = f32::NEG_INFINITY;
+	}
+
+	/// `true` when the brightness changed.
+	pub fn update(&mut self, now: f32) -> bool {
+		let Some(dark_brightness) = self.config.dark_brightness else {
+			return false;
+		};
+		if now - self.last_check < CHECK_INTERVAL {
+			return false;
+		}
+		self.last_check = now;
+		let level = self
+			.level
+			.unwrap_or_else(|| if self.dark_by_clock() { 0.0 } else { 1.0 });
+		let dark_brightness = dark_brightness.clamp(0.0, 1.0);
+		let brightness = dark_brightness + (1.0 - dark_brightness) * level;
+		if brightness == self.brightness {
+			return false;
+		}
+		self.brightness = brightness;
+		true
+	}
+
+	fn dark_by_clock(&self) -> bool {
+		let Ok((dusk, dawn)) = self.config.times() else {
+			return false;
+		};
+		let now = Local::now().time();
+		if dusk > dawn {
+			now >= dusk || now < dawn
+		} else {
+			now >= dusk && now < dawn
+		}
+	}
+}
@@ -0,0 +1,147 @@
+use glam::Quat;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines, Text, TextStyle},
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use tokio::sync::mpsc;
+
+/// Half the length of each arm of an anchor's cross marker, in meters
+const MARKER_SIZE: f32 = 0.01;
+const LABEL_HEIGHT: f32 = 0.006;
+
+/// A named spot to send panels back to, like "above desk".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anchor {
+	pub name: String,
+	/// Relative to the client root
+	pub translation: [f32; 3],
+	/// Quaternion as `[x, y, z, w]`
+	pub rotation: [f32; 4],
+}
+impl Anchor {
+	pub fn transform(&self) -> Transform {
+		Transform::from_translation_rotation(self.translation, Quat::from_array(self.rotation))
+	}
+}
+
+struct Marker {
+	_lines: Lines,
+	_label: Text,
+}
+impl Marker {
+	fn create(reference: &Spatial, anchor: &Anchor) -> Option<Self> {
+		let arm = |axis: [f32; 3]| Line {
+			points: [-MARKER_SIZE, MARKER_SIZE]
+				.into_iter()
+				.map(|t| LinePoint {
+					point: axis.map(|a| a * t).into(),
+					thickness: 0.001,
+					color: rgba_linear!(1.0, 1.0, 1.0, 0.3),
+				})
+				.collect(),
+			cyclic: false,
+		};
+		let lines = Lines::create(
+			reference,
+			anchor.transform(),
+			&[arm([1.0, 0.0, 0.0]), arm([0.0, 1.0, 0.0])],
+		)
+		.ok()?;
+		let label = Text::create(
+			&lines,
+			Transform::from_translation([0.0, MARKER_SIZE * 1.5, 0.0]),
+			&anchor.name,
+			TextStyle {
+				character_height: LABEL_HEIGHT,
+				..Default::default()
+			},
+		)
+		.ok()?;
+		Some(Marker {
+			_lines: lines,
+			_label: label,
+		})
+	}
+}
+
+/// Saved anchors, with faint markers at each while a panel is held.
+pub struct Anchors {
+	reference: Spatial,
+	anchors: Vec<Anchor>,
+	markers: Option<Vec<Marker>>,
+	saved_tx: mpsc::UnboundedSender<Anchor>,
+	saved_rx: mpsc::UnboundedReceiver<Anchor>,
+}
+impl Anchors {
+	pub fn new(reference: &Spatial, anchors: Vec<Anchor>) -> Self {
+		let (saved_tx, saved_rx) = mpsc::unbounded_channel();
+		Anchors {
+			reference: reference.alias(),
+			anchors,
+			markers: None,
+			saved_tx,
+			saved_rx,
+		}
+	}
+	pub fn anchors(&self) -> &[Anchor] {
+		&self.anchors
+	}
+	pub fn get(&self, name: &str) -> Option<&Anchor> {
+		self.anchors.iter().find(|anchor| anchor.name == name)
+	}
+
+	/// Saves where `spatial` is right now under `name`, replacing any anchor already called that.
+	pub fn save(&self, name: String, spatial: &Spatial) {
+		let spatial = spatial.alias();
+		let reference = self.reference.alias();
+		let saved_tx = self.saved_tx.clone();
+		tokio::spawn(async move {
+			let Ok(transform) = spatial.get_transform(&reference).await else {
+				return;
+			};
+			let (Some(translation), Some(rotation)) = (transform.translation, transform.rotation)
+			else {
+				return;
+			};
+			let _ = saved_tx.send(Anchor {
+				name,
+				translation: translation.into(),
+				rotation: Quat::from(rotation).to_array(),
+			});
+		});
+	}
+	/// Returns whether there was an anchor with that name.
+	pub fn remove(&mut self, name: &str) -> bool {
+		let len = self.anchors.len();
+		self.anchors.retain(|anchor| anchor.name != name);
+		self.markers = None;
+		self.anchors.len() != len
+	}
+
+	/// Returns whether an anchor was saved this frame.
+	pub fn update(&mut self, holding: bool) -> bool {
+		let mut saved = false;
+		while let Ok(anchor) = self.saved_rx.try_recv() {
+			self.anchors.retain(|a| a.name != anchor.name);
+			self.anchors.push(anchor);
+			self.markers = None;
+			saved = true;
+		}
+		match (holding, self.markers.is_some()) {
+			(true, false) => {
+				self.markers = Some(
+					self.anchors
+						.iter()
+						.filter_map(|anchor| Marker::create(&self.reference, anchor))
+						.collect(),
+				)
+			}
+			(false, true) => self.markers = None,
+			_ => (),
+		}
+		saved
+	}
+}
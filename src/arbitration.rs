@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	items::panel::{PanelItem, SurfaceID},
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::Grabbable;
+
+const BTN_LEFT: u32 = 0x110;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArbitrationConfig {
+	/// Seconds a pinch may last and still count as a click
+	pub click_max_duration: f32,
+	/// Meters the panel may drift during a pinch and still count as a click
+	pub click_max_distance: f32,
+}
+impl Default for ArbitrationConfig {
+	fn default() -> Self {
+		ArbitrationConfig {
+			click_max_duration: 0.25,
+			click_max_distance: 0.01,
+		}
+	}
+}
+
+/// Lets one field serve both clicking and grabbing: quick, still pinches are undone and sent as clicks.
+pub struct GrabArbiter {
+	config: ArbitrationConfig,
+	started_at: f32,
+	start_pose: Arc<Mutex<Option<Transform>>>,
+}
+impl GrabArbiter {
+	pub fn new(config: ArbitrationConfig) -> Self {
+		GrabArbiter {
+			config,
+			started_at: 0.0,
+			start_pose: Default::default(),
+		}
+	}
+
+	pub fn update(
+		&mut self,
+		grabbable: &Grabbable,
+		panel_item: &PanelItem,
+		reference: &Spatial,
+		elapsed: f32,
+	) {
+		let grab_action = grabbable.grab_action();
+		if grab_action.actor_started() {
+			self.started_at = elapsed;
+			let content_parent = grabbable.content_parent().alias();
+			let reference = reference.alias();
+			let start_pose = self.start_pose.clone();
+			*start_pose.lock().unwrap() = None;
+			tokio::spawn(async move {
+				let pose = content_parent.get_transform(&reference).await.ok();
+				*start_pose.lock().unwrap() = pose;
+			});
+		}
+		if !grab_action.actor_stopped() {
+			return;
+		}
+		if elapsed - self.started_at > self.config.click_max_duration {
+			return;
+		}
+		let Some(start_pose) = self.start_pose.lock().unwrap().take() else {
+			return;
+		};
+		let content_parent = grabbable.content_parent().alias();
+		let reference = reference.alias();
+		let panel_item = panel_item.alias();
+		let max_distance = self.config.click_max_distance;
+		tokio::spawn(async move {
+			let Ok(end_pose) = content_parent.get_transform(&reference).await else {
+				return;
+			};
+			let (Some(start), Some(end)) = (start_pose.translation, end_pose.translation) else {
+				return;
+			};
+			let drift = glam::Vec3::from(start).distance(glam::Vec3::from(end));
+			if drift > max_distance {
+				return;
+			}
+			let _ = content_parent.set_relative_transform(&reference, start_pose);
+			let _ = panel_item.pointer_button(&SurfaceID::Toplevel, BTN_LEFT, true);
+			let _ = panel_item.pointer_button(&SurfaceID::Toplevel, BTN_LEFT, false);
+		});
+	}
+}
@@ -0,0 +1,74 @@
+use mint::Vector2;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::{rgba_linear, ResourceID},
+	drawable::{MaterialParameter, Model, ModelPartAspect},
+	node::NodeError,
+	spatial::{SpatialAspect, Transform},
+};
+
+use crate::theme::Theme;
+
+/// How far the plate reaches past the panel on every side, in meters
+const MARGIN: f32 = 0.004;
+/// Plate depth and distance behind the panel, relative to the panel's thickness
+const DEPTH: f32 = 0.2;
+const OFFSET: f32 = -0.6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackingPlateConfig {
+	/// From 0 for invisible to 1 for solid black
+	pub opacity: f32,
+	/// Themes that put a plate behind every panel, each panel can still turn its own on or off
+	pub themes: Vec<Theme>,
+}
+impl Default for BackingPlateConfig {
+	fn default() -> Self {
+		BackingPlateConfig {
+			opacity: 0.6,
+			themes: Vec::new(),
+		}
+	}
+}
+
+/// A dark plate just behind the panel, so text stays readable in front of busy passthrough.
+pub struct BackingPlate {
+	model: Model,
+}
+impl BackingPlate {
+	/// Parented to the panel model so it follows its size, and hides when the panel is captured or suspended.
+	pub fn create(
+		panel_model: &Model,
+		physical_size: Vector2<f32>,
+		opacity: f32,
+	) -> Result<Self, NodeError> {
+		let model = Model::create(
+			panel_model,
+			Self::transform(physical_size),
+			&ResourceID::new_namespaced("orbit", "panel"),
+		)?;
+		let color = MaterialParameter::Color(rgba_linear!(0.0, 0.0, 0.0, opacity.clamp(0.0, 1.0)));
+		for part in ["Face", "Edge"] {
+			model
+				.model_part(part)?
+				.set_material_parameter("color", color.clone())?;
+		}
+		Ok(BackingPlate { model })
+	}
+	fn transform(physical_size: Vector2<f32>) -> Transform {
+		Transform::from_translation_scale(
+			[0.0, 0.0, OFFSET],
+			[
+				1.0 + 2.0 * MARGIN / physical_size.x,
+				1.0 + 2.0 * MARGIN / physical_size.y,
+				DEPTH,
+			],
+		)
+	}
+	pub fn resize(&self, physical_size: Vector2<f32>) {
+		let _ = self
+			.model
+			.set_local_transform(Self::transform(physical_size));
+	}
+}
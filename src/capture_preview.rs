@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use stardust_xr_fusion::{items::panel::PanelItem, node::NodeType, spatial::SpatialAspect};
+use stardust_xr_molecules::Grabbable;
+
+/// Seconds a previewed panel can still be pulled back out of its acceptor
+const GRACE: f32 = 1.5;
+/// How far the hand has to pull away from a previewed panel to take it back, in meters
+const PULL_AWAY: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewOutcome {
+	/// Still deciding
+	Pending,
+	/// Let go of or held long enough, the acceptor keeps it
+	Keep,
+	/// Pulled away in time, back to the hand
+	Return,
+}
+
+/// A panel the acceptor captured while it was still held, so the hand can see how it's presented and take it back.
+/// The grab stays on the content parent, which keeps following the hand while the acceptor has the panel.
+#[derive(Default)]
+pub struct CapturePreview {
+	started: Option<f32>,
+	/// How far the grab is from the captured panel, measured a frame late
+	pull: Arc<Mutex<Option<f32>>>,
+}
+impl CapturePreview {
+	pub fn update(
+		&mut self,
+		grabbable: &Grabbable,
+		panel_item: &PanelItem,
+		elapsed: f32,
+	) -> PreviewOutcome {
+		let started = *self.started.get_or_insert(elapsed);
+		if !grabbable.grab_action().actor_acting() || elapsed - started > GRACE {
+			return PreviewOutcome::Keep;
+		}
+		if self
+			.pull
+			.lock()
+			.unwrap()
+			.is_some_and(|pull| pull > PULL_AWAY)
+		{
+			return PreviewOutcome::Return;
+		}
+		let pull = self.pull.clone();
+		let content_parent = grabbable.content_parent().alias();
+		let panel_item = panel_item.alias();
+		tokio::spawn(async move {
+			if let Some(translation) = content_parent
+				.get_transform(&panel_item)
+				.await
+				.ok()
+				.and_then(|t| t.translation)
+			{
+				*pull.lock().unwrap() = Some(glam::Vec3::from(translation).length());
+			}
+		});
+		PreviewOutcome::Pending
+	}
+}
@@ -0,0 +1,161 @@
+use rustc_hash::FxHashMap;
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	core::values::ResourceID,
+	drawable::{Model, ModelPart, ModelPartAspect},
+	fields::{BoxField, UnknownField},
+	items::{camera::CameraItem, environment::EnvironmentItem, Item, ItemAcceptor, ItemUIHandler},
+	node::{NodeError, NodeType},
+	spatial::{SpatialAspect, Transform},
+};
+use stardust_xr_molecules::{Grabbable, GrabbableSettings};
+
+use crate::{
+	acceptor_link::{self, proximity_color, AcceptorLink},
+	acceptor_registry::AcceptorRegistry,
+};
+
+const CARRIER_SIZE: f32 = 0.05;
+
+/// Items Orbit carries around on a grabbable block, with no UI of their own.
+pub trait Carried: Item + SpatialAspect + NodeType + Send + Sync + 'static {}
+impl Carried for EnvironmentItem {}
+impl Carried for CameraItem {}
+
+pub type EnvironmentItemUIHandler = CarrierUIHandler<EnvironmentItem>;
+pub type CameraItemUIHandler = CarrierUIHandler<CameraItem>;
+
+pub struct CarrierUIHandler<I: Carried> {
+	items: FxHashMap<String, CarrierUI<I>>,
+	acceptors: AcceptorRegistry,
+	grabbable_settings: GrabbableSettings,
+}
+impl<I: Carried> CarrierUIHandler<I> {
+	pub fn new(acceptors: AcceptorRegistry, grabbable_settings: GrabbableSettings) -> Self {
+		CarrierUIHandler {
+			items: FxHashMap::default(),
+			acceptors,
+			grabbable_settings,
+		}
+	}
+	pub fn frame(&mut self, info: &FrameInfo) {
+		for item in self.items.values_mut() {
+			item.frame(info);
+		}
+	}
+}
+impl<I: Carried> ItemUIHandler<I> for CarrierUIHandler<I> {
+	fn item_created(&mut self, uid: String, item: I, _init_data: I::InitData) {
+		let Ok(ui) = CarrierUI::new(item, self.acceptors.link(), self.grabbable_settings.clone())
+		else {
+			return;
+		};
+		self.items.insert(uid, ui);
+	}
+	fn item_captured(&mut self, uid: String, _acceptor_uid: String) {
+		if let Some(ui) = self.items.get_mut(&uid) {
+			ui.set_captured(true);
+			self.acceptors.captured::<I>();
+		}
+	}
+	fn item_released(&mut self, uid: String, _acceptor_uid: String) {
+		if let Some(ui) = self.items.get_mut(&uid) {
+			ui.set_captured(false);
+			self.acceptors.released::<I>();
+		}
+	}
+	fn item_destroyed(&mut self, uid: String) {
+		self.items.remove(&uid);
+	}
+
+	fn acceptor_created(
+		&mut self,
+		acceptor_uid: String,
+		acceptor: ItemAcceptor<I>,
+		field: UnknownField,
+	) {
+		self.acceptors
+			.acceptor_created(acceptor_uid, acceptor, field);
+	}
+	fn acceptor_destroyed(&mut self, acceptor_uid: String) {
+		self.acceptors.acceptor_destroyed::<I>(&acceptor_uid);
+	}
+}
+
+/// A small grabbable block standing in for an item so it can be carried to an acceptor.
+struct CarrierUI<I: Carried> {
+	captured: bool,
+	item: I,
+	model: Model,
+	edge: ModelPart,
+	_field: BoxField,
+	grabbable: Grabbable,
+	acceptor_link: AcceptorLink<I>,
+}
+impl<I: Carried> CarrierUI<I> {
+	fn new(
+		item: I,
+		acceptor_link: AcceptorLink<I>,
+		grabbable_settings: GrabbableSettings,
+	) -> Result<Self, NodeError> {
+		let field = BoxField::create(&item, Transform::identity(), [CARRIER_SIZE; 3])?;
+		let grabbable =
+			Grabbable::create(&item, Transform::identity(), &field, grabbable_settings)?;
+		let model = Model::create(
+			&item,
+			Transform::from_scale([CARRIER_SIZE; 3]),
+			&ResourceID::new_namespaced("orbit", "panel"),
+		)?;
+		let edge = model.model_part("Edge")?;
+		item.set_spatial_parent_in_place(grabbable.content_parent())?;
+
+		Ok(CarrierUI {
+			captured: false,
+			item,
+			model,
+			edge,
+			_field: field,
+			grabbable,
+			acceptor_link,
+		})
+	}
+	fn set_captured(&mut self, captured: bool) {
+		self.captured = captured;
+		let _ = self.model.set_enabled(!captured);
+		let _ = self.grabbable.set_enabled(!captured);
+		if captured {
+			self.acceptor_link.captured();
+			self.grabbable.cancel_linear_velocity();
+			self.grabbable.cancel_angular_velocity();
+		} else {
+			// Back where the item is, wherever the acceptor left it
+			let _ = self
+				.grabbable
+				.content_parent()
+				.set_relative_transform(&self.item, Transform::identity());
+			let _ = self.item.set_local_transform(Transform::identity());
+		}
+	}
+	fn frame(&mut self, info: &FrameInfo) {
+		if self.captured {
+			return;
+		}
+		self.grabbable.update(info).unwrap();
+		self.acceptor_link.update(
+			&self.item,
+			self.grabbable.grab_action().actor_acting(),
+			acceptor_link::released(
+				self.grabbable.grab_action().actor_acting(),
+				self.grabbable.linear_speed().is_some(),
+				self.grabbable.grab_action().actor_stopped(),
+			),
+		);
+		if !self.acceptor_link.closest().has_changed().unwrap_or(false) {
+			return;
+		}
+		let closest = self.acceptor_link.closest().borrow_and_update().clone();
+		let _ = self
+			.edge
+			.set_material_parameter("color", proximity_color(closest.as_ref()));
+	}
+}
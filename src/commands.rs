@@ -0,0 +1,82 @@
+use clap::{Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// How far a single move command nudges a panel, in meters
+pub const MOVE_STEP: f32 = 0.05;
+
+/// Window management commands meant to be bound to keys by a keyboard relay or hotkey daemon.
+#[derive(Debug, Clone, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum WmCommand {
+	/// Focus the panel opened after the focused one
+	FocusNext,
+	/// Focus the panel opened before the focused one
+	FocusPrevious,
+	/// Return focus to the panel focused before this one, repeating toggles between the two
+	FocusLast,
+	/// Nudge the focused panel one step along its own axes
+	Move {
+		direction: Direction,
+	},
+	/// Park the focused panel on the wall, or take it off again
+	ToggleTiling,
+	/// Show or hide the settings panel
+	ToggleSettings,
+	/// Show or hide the log of recent events, only those about one panel when given its uid
+	ToggleLog {
+		uid: Option<String>,
+	},
+	/// Show or hide every panel's uid and app id on its face
+	ToggleIdentityLabels,
+	/// Show or hide the map of panels and acceptors, poking a panel's dot brings it over
+	ToggleMinimap,
+	/// Draw a link from the previously focused panel to the focused one
+	Link {
+		label: Option<String>,
+	},
+	/// Remove every link to or from the focused panel
+	Unlink,
+	/// Remember where the focused panel is under a name like "above desk"
+	SaveAnchor {
+		name: String,
+	},
+	/// Send the focused panel to a saved anchor
+	SendToAnchor {
+		name: String,
+	},
+	RemoveAnchor {
+		name: String,
+	},
+	/// Magnify the focused panel's content one step, keeping the panel's size
+	ZoomIn,
+	ZoomOut,
+	ResetZoom,
+	/// Close every panel that has gone unfocused long enough to fade
+	CloseStale,
+	/// Stop acting on keyboard shortcuts for apps that need those keys, or start again
+	ToggleShortcutPassthrough,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+	Left,
+	Right,
+	Up,
+	Down,
+	Forward,
+	Back,
+}
+impl Direction {
+	/// Unit vector in panel space, panels face +Z
+	pub fn vector(self) -> [f32; 3] {
+		match self {
+			Direction::Left => [-1.0, 0.0, 0.0],
+			Direction::Right => [1.0, 0.0, 0.0],
+			Direction::Up => [0.0, 1.0, 0.0],
+			Direction::Down => [0.0, -1.0, 0.0],
+			Direction::Forward => [0.0, 0.0, -1.0],
+			Direction::Back => [0.0, 0.0, 1.0],
+		}
+	}
+}
@@ -0,0 +1,449 @@
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use stardust_xr_molecules::GrabbableSettings;
+use std::{net::SocketAddr, path::PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::{
+	acceptor_link::{CapturePolicyKind, MAX_ACCEPT_DISTANCE},
+	aging::AgingConfig,
+	ambient::AmbientConfig,
+	arbitration::ArbitrationConfig,
+	backing_plate::BackingPlateConfig,
+	desk::DeskConfig,
+	energy::EnergySaverConfig,
+	floor_marker::FloorMarkerConfig,
+	grab_smoothing::GrabSmoothingConfig,
+	hand::Hand,
+	handle_bar::GrabMode,
+	handoff::HandoffConfig,
+	hooks::HooksConfig,
+	i18n::{tr, tr_with},
+	idle::IdleConfig,
+	input::InputConfig,
+	metrics::MetricsConfig,
+	pocket::PocketConfig,
+	profile,
+	proximity_cue::ProximityCueConfig,
+	schedule::ScheduledRequest,
+	shortcuts::ShortcutsConfig,
+	speech::SpeechConfig,
+	theme::Theme,
+	title_bar::TitleBarConfig,
+	urgency::UrgencyConfig,
+	wall::WallConfig,
+};
+
+/// What to do when another shell already has the item UI for a kind of item.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompetingShell {
+	/// Don't start, showing what's wrong
+	#[default]
+	Fail,
+	/// Leave those items to the other shell and run with whatever is left
+	Yield,
+}
+impl CompetingShell {
+	/// `Ok(None)` to go on without the item UI that couldn't be registered.
+	pub fn yield_or<T, E: Into<color_eyre::Report>>(self, e: E, items: &str) -> Result<Option<T>> {
+		match self {
+			CompetingShell::Fail => Err(e.into()),
+			CompetingShell::Yield => {
+				println!("Another shell manages {items}, leaving them to it");
+				Ok(None)
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	pub hooks: HooksConfig,
+	/// Defaults to `placement.rhai` next to the config file
+	pub placement_script: Option<PathBuf>,
+	/// Names of decorators added to every panel, in order
+	pub decorators: Vec<String>,
+	pub title_bar: TitleBarConfig,
+	pub capture_policy: CapturePolicyKind,
+	/// How close a released panel has to be to an acceptor to be captured, in meters
+	pub accept_distance: f32,
+	/// Holding a panel in range for a moment shows it in the acceptor, pulling away right after takes it back
+	pub capture_preview: bool,
+	/// Sound played from a panel an acceptor didn't take when asked to
+	pub rejection_sound: Option<PathBuf>,
+	/// Physical width of every panel, in meters
+	pub panel_width: f32,
+	/// App IDs covered by the privacy shutter while streaming mode is on
+	pub streaming_hidden_apps: Vec<String>,
+	/// App IDs shown as just their surface with no edge around it, like video players
+	pub frameless_apps: Vec<String>,
+	pub grab_mode: GrabMode,
+	/// Deadzones and activation distances for touching panels
+	pub input: InputConfig,
+	/// Physical mouse control of the focused panel, for sitting at a real desk
+	pub desk: DeskConfig,
+	/// Window management bound to keys on a physical keyboard
+	pub shortcuts: ShortcutsConfig,
+	pub arbitration: ArbitrationConfig,
+	pub wall: Option<WallConfig>,
+	/// A pocket on the dominant hip, panels let go in it shrink onto a belt that follows you around
+	pub pocket: Option<PocketConfig>,
+	pub urgency: UrgencyConfig,
+	/// Adds a ring around each panel that turns it in 15° steps
+	pub rotation_ring: bool,
+	/// Holding a grabbed panel still for a moment locks it to its normal or its plane
+	pub grab_rails: bool,
+	/// Filters hand jitter out of carried panels, trading a little lag for readable text
+	pub grab_smoothing: Option<GrabSmoothingConfig>,
+	pub energy_saver: EnergySaverConfig,
+	pub idle: IdleConfig,
+	pub proximity_cue: ProximityCueConfig,
+	/// Hand a reopened window back to the acceptor that held it when it closed
+	pub recapture: bool,
+	pub competing_shell: CompetingShell,
+	pub metrics: MetricsConfig,
+	/// Taking layouts handed off from Orbit on another machine
+	pub handoff: HandoffConfig,
+	/// Language for in-world text like `de` or `pt_BR`, the system locale when unset
+	pub locale: Option<String>,
+	pub theme: Theme,
+	/// A binary glTF with `Face` and `Edge` parts to draw panels with instead of the built in one,
+	/// for styles like rounded corners or a wider bezel
+	pub frame_model: Option<PathBuf>,
+	/// Dims panel frames when the room is dark
+	pub ambient: AmbientConfig,
+	pub backing_plate: BackingPlateConfig,
+	/// A soft shadow on the floor under each panel, for judging how far away it is
+	pub floor_marker: FloorMarkerConfig,
+	/// Spoken confirmation of window actions through a text to speech command
+	pub speech: SpeechConfig,
+	/// Panels stop when let go instead of drifting, and the wall snaps instead of sliding
+	pub reduce_motion: bool,
+	/// Turn panels around their vertical axis to face the head when let go
+	pub face_on_release: bool,
+	/// Round the yaw of let go panels to 45° steps from where the head faces, so they line up in tidy facets
+	pub snap_yaw_on_release: bool,
+	/// Show every panel's hovered surface pixel and last forwarded input, for debugging input mapping
+	pub dev_overlay: bool,
+	/// Write each panel's uid and app id on its face, for naming panels in bug reports and IPC commands
+	pub identity_labels: bool,
+	/// Tint each panel's idle edge with the main color of its app's icon
+	pub icon_accent: bool,
+	/// Fades the edges of panels that haven't been focused in a long time
+	pub aging: AgingConfig,
+	/// Side the privacy button and the vertical scroll strip go on
+	pub dominant_hand: Hand,
+	/// Eye height layouts and placements are designed for, in meters above the client root
+	pub standing_eye_height: f32,
+	/// Requests Orbit sends itself daily, like hiding entertainment apps at 09:00
+	pub schedule: Vec<ScheduledRequest>,
+}
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			hooks: HooksConfig::default(),
+			placement_script: None,
+			decorators: vec!["title_bar".to_string()],
+			title_bar: TitleBarConfig::default(),
+			capture_policy: CapturePolicyKind::default(),
+			accept_distance: MAX_ACCEPT_DISTANCE,
+			capture_preview: false,
+			rejection_sound: None,
+			panel_width: 0.1,
+			streaming_hidden_apps: Vec::new(),
+			frameless_apps: Vec::new(),
+			grab_mode: GrabMode::default(),
+			input: InputConfig::default(),
+			desk: DeskConfig::default(),
+			shortcuts: ShortcutsConfig::default(),
+			arbitration: ArbitrationConfig::default(),
+			wall: None,
+			pocket: None,
+			urgency: UrgencyConfig::default(),
+			rotation_ring: false,
+			grab_rails: false,
+			grab_smoothing: None,
+			energy_saver: EnergySaverConfig::default(),
+			idle: IdleConfig::default(),
+			proximity_cue: ProximityCueConfig::default(),
+			recapture: false,
+			competing_shell: CompetingShell::default(),
+			metrics: MetricsConfig::default(),
+			handoff: HandoffConfig::default(),
+			locale: None,
+			theme: Theme::default(),
+			frame_model: None,
+			ambient: AmbientConfig::default(),
+			backing_plate: BackingPlateConfig::default(),
+			floor_marker: FloorMarkerConfig::default(),
+			speech: SpeechConfig::default(),
+			reduce_motion: false,
+			face_on_release: false,
+			snap_yaw_on_release: false,
+			dev_overlay: false,
+			identity_labels: false,
+			icon_accent: false,
+			aging: AgingConfig::default(),
+			dominant_hand: Hand::default(),
+			standing_eye_height: 1.65,
+			schedule: Vec::new(),
+		}
+	}
+}
+
+/// Dotted paths of keys in `raw` with nothing to go to in `known`, the config serialized back.
+fn unknown_keys(raw: &toml::Value, known: &toml::Value, path: &str, unknown: &mut Vec<String>) {
+	match (raw, known) {
+		(toml::Value::Table(raw), toml::Value::Table(known)) => {
+			for (key, value) in raw {
+				let key_path = match path {
+					"" => key.clone(),
+					path => format!("{path}.{key}"),
+				};
+				match known.get(key) {
+					Some(known) => unknown_keys(value, known, &key_path, unknown),
+					None => unknown.push(key_path),
+				}
+			}
+		}
+		(toml::Value::Array(raw), toml::Value::Array(known)) => {
+			for (index, (raw, known)) in raw.iter().zip(known).enumerate() {
+				unknown_keys(raw, known, &format!("{path}[{index}]"), unknown);
+			}
+		}
+		_ => (),
+	}
+}
+
+impl Config {
+	pub fn dir() -> Option<PathBuf> {
+		Some(profile::dir(dirs::config_dir()?))
+	}
+	pub fn path() -> Option<PathBuf> {
+		Some(Self::dir()?.join("config.toml"))
+	}
+	pub fn placement_script_path(&self) -> Option<PathBuf> {
+		self.placement_script
+			.clone()
+			.or_else(|| Some(Self::dir()?.join("placement.rhai")))
+	}
+	/// Settings for everything the user throws around, without momentum when reducing motion.
+	pub fn grabbable_settings(&self) -> GrabbableSettings {
+		if self.reduce_motion {
+			GrabbableSettings {
+				linear_momentum: None,
+				angular_momentum: None,
+				..Default::default()
+			}
+		} else {
+			GrabbableSettings::default()
+		}
+	}
+	/// Missing config files fall back to defaults, broken ones are an error.
+	pub fn load() -> Result<Self> {
+		let Some(path) = Self::path() else {
+			return Ok(Config::default());
+		};
+		let Ok(file) = std::fs::read_to_string(&path) else {
+			return Ok(Config::default());
+		};
+		toml::from_str(&file).wrap_err_with(|| format!("Invalid config at {}", path.display()))
+	}
+	/// Everything that parsed but can't be meant that way, from misspelled keys to impossible sizes.
+	pub fn validate(&self) -> Vec<String> {
+		let mut problems = Vec::new();
+		let file = Self::path().and_then(|path| std::fs::read_to_string(path).ok());
+		let raw = file.and_then(|file| toml::from_str::<toml::Value>(&file).ok());
+		if let (Some(raw), Ok(known)) = (raw, toml::Value::try_from(self)) {
+			let mut unknown = Vec::new();
+			unknown_keys(&raw, &known, "", &mut unknown);
+			problems.extend(
+				unknown
+					.iter()
+					.map(|key| tr_with("config-unknown-key", &[("key", key)])),
+			);
+		}
+
+		let mut positive = |key: &str, value: f32| {
+			if value.is_nan() || value <= 0.0 {
+				problems.push(tr_with(
+					"config-not-positive",
+					&[("key", &key), ("value", &value)],
+				));
+			}
+		};
+		positive("panel_width", self.panel_width);
+		positive("accept_distance", self.accept_distance);
+		positive("standing_eye_height", self.standing_eye_height);
+		positive("energy_saver.radius", self.energy_saver.radius);
+		positive("proximity_cue.reach", self.proximity_cue.reach);
+		positive("input.scroll.speed", self.input.scroll.speed);
+		positive(
+			"arbitration.click_max_duration",
+			self.arbitration.click_max_duration,
+		);
+		positive(
+			"arbitration.click_max_distance",
+			self.arbitration.click_max_distance,
+		);
+		if let Some(grab_smoothing) = &self.grab_smoothing {
+			positive("grab_smoothing.min_cutoff", grab_smoothing.min_cutoff);
+		}
+		positive("aging.brightness", self.aging.brightness);
+		if let Some(after) = self.aging.after {
+			positive("aging.after", after);
+		}
+		if let Some(timeout) = self.idle.timeout {
+			positive("idle.timeout", timeout);
+		}
+		if let Some(pocket) = &self.pocket {
+			positive("pocket.drop", pocket.drop);
+			positive("pocket.reach", pocket.reach);
+		}
+		if let Some(wall) = &self.wall {
+			positive("wall.width", wall.width);
+			positive("wall.height", wall.height);
+			for (key, value) in [
+				("wall.tiling.gap", wall.tiling.gap),
+				("wall.tiling.margin", wall.tiling.margin),
+			] {
+				if value.is_nan() || value < 0.0 {
+					problems.push(tr_with(
+						"config-negative",
+						&[("key", &key), ("value", &value)],
+					));
+				}
+			}
+			let master_ratio = wall.tiling.master_ratio;
+			if !(master_ratio > 0.0 && master_ratio < 1.0) {
+				problems.push(tr_with("config-master-ratio", &[("value", &master_ratio)]));
+			}
+		}
+
+		if self.speech.enabled && self.speech.command.is_empty() {
+			problems.push(tr("config-speech-command"));
+		}
+		if let Some(listen) = &self.metrics.listen {
+			if listen.parse::<SocketAddr>().is_err() {
+				problems.push(tr_with("config-metrics-listen", &[("listen", listen)]));
+			}
+		}
+		if let Some(listen) = &self.handoff.listen {
+			if listen.parse::<SocketAddr>().is_err() {
+				problems.push(tr_with("config-handoff-listen", &[("listen", listen)]));
+			}
+			if self.handoff.token.as_deref().map_or(true, str::is_empty) {
+				problems.push(tr("config-handoff-token"));
+			}
+		}
+		if let Some(path) = &self.frame_model {
+			if !path.is_absolute() {
+				problems.push(tr_with("config-frame-model", &[("path", &path.display())]));
+			}
+		}
+		for (index, decorator) in self.decorators.iter().enumerate() {
+			if self.decorators[..index].contains(decorator) {
+				problems.push(tr_with(
+					"config-duplicate-decorator",
+					&[("decorator", decorator)],
+				));
+			}
+		}
+		problems.extend(self.shortcuts.problems());
+		if let Err(e) = self.ambient.times() {
+			problems.push(e);
+		}
+		for entry in &self.schedule {
+			if let Err(e) = entry.time() {
+				problems.push(format!("{e:#}"));
+			}
+		}
+		problems
+	}
+	/// Only the settings that changed are written, the rest of a hand-edited file stays as it was.
+	pub fn save(&self) -> Result<()> {
+		let path = Self::path().ok_or_else(|| eyre!("No config directory"))?;
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		let file = std::fs::read_to_string(&path).unwrap_or_default();
+		std::fs::write(&path, self.edit(&file)?)
+			.wrap_err_with(|| format!("Couldn't write config to {}", path.display()))
+	}
+	/// `file` with every setting that differs from what it says changed to this config's,
+	/// keeping comments, formatting and the order of everything else.
+	fn edit(&self, file: &str) -> Result<String> {
+		let mut document = file.parse::<DocumentMut>()?;
+		let saved = toml::Value::try_from(toml::from_str::<Config>(file)?)?;
+		let current = toml::Value::try_from(self)?;
+		if let (toml::Value::Table(saved), toml::Value::Table(current)) = (saved, current) {
+			edit_table(document.as_table_mut(), &saved, &current)?;
+		}
+		Ok(document.to_string())
+	}
+}
+
+/// Brings `table` from `saved` to `current`, leaving keys that are the same in both alone.
+fn edit_table(table: &mut dyn TableLike, saved: &toml::Table, current: &toml::Table) -> Result<()> {
+	for key in saved.keys().filter(|key| !current.contains_key(*key)) {
+		table.remove(key);
+	}
+	for (key, value) in current {
+		let saved = saved.get(key);
+		if saved == Some(value) {
+			continue;
+		}
+		if let (toml::Value::Table(current), Some(toml::Value::Table(saved))) = (value, saved) {
+			if let Some(table) = table.get_mut(key).and_then(Item::as_table_like_mut) {
+				edit_table(table, saved, current)?;
+				continue;
+			}
+		}
+		let mut item = toml_item(key, value)?;
+		// Swapped in place, since inserting over a key would reset the comments before it
+		if let (Some(Item::Value(old)), Item::Value(new)) = (table.get_mut(key), &mut item) {
+			*new.decor_mut() = old.decor().clone();
+			std::mem::swap(old, new);
+			continue;
+		}
+		table.insert(key, item);
+	}
+	Ok(())
+}
+/// The item `toml` would write for `key = value`, as a section when it's a table.
+fn toml_item(key: &str, value: &toml::Value) -> Result<Item> {
+	let table = toml::Table::from_iter([(key.to_string(), value.clone())]);
+	let mut document = toml::to_string(&table)?.parse::<DocumentMut>()?;
+	Ok(document.remove(key).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn saving_keeps_comments_and_untouched_settings() {
+		let file =
+			"# tuned by hand\npanel_width = 0.4 # wide\n\n[idle]\n# a minute\ntimeout = 60.0\n";
+		let mut config = toml::from_str::<Config>(file).unwrap();
+		config.panel_width = 0.5;
+		assert_eq!(
+			config.edit(file).unwrap(),
+			"# tuned by hand\npanel_width = 0.5 # wide\n\n[idle]\n# a minute\ntimeout = 60.0\n"
+		);
+	}
+
+	#[test]
+	fn saving_adds_and_removes_only_what_changed() {
+		let file = "[idle]\ntimeout = 60.0\n";
+		let mut config = toml::from_str::<Config>(file).unwrap();
+		config.idle.timeout = None;
+		config.reduce_motion = !config.reduce_motion;
+		let edited = config.edit(file).unwrap();
+		assert!(!edited.contains("timeout"));
+		assert!(edited.contains(&format!("reduce_motion = {}", config.reduce_motion)));
+		assert_eq!(toml::from_str::<toml::Table>(&edited).unwrap().len(), 2);
+	}
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::TAU;
+
+use mint::Vector3;
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	node::{NodeError, NodeType},
+	spatial::{SpatialAspect, Transform},
+};
+
+const CURSOR_RADIUS: f32 = 0.0015;
+const CURSOR_THICKNESS: f32 = 0.0005;
+const CURSOR_SEGMENTS: usize = 12;
+
+/// A small ring on the panel face marking where input will land.
+pub struct HoverCursor {
+	lines: Lines,
+	visible: bool,
+}
+impl HoverCursor {
+	pub fn create(parent: &impl SpatialAspect) -> Result<Self, NodeError> {
+		let points = (0..CURSOR_SEGMENTS)
+			.map(|i| {
+				let angle = i as f32 / CURSOR_SEGMENTS as f32 * TAU;
+				LinePoint {
+					point: [
+						angle.cos() * CURSOR_RADIUS,
+						angle.sin() * CURSOR_RADIUS,
+						0.0,
+					]
+					.into(),
+					thickness: CURSOR_THICKNESS,
+					color: rgba_linear!(1.0, 1.0, 1.0, 1.0),
+				}
+			})
+			.collect();
+		let lines = Lines::create(
+			parent,
+			Transform::identity(),
+			&[Line {
+				points,
+				cyclic: true,
+			}],
+		)?;
+		lines.set_enabled(false)?;
+		Ok(HoverCursor {
+			lines,
+			visible: false,
+		})
+	}
+
+	pub fn show_at(&mut self, position: Vector3<f32>) {
+		let _ = self
+			.lines
+			.set_local_transform(Transform::from_translation(position));
+		self.set_visible(true);
+	}
+	pub fn hide(&mut self) {
+		self.set_visible(false);
+	}
+	fn set_visible(&mut self, visible: bool) {
+		if self.visible != visible {
+			self.visible = visible;
+			let _ = self.lines.set_enabled(visible);
+		}
+	}
+}
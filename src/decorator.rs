@@ -0,0 +1,101 @@
+use mint::Vector2;
+use rustc_hash::FxHashMap;
+use stardust_xr_fusion::{
+	client::FrameInfo, drawable::Model, items::panel::PanelItem, node::NodeType,
+	spatial::SpatialRef,
+};
+
+use crate::{
+	frame_rate::FrameRate,
+	measurements::Measurements,
+	theme::Theme,
+	title_bar::{TitleBar, TitleBarConfig},
+};
+
+/// Everything a decorator may look at or attach nodes to.
+pub struct DecoratorContext<'a> {
+	pub uid: &'a str,
+	pub app_id: Option<&'a str>,
+	pub title: Option<&'a str>,
+	/// Unread count set from outside, 0 when there's nothing to show
+	pub badge: u32,
+	/// Moves with the panel, not scaled
+	pub panel_item: &'a PanelItem,
+	pub model: &'a Model,
+	/// Face size in meters
+	pub size: Vector2<f32>,
+	pub pixel_size: Vector2<u32>,
+	pub focused: bool,
+	/// Something is pointing at or touching the face
+	pub hovered: bool,
+	pub theme: Theme,
+}
+
+/// Optional per-panel features layered on top of the base panel.
+pub trait Decorator: Send + Sync {
+	fn created(&mut self, _ctx: &DecoratorContext) {}
+	fn resized(&mut self, _ctx: &DecoratorContext) {}
+	fn focus_changed(&mut self, _ctx: &DecoratorContext) {}
+	fn frame(&mut self, _ctx: &DecoratorContext, _info: &FrameInfo) {}
+}
+
+pub type DecoratorFactory = Box<dyn Fn() -> Box<dyn Decorator> + Send + Sync>;
+
+/// Decorators by the name the config enables them with, extended through [`crate::start`].
+#[derive(Default)]
+pub struct DecoratorRegistry {
+	factories: FxHashMap<String, DecoratorFactory>,
+}
+impl DecoratorRegistry {
+	pub fn with_builtins(hmd: &SpatialRef, title_bar: &TitleBarConfig) -> Self {
+		let mut registry = DecoratorRegistry::default();
+		let title_bar = title_bar.clone();
+		registry.register("title_bar", move || {
+			Box::new(TitleBar::new(title_bar.clone()))
+		});
+		registry.register("frame_rate", || Box::<FrameRate>::default());
+		let hmd = hmd.alias();
+		registry.register("measurements", move || {
+			Box::new(Measurements::new(hmd.alias()))
+		});
+		registry
+	}
+	pub fn register<F: Fn() -> Box<dyn Decorator> + Send + Sync + 'static>(
+		&mut self,
+		name: &str,
+		factory: F,
+	) {
+		self.factories.insert(name.to_string(), Box::new(factory));
+	}
+	/// Unknown names are skipped so a stale config doesn't stop panels from showing up.
+	pub fn instantiate(&self, enabled: &[String]) -> Vec<Box<dyn Decorator>> {
+		enabled
+			.iter()
+			.filter_map(|name| {
+				let factory = self.factories.get(name);
+				if factory.is_none() {
+					eprintln!("Unknown decorator {name}");
+				}
+				factory
+			})
+			.map(|factory| factory())
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Blank;
+	impl Decorator for Blank {}
+
+	#[test]
+	fn registered_decorators_are_made_in_config_order() {
+		let mut registry = DecoratorRegistry::default();
+		registry.register("blank", || Box::new(Blank));
+		assert_eq!(registry.instantiate(&[]).len(), 0);
+		let enabled = ["blank", "missing", "blank"].map(str::to_string);
+		assert_eq!(registry.instantiate(&enabled).len(), 2);
+	}
+}
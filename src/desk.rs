@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeskConfig {
+	/// Evdev device of a physical mouse, like `/dev/input/by-id/usb-…-event-mouse`, driving a cursor on the focused panel
+	pub mouse: Option<PathBuf>,
+	/// Surface pixels per mouse count
+	pub sensitivity: f32,
+}
+impl Default for DeskConfig {
+	fn default() -> Self {
+		DeskConfig {
+			mouse: None,
+			sensitivity: 1.0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseEvent {
+	/// Relative movement in mouse counts
+	Motion { dx: f32, dy: f32 },
+	/// Linux button code, which is what toplevels get too
+	Button { button: u32, pressed: bool },
+	/// Wheel steps
+	Scroll { dx: f32, dy: f32 },
+}
+
+/// Read the configured mouse, `None` when there isn't one or Orbit was built without evdev.
+pub fn spawn(config: &DeskConfig) -> Option<mpsc::UnboundedReceiver<MouseEvent>> {
+	let path = config.mouse.clone()?;
+	let (tx, rx) = mpsc::unbounded_channel();
+	#[cfg(feature = "evdev")]
+	tokio::spawn(async move {
+		if let Err(e) = evdev_mouse::read(&path, tx).await {
+			eprintln!("Couldn't read mouse at {}: {e}", path.display());
+		}
+	});
+	#[cfg(not(feature = "evdev"))]
+	{
+		drop(tx);
+		eprintln!(
+			"Orbit was built without the evdev feature, ignoring mouse {}",
+			path.display()
+		);
+	}
+	Some(rx)
+}
+
+#[cfg(feature = "evdev")]
+mod evdev_mouse {
+	use std::path::Path;
+
+	use evdev::{Device, InputEventKind, RelativeAxisType};
+	use tokio::sync::mpsc;
+
+	use super::MouseEvent;
+
+	pub async fn read(path: &Path, tx: mpsc::UnboundedSender<MouseEvent>) -> std::io::Result<()> {
+		let mut events = Device::open(path)?.into_event_stream()?;
+		loop {
+			let event = events.next_event().await?;
+			let value = event.value();
+			let mouse_event = match event.kind() {
+				InputEventKind::RelAxis(RelativeAxisType::REL_X) => MouseEvent::Motion {
+					dx: value as f32,
+					dy: 0.0,
+				},
+				InputEventKind::RelAxis(RelativeAxisType::REL_Y) => MouseEvent::Motion {
+					dx: 0.0,
+					dy: value as f32,
+				},
+				// wheel up is positive on evdev and negative for toplevels
+				InputEventKind::RelAxis(RelativeAxisType::REL_WHEEL) => MouseEvent::Scroll {
+					dx: 0.0,
+					dy: -value as f32,
+				},
+				InputEventKind::RelAxis(RelativeAxisType::REL_HWHEEL) => MouseEvent::Scroll {
+					dx: value as f32,
+					dy: 0.0,
+				},
+				InputEventKind::Key(key) if (0x110..0x118).contains(&key.code()) && value != 2 => {
+					MouseEvent::Button {
+						button: key.code() as u32,
+						pressed: value == 1,
+					}
+				}
+				_ => continue,
+			};
+			if tx.send(mouse_event).is_err() {
+				return Ok(());
+			}
+		}
+	}
+}
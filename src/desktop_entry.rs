@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+/// Icon sizes to look for, largest first since they give the truest colors
+const ICON_SIZES: &[&str] = &["256x256", "128x128", "96x96", "64x64", "48x48"];
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`.
+fn data_dirs() -> Vec<PathBuf> {
+	let data_dirs = std::env::var("XDG_DATA_DIRS")
+		.unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+	dirs::data_dir()
+		.into_iter()
+		.chain(std::env::split_paths(&data_dirs))
+		.collect()
+}
+
+/// The `[Desktop Entry]` group of an app's `.desktop` file.
+pub struct DesktopEntry {
+	keys: Vec<(String, String)>,
+}
+impl DesktopEntry {
+	fn parse(file: &str) -> Self {
+		let keys = file
+			.lines()
+			.skip_while(|line| line.trim() != "[Desktop Entry]")
+			.skip(1)
+			.take_while(|line| !line.starts_with('['))
+			.filter_map(|line| line.split_once('='))
+			.map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+			.collect();
+		DesktopEntry { keys }
+	}
+
+	/// Found by file name or `StartupWMClass`, the two ways toolkits tie a window to its entry.
+	/// App ids come from clients, so ones that could name a file elsewhere are never looked up by name.
+	pub fn find(app_id: &str) -> Option<Self> {
+		if app_id.is_empty() || app_id.starts_with('.') || app_id.contains(['/', '\\', '\0']) {
+			return None;
+		}
+		let dirs = data_dirs()
+			.into_iter()
+			.map(|dir| dir.join("applications"))
+			.collect::<Vec<_>>();
+		for dir in &dirs {
+			if let Ok(file) = std::fs::read_to_string(dir.join(format!("{app_id}.desktop"))) {
+				return Some(Self::parse(&file));
+			}
+		}
+		dirs.iter()
+			.filter_map(|dir| std::fs::read_dir(dir).ok())
+			.flatten()
+			.flatten()
+			.filter_map(|file| std::fs::read_to_string(file.path()).ok())
+			.map(|file| Self::parse(&file))
+			.find(|entry| entry.get("StartupWMClass") == Some(app_id))
+	}
+
+	pub fn get(&self, key: &str) -> Option<&str> {
+		self.keys
+			.iter()
+			.find(|(k, _)| k == key)
+			.map(|(_, value)| value.as_str())
+	}
+
+	/// The `Exec` line as program and arguments, unquoted as the desktop entry spec says and
+	/// without the files and URLs to open. `None` when missing or badly quoted.
+	pub fn exec(&self) -> Option<Vec<String>> {
+		let exec = unescape(self.get("Exec")?);
+		let mut args = Vec::new();
+		let mut arg = String::new();
+		// Set once the argument has anything, even just empty quotes
+		let mut started = false;
+		let mut quoted = false;
+		let mut chars = exec.chars().peekable();
+		while let Some(c) = chars.next() {
+			match c {
+				'"' => {
+					quoted = !quoted;
+					started = true;
+				}
+				'\\' if quoted => match chars.peek() {
+					Some(&escaped @ ('"' | '`' | '$' | '\\')) => {
+						chars.next();
+						arg.push(escaped);
+					}
+					_ => arg.push(c),
+				},
+				c if c.is_whitespace() && !quoted => {
+					if std::mem::take(&mut started) {
+						args.push(std::mem::take(&mut arg));
+					}
+				}
+				// Field codes only count outside quotes
+				'%' if !quoted => match chars.next()? {
+					'%' => {
+						arg.push('%');
+						started = true;
+					}
+					'i' if !started && chars.peek().map_or(true, |c| c.is_whitespace()) => {
+						if let Some(icon) = self.get("Icon") {
+							args.push("--icon".to_string());
+							arg.push_str(icon);
+							started = true;
+						}
+					}
+					'c' => {
+						arg.push_str(self.get("Name").unwrap_or_default());
+						started = true;
+					}
+					_ => (),
+				},
+				c => {
+					arg.push(c);
+					started = true;
+				}
+			}
+		}
+		if quoted {
+			return None;
+		}
+		if started {
+			args.push(arg);
+		}
+		(!args.is_empty()).then_some(args)
+	}
+
+	/// A PNG of the entry's icon from the hicolor theme or pixmaps, SVG-only icons aren't found.
+	pub fn icon_path(&self) -> Option<PathBuf> {
+		let icon = self.get("Icon")?;
+		if Path::new(icon).is_absolute() {
+			return Some(PathBuf::from(icon));
+		}
+		let dirs = data_dirs();
+		let themed = ICON_SIZES.iter().flat_map(|size| {
+			dirs.iter()
+				.map(move |dir| dir.join(format!("icons/hicolor/{size}/apps/{icon}.png")))
+		});
+		let pixmaps = dirs
+			.iter()
+			.map(|dir| dir.join(format!("pixmaps/{icon}.png")));
+		themed.chain(pixmaps).find(|path| path.exists())
+	}
+}
+
+/// The escapes every desktop entry string value can have.
+fn unescape(value: &str) -> String {
+	let mut unescaped = String::with_capacity(value.len());
+	let mut chars = value.chars();
+	while let Some(c) = chars.next() {
+		if c != '\\' {
+			unescaped.push(c);
+			continue;
+		}
+		match chars.next() {
+			Some('s') => unescaped.push(' '),
+			Some('n') => unescaped.push('\n'),
+			Some('t') => unescaped.push('\t'),
+			Some('r') => unescaped.push('\r'),
+			Some('\\') => unescaped.push('\\'),
+			Some(c) => {
+				unescaped.push('\\');
+				unescaped.push(c);
+			}
+			None => unescaped.push('\\'),
+		}
+	}
+	unescaped
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn exec(line: &str) -> Option<Vec<String>> {
+		DesktopEntry::parse(&format!(
+			"[Desktop Entry]\nName=Editor\nIcon=editor\nExec={line}\n"
+		))
+		.exec()
+	}
+
+	#[test]
+	fn exec_splits_on_whitespace() {
+		assert_eq!(
+			exec("editor  --new-window"),
+			Some(vec!["editor".into(), "--new-window".into()])
+		);
+	}
+
+	#[test]
+	fn exec_keeps_quoted_arguments_whole() {
+		assert_eq!(
+			exec(r#"sh -c "echo \\$HOME; ls" """#),
+			Some(vec![
+				"sh".into(),
+				"-c".into(),
+				"echo $HOME; ls".into(),
+				"".into()
+			])
+		);
+	}
+
+	#[test]
+	fn exec_drops_file_and_url_codes() {
+		assert_eq!(
+			exec("editor %F --url=%u %%d"),
+			Some(vec!["editor".into(), "--url=".into(), "%d".into()])
+		);
+	}
+
+	#[test]
+	fn exec_expands_icon_and_name() {
+		assert_eq!(
+			exec("editor %i --title=%c"),
+			Some(vec![
+				"editor".into(),
+				"--icon".into(),
+				"editor".into(),
+				"--title=Editor".into()
+			])
+		);
+	}
+
+	#[test]
+	fn exec_leaves_codes_in_quotes_alone() {
+		assert_eq!(
+			exec(r#"printf "%f""#),
+			Some(vec!["printf".into(), "%f".into()])
+		);
+	}
+
+	#[test]
+	fn exec_rejects_bad_quoting() {
+		assert_eq!(exec(r#"editor "unterminated"#), None);
+		assert_eq!(exec("%f"), None);
+	}
+
+	#[test]
+	fn find_never_looks_outside_applications() {
+		for app_id in ["../../../tmp/x", "/tmp/x", ".hidden", "", "a\\b"] {
+			assert!(DesktopEntry::find(app_id).is_none(), "{app_id:?}");
+		}
+	}
+}
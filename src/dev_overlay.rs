@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use mint::Vector2;
+use stardust_xr_fusion::{
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeError,
+	spatial::{SpatialAspect, Transform},
+};
+
+const TEXT_HEIGHT: f32 = 0.004;
+/// Gap between the panel's bottom edge and the first line
+const MARGIN: f32 = 0.006;
+/// Forwarded events kept for the overlay, oldest dropped first
+const LOG_LENGTH: usize = 6;
+/// How far in front of the face identity labels float, so the surface doesn't cover them
+const LABEL_LIFT: f32 = 0.001;
+
+/// The last few events sent to a toplevel, only kept while someone is looking at them.
+#[derive(Debug, Default)]
+pub struct InputLog(Option<VecDeque<String>>);
+impl InputLog {
+	pub fn set_enabled(&mut self, enabled: bool) {
+		if enabled != self.0.is_some() {
+			self.0 = enabled.then(VecDeque::new);
+		}
+	}
+	/// The event is only formatted when logging, so this is free otherwise.
+	pub fn push(&mut self, event: impl FnOnce() -> String) {
+		let Some(log) = &mut self.0 else {
+			return;
+		};
+		if log.len() == LOG_LENGTH {
+			log.pop_front();
+		}
+		log.push_back(event());
+	}
+	pub fn lines(&self) -> impl Iterator<Item = &str> {
+		self.0.iter().flatten().map(String::as_str)
+	}
+}
+
+/// Text under a panel showing which surface pixel is hovered and what the toplevel was last sent.
+pub struct DevOverlay {
+	text: Text,
+	shown: String,
+}
+impl DevOverlay {
+	pub fn create(
+		parent: &impl SpatialAspect,
+		physical_size: Vector2<f32>,
+	) -> Result<Self, NodeError> {
+		let text = Text::create(
+			parent,
+			Self::transform(physical_size),
+			"",
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)?;
+		Ok(DevOverlay {
+			text,
+			shown: String::new(),
+		})
+	}
+	fn transform(physical_size: Vector2<f32>) -> Transform {
+		Transform::from_translation([0.0, -physical_size.y / 2.0 - MARGIN, 0.0])
+	}
+	pub fn resize(&self, physical_size: Vector2<f32>) {
+		let _ = self
+			.text
+			.set_local_transform(Self::transform(physical_size));
+	}
+	pub fn update(&mut self, hovered: Option<Vector2<f32>>, log: &InputLog) {
+		let mut shown = match hovered {
+			Some(position) => format!("{:.0}, {:.0}", position.x, position.y),
+			None => "-".to_string(),
+		};
+		for line in log.lines() {
+			shown.push('\n');
+			shown.push_str(line);
+		}
+		if shown != self.shown {
+			let _ = self.text.set_text(&shown);
+			self.shown = shown;
+		}
+	}
+}
+
+/// The panel's item uid and app id in small text along the top of its face,
+/// so panels seen in-headset can be named exactly in bug reports and IPC commands.
+pub struct IdentityLabel {
+	text: Text,
+	uid: String,
+}
+impl IdentityLabel {
+	pub fn create(
+		parent: &impl SpatialAspect,
+		physical_size: Vector2<f32>,
+		uid: &str,
+		app_id: Option<&str>,
+	) -> Result<Self, NodeError> {
+		let text = Text::create(
+			parent,
+			Self::transform(physical_size),
+			&Self::label(uid, app_id),
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)?;
+		Ok(IdentityLabel {
+			text,
+			uid: uid.to_string(),
+		})
+	}
+	fn label(uid: &str, app_id: Option<&str>) -> String {
+		format!("{uid}\n{}", app_id.unwrap_or("-"))
+	}
+	fn transform(physical_size: Vector2<f32>) -> Transform {
+		Transform::from_translation([0.0, physical_size.y / 2.0 - MARGIN, LABEL_LIFT])
+	}
+	pub fn resize(&self, physical_size: Vector2<f32>) {
+		let _ = self
+			.text
+			.set_local_transform(Self::transform(physical_size));
+	}
+	pub fn set_app_id(&self, app_id: &str) {
+		let _ = self.text.set_text(&Self::label(&self.uid, Some(app_id)));
+	}
+}
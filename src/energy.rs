@@ -0,0 +1,133 @@
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, SpatialRef},
+};
+use tokio::sync::mpsc;
+
+use crate::event_log;
+
+/// Seconds between power source checks and distance sweeps
+const CHECK_INTERVAL: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergySaverMode {
+	#[default]
+	Never,
+	/// Whenever a battery reports it's discharging
+	OnBattery,
+	Always,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnergySaverConfig {
+	pub mode: EnergySaverMode,
+	/// Panels farther than this from the head get suspended, in meters
+	pub radius: f32,
+}
+impl Default for EnergySaverConfig {
+	fn default() -> Self {
+		EnergySaverConfig {
+			mode: EnergySaverMode::default(),
+			radius: 2.0,
+		}
+	}
+}
+
+/// Whether any battery under `/sys/class/power_supply` is discharging.
+fn on_battery() -> bool {
+	let Ok(supplies) = std::fs::read_dir("/sys/class/power_supply") else {
+		return false;
+	};
+	supplies.flatten().any(|supply| {
+		std::fs::read_to_string(supply.path().join("status"))
+			.is_ok_and(|status| status.trim() == "Discharging")
+	})
+}
+
+/// Tracks which panels are far enough away to suspend while saving energy.
+pub struct EnergySaver {
+	config: EnergySaverConfig,
+	hmd: SpatialRef,
+	active: bool,
+	last_check: f32,
+	far: FxHashSet<String>,
+	far_tx: mpsc::UnboundedSender<(String, bool)>,
+	far_rx: mpsc::UnboundedReceiver<(String, bool)>,
+}
+impl EnergySaver {
+	pub fn new(config: EnergySaverConfig, hmd: SpatialRef) -> Self {
+		let (far_tx, far_rx) = mpsc::unbounded_channel();
+		EnergySaver {
+			config,
+			hmd,
+			active: false,
+			last_check: f32::NEG_INFINITY,
+			far: FxHashSet::default(),
+			far_tx,
+			far_rx,
+		}
+	}
+	pub fn active(&self) -> bool {
+		self.active
+	}
+
+	/// Returns whether panel distances should be swept this frame.
+	pub fn update(&mut self, now: f32) -> bool {
+		while let Ok((uid, far)) = self.far_rx.try_recv() {
+			if far {
+				self.far.insert(uid);
+			} else {
+				self.far.remove(&uid);
+			}
+		}
+		if now - self.last_check < CHECK_INTERVAL {
+			return false;
+		}
+		self.last_check = now;
+		let active = match self.config.mode {
+			EnergySaverMode::Never => false,
+			EnergySaverMode::OnBattery => on_battery(),
+			EnergySaverMode::Always => true,
+		};
+		if active != self.active {
+			self.active = active;
+			event_log::record(
+				None,
+				if active {
+					"Energy saver on"
+				} else {
+					"Energy saver off"
+				},
+			);
+		}
+		active
+	}
+	pub fn query(&self, uid: &str, panel: &Spatial) {
+		let panel = panel.alias();
+		let hmd = self.hmd.alias();
+		let far_tx = self.far_tx.clone();
+		let uid = uid.to_string();
+		let radius = self.config.radius;
+		tokio::spawn(async move {
+			let Some(translation) = panel
+				.get_transform(&hmd)
+				.await
+				.ok()
+				.and_then(|t| t.translation)
+			else {
+				return;
+			};
+			let _ = far_tx.send((uid, glam::Vec3::from(translation).length() > radius));
+		});
+	}
+	pub fn forget(&mut self, uid: &str) {
+		self.far.remove(uid);
+	}
+	pub fn suspended(&self, uid: &str) -> bool {
+		self.active && self.far.contains(uid)
+	}
+}
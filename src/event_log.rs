@@ -0,0 +1,106 @@
+use std::{
+	collections::VecDeque,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+	time::SystemTime,
+};
+
+use stardust_xr_fusion::{
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeError,
+	spatial::{Spatial, Transform},
+};
+
+/// How many events are kept around for the log panel
+const LOG_LEN: usize = 256;
+/// Events shown on the panel at once
+const VISIBLE_LINES: usize = 20;
+/// Where the panel shows up relative to the reference space
+const LOG_POSITION: [f32; 3] = [-0.3, 0.0, -0.4];
+const TEXT_HEIGHT: f32 = 0.005;
+
+struct Event {
+	/// Seconds since the Unix epoch
+	time: u64,
+	uid: Option<String>,
+	message: String,
+}
+
+static EVENTS: Mutex<VecDeque<Event>> = Mutex::new(VecDeque::new());
+/// Bumped on every event so log panels know to redraw
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Keep an event for the log panel, about a panel when `uid` is given.
+pub fn record(uid: Option<&str>, message: impl Into<String>) {
+	let time = SystemTime::now()
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.map_or(0, |d| d.as_secs());
+	let mut events = EVENTS.lock().unwrap();
+	events.push_back(Event {
+		time,
+		uid: uid.map(str::to_string),
+		message: message.into(),
+	});
+	if events.len() > LOG_LEN {
+		events.pop_front();
+	}
+	GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The most recent events in-world, so capture failures can be debugged without a terminal.
+pub struct LogPanel {
+	_root: Spatial,
+	text: Text,
+	/// Only events about this panel when set
+	uid: Option<String>,
+	generation: Option<u64>,
+}
+impl LogPanel {
+	pub fn create(reference: &Spatial, uid: Option<String>) -> Result<Self, NodeError> {
+		let root = Spatial::create(reference, Transform::from_translation(LOG_POSITION), false)?;
+		let text = Text::create(
+			&root,
+			Transform::identity(),
+			"",
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)?;
+		Ok(LogPanel {
+			_root: root,
+			text,
+			uid,
+			generation: None,
+		})
+	}
+
+	pub fn update(&mut self) {
+		let generation = GENERATION.load(Ordering::Relaxed);
+		if self.generation == Some(generation) {
+			return;
+		}
+		self.generation = Some(generation);
+		let events = EVENTS.lock().unwrap();
+		let mut lines = events
+			.iter()
+			.rev()
+			.filter(|event| self.uid.is_none() || event.uid == self.uid)
+			.take(VISIBLE_LINES)
+			.map(|event| {
+				let time = event.time % 86400;
+				let clock = format!("{:02}:{:02}:{:02}", time / 3600, time / 60 % 60, time % 60);
+				match &event.uid {
+					Some(uid) if self.uid.is_none() => {
+						format!("{clock} [{uid}] {}", event.message)
+					}
+					_ => format!("{clock} {}", event.message),
+				}
+			})
+			.collect::<Vec<_>>();
+		lines.reverse();
+		let _ = self.text.set_text(&lines.join("\n"));
+	}
+}
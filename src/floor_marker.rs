@@ -0,0 +1,106 @@
+use std::{
+	f32::consts::TAU,
+	sync::{Arc, Mutex},
+};
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+
+use crate::theme::Theme;
+
+const SEGMENTS: usize = 24;
+/// Rings from the middle outwards, fading out to give the blob a soft edge
+const RINGS: usize = 4;
+const CENTER_OPACITY: f32 = 0.3;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FloorMarkerConfig {
+	/// Themes that put a marker on the floor under every panel
+	pub themes: Vec<Theme>,
+}
+
+/// A faint blob on the floor right under a panel, so it's easier to tell how far away it is.
+pub struct FloorMarker {
+	lines: Lines,
+	/// Where the panel was last measured, relative to the reference
+	position: Arc<Mutex<Option<Vec3>>>,
+	shown_at: Option<Vec3>,
+}
+impl FloorMarker {
+	/// `reference` is the space the floor is level at height 0 in.
+	pub fn create(reference: &Spatial, radius: f32) -> Result<Self, NodeError> {
+		let lines = (1..=RINGS)
+			.map(|ring| {
+				let ring_radius = radius * ring as f32 / RINGS as f32;
+				let opacity = CENTER_OPACITY * (1.0 - (ring - 1) as f32 / RINGS as f32);
+				Line {
+					points: (0..SEGMENTS)
+						.map(|i| {
+							let angle = i as f32 / SEGMENTS as f32 * TAU;
+							LinePoint {
+								point: [angle.cos() * ring_radius, 0.0, angle.sin() * ring_radius]
+									.into(),
+								thickness: radius / RINGS as f32,
+								color: rgba_linear!(0.0, 0.0, 0.0, opacity),
+							}
+						})
+						.collect(),
+					cyclic: true,
+				}
+			})
+			.collect::<Vec<_>>();
+		let lines = Lines::create(reference, Transform::identity(), &lines)?;
+		lines.set_enabled(false)?;
+		Ok(FloorMarker {
+			lines,
+			position: Arc::default(),
+			shown_at: None,
+		})
+	}
+
+	/// Follows the panel with a frame of delay, only moving when the panel did.
+	pub fn update(&mut self, content_parent: &Spatial, reference: &Spatial) {
+		{
+			let position = self.position.clone();
+			let content_parent = content_parent.alias();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				if let Some(translation) = content_parent
+					.get_transform(&reference)
+					.await
+					.ok()
+					.and_then(|t| t.translation)
+				{
+					*position.lock().unwrap() = Some(translation.into());
+				}
+			});
+		}
+		let Some(position) = *self.position.lock().unwrap() else {
+			return;
+		};
+		let floor = Vec3::new(position.x, 0.0, position.z);
+		if self.shown_at == Some(floor) {
+			return;
+		}
+		if self.shown_at.is_none() {
+			let _ = self.lines.set_enabled(true);
+		}
+		self.shown_at = Some(floor);
+		let _ = self
+			.lines
+			.set_relative_transform(reference, Transform::from_translation(floor));
+	}
+	/// Until the next update, for while the panel is captured or suspended.
+	pub fn hide(&mut self) {
+		if self.shown_at.take().is_some() {
+			let _ = self.lines.set_enabled(false);
+		}
+	}
+}
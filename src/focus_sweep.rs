@@ -0,0 +1,79 @@
+use mint::Vector2;
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	node::NodeError,
+	spatial::{SpatialAspect, Transform},
+};
+
+/// Seconds for the highlight to go once around the panel
+const SWEEP_DURATION: f32 = 0.45;
+/// Length of the highlight as a fraction of the panel's perimeter
+const SWEEP_LENGTH: f32 = 0.2;
+const SWEEP_POINTS: usize = 12;
+const SWEEP_THICKNESS: f32 = 0.002;
+
+/// Point on the panel's outline, `t` going from 0 to 1 clockwise from the top left corner.
+fn outline_point(size: Vector2<f32>, t: f32) -> [f32; 2] {
+	let [w, h] = [size.x, size.y];
+	let d = t.rem_euclid(1.0) * 2.0 * (w + h);
+	let [left, top] = [-w / 2.0, h / 2.0];
+	if d < w {
+		[left + d, top]
+	} else if d < w + h {
+		[-left, top - (d - w)]
+	} else if d < 2.0 * w + h {
+		[-left - (d - w - h), -top]
+	} else {
+		[left, -top + (d - 2.0 * w - h)]
+	}
+}
+
+/// A short bright streak running once around the edge of a panel that just got focus.
+pub struct FocusSweep {
+	lines: Lines,
+	size: Vector2<f32>,
+	depth: f32,
+	started_at: Option<f32>,
+}
+impl FocusSweep {
+	pub fn create(
+		panel: &impl SpatialAspect,
+		physical_size: Vector2<f32>,
+		thickness: f32,
+	) -> Result<Self, NodeError> {
+		Ok(FocusSweep {
+			lines: Lines::create(panel, Transform::identity(), &[])?,
+			size: physical_size,
+			depth: thickness / 2.0 + 0.0005,
+			started_at: None,
+		})
+	}
+
+	/// `false` once it's gone all the way around.
+	pub fn update(&mut self, now: f32) -> bool {
+		let started_at = *self.started_at.get_or_insert(now);
+		let progress = (now - started_at) / SWEEP_DURATION;
+		if progress >= 1.0 {
+			return false;
+		}
+		let points = (0..SWEEP_POINTS)
+			.map(|i| {
+				let fraction = i as f32 / (SWEEP_POINTS - 1) as f32;
+				let [x, y] = outline_point(self.size, progress - SWEEP_LENGTH * fraction);
+				// Brightest at the head, fading out along the tail and toward the end of the sweep
+				let alpha = (1.0 - fraction) * (1.0 - progress * progress);
+				LinePoint {
+					point: [x, y, self.depth].into(),
+					thickness: SWEEP_THICKNESS,
+					color: rgba_linear!(1.0, 1.0, 1.0, alpha),
+				}
+			})
+			.collect();
+		let _ = self.lines.set_lines(&[Line {
+			points,
+			cyclic: false,
+		}]);
+		true
+	}
+}
@@ -0,0 +1,69 @@
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	drawable::{Text, TextAspect, TextStyle},
+	spatial::{SpatialAspect, Transform},
+};
+
+use crate::{
+	decorator::{Decorator, DecoratorContext},
+	i18n::tr_with,
+};
+
+const TEXT_HEIGHT: f32 = 0.003;
+const TEXT_MARGIN: f32 = 0.002;
+/// Seconds between label updates, so the number stays readable
+const REFRESH_INTERVAL: f32 = 0.5;
+
+/// A small readout at the panel's top right corner with the rate Orbit itself is being framed at.
+///
+/// The panel protocol doesn't tell clients when a toplevel commits a new buffer, so the guest
+/// app's own rate can't be shown. If this reads smoothly while the panel content stutters,
+/// the app is the one falling behind. The label says so, so it isn't read as the app's rate.
+#[derive(Default)]
+pub struct FrameRate {
+	text: Option<Text>,
+	frames: u32,
+	window_start: f32,
+}
+impl FrameRate {
+	fn transform(ctx: &DecoratorContext) -> Transform {
+		Transform::from_translation([
+			ctx.size.x / 2.0,
+			ctx.size.y / 2.0 + TEXT_MARGIN + TEXT_HEIGHT,
+			0.0,
+		])
+	}
+}
+impl Decorator for FrameRate {
+	fn created(&mut self, ctx: &DecoratorContext) {
+		self.text = Text::create(
+			ctx.panel_item,
+			Self::transform(ctx),
+			"",
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)
+		.ok();
+	}
+	fn resized(&mut self, ctx: &DecoratorContext) {
+		if let Some(text) = &self.text {
+			let _ = text.set_local_transform(Self::transform(ctx));
+		}
+	}
+	fn frame(&mut self, _ctx: &DecoratorContext, info: &FrameInfo) {
+		let now = info.elapsed as f32;
+		self.frames += 1;
+		let window = now - self.window_start;
+		if window < REFRESH_INTERVAL {
+			return;
+		}
+		let rate = self.frames as f32 / window;
+		self.frames = 0;
+		self.window_start = now;
+		if let Some(text) = &self.text {
+			let _ = text.set_text(&tr_with("frame-rate", &[("rate", &format!("{rate:.0}"))]));
+		}
+	}
+}
@@ -0,0 +1,79 @@
+/// Seconds a grab may last to count as a tap
+const TAP_MAX_DURATION: f32 = 0.25;
+/// Seconds allowed between the two taps of a double tap
+const DOUBLE_TAP_WINDOW: f32 = 0.4;
+
+#[derive(Default)]
+pub struct DoubleTap {
+	started_at: f32,
+	last_tap: Option<f32>,
+}
+impl DoubleTap {
+	/// Feed grab start/stop edges, returns true on the second tap.
+	pub fn update(&mut self, started: bool, stopped: bool, elapsed: f32) -> bool {
+		if started {
+			self.started_at = elapsed;
+		}
+		if !stopped || elapsed - self.started_at > TAP_MAX_DURATION {
+			return false;
+		}
+		match self.last_tap.take() {
+			Some(last_tap) if elapsed - last_tap < DOUBLE_TAP_WINDOW => true,
+			_ => {
+				self.last_tap = Some(elapsed);
+				false
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A grab from `start` to `stop`, whether its release completed a double tap.
+	fn tap(gesture: &mut DoubleTap, start: f32, stop: f32) -> bool {
+		gesture.update(true, false, start);
+		gesture.update(false, true, stop)
+	}
+
+	#[test]
+	fn two_quick_taps_count() {
+		let mut gesture = DoubleTap::default();
+		assert!(!tap(&mut gesture, 0.0, 0.1));
+		assert!(tap(&mut gesture, 0.2, 0.3));
+	}
+
+	#[test]
+	fn held_grabs_are_no_taps() {
+		let mut gesture = DoubleTap::default();
+		assert!(!tap(&mut gesture, 0.0, 0.3));
+		assert!(!tap(&mut gesture, 0.4, 0.5));
+		assert!(tap(&mut gesture, 0.6, 0.7));
+	}
+
+	#[test]
+	fn slow_taps_start_over() {
+		let mut gesture = DoubleTap::default();
+		assert!(!tap(&mut gesture, 0.0, 0.1));
+		assert!(!tap(&mut gesture, 0.6, 0.7));
+		assert!(tap(&mut gesture, 0.8, 0.9));
+	}
+
+	#[test]
+	fn a_third_tap_starts_a_new_pair() {
+		let mut gesture = DoubleTap::default();
+		assert!(!tap(&mut gesture, 0.0, 0.1));
+		assert!(tap(&mut gesture, 0.2, 0.3));
+		assert!(!tap(&mut gesture, 0.4, 0.5));
+	}
+
+	#[test]
+	fn frames_without_a_release_never_count() {
+		let mut gesture = DoubleTap::default();
+		assert!(!tap(&mut gesture, 0.0, 0.1));
+		assert!(!gesture.update(false, false, 0.15));
+		assert!(!gesture.update(true, false, 0.2));
+		assert!(gesture.update(false, true, 0.3));
+	}
+}
@@ -0,0 +1,93 @@
+use std::sync::{Arc, Mutex};
+
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::Grabbable;
+
+use crate::one_euro::OneEuroFilter;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrabSmoothingConfig {
+	/// Cutoff in Hz while the hand holds still, lower is steadier but lags more
+	pub min_cutoff: f32,
+	/// How quickly the lag goes away as the hand speeds up
+	pub beta: f32,
+}
+impl Default for GrabSmoothingConfig {
+	fn default() -> Self {
+		GrabSmoothingConfig {
+			min_cutoff: 2.0,
+			beta: 10.0,
+		}
+	}
+}
+
+/// Steadies a carried panel so its text stays readable in shaky hands,
+/// filtering where the grab puts it each frame before it gets there.
+pub struct GrabSmoothing {
+	position: OneEuroFilter<3>,
+	rotation: OneEuroFilter<4>,
+	/// Where the grab itself put the panel, read back a frame late
+	raw: Arc<Mutex<Option<(Vec3, Quat)>>>,
+	last_rotation: Quat,
+}
+impl GrabSmoothing {
+	pub fn new(config: GrabSmoothingConfig) -> Self {
+		GrabSmoothing {
+			position: OneEuroFilter::new(config.min_cutoff, config.beta),
+			rotation: OneEuroFilter::new(config.min_cutoff, config.beta),
+			raw: Arc::default(),
+			last_rotation: Quat::IDENTITY,
+		}
+	}
+
+	/// After the grab and any rails have moved the panel this frame.
+	pub fn update(&mut self, grabbable: &Grabbable, reference: &Spatial, elapsed: f32) {
+		let grab_action = grabbable.grab_action();
+		if grab_action.actor_started() {
+			self.position.reset();
+			self.rotation.reset();
+			*self.raw.lock().unwrap() = None;
+		}
+		if !grab_action.actor_acting() {
+			return;
+		}
+		let content_parent = grabbable.content_parent();
+		{
+			let raw = self.raw.clone();
+			let content_parent = content_parent.alias();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				let Ok(pose) = content_parent.get_transform(&reference).await else {
+					return;
+				};
+				*raw.lock().unwrap() = Some((
+					pose.translation.map(Vec3::from).unwrap_or_default(),
+					pose.rotation.map(Quat::from).unwrap_or_default(),
+				));
+			});
+		}
+		let Some((position, rotation)) = *self.raw.lock().unwrap() else {
+			return;
+		};
+		// q and -q are the same rotation, keep to the side of the last one so the filter doesn't average across
+		let rotation = if rotation.dot(self.last_rotation) < 0.0 {
+			-rotation
+		} else {
+			rotation
+		};
+		let position = Vec3::from(self.position.filter_array(elapsed, position.into()));
+		let rotation =
+			Quat::from_array(self.rotation.filter_array(elapsed, rotation.into())).normalize();
+		self.last_rotation = rotation;
+		let _ = content_parent.set_relative_transform(
+			reference,
+			Transform::from_translation_rotation(position, rotation),
+		);
+	}
+}
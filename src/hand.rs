@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// The user's dominant hand. Controls meant to be reached by it sit on its side of the panel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hand {
+	Left,
+	#[default]
+	Right,
+}
+impl Hand {
+	/// Flip an x coordinate laid out for right handed use.
+	pub fn mirror(self, x: f32) -> f32 {
+		match self {
+			Hand::Left => -x,
+			Hand::Right => x,
+		}
+	}
+}
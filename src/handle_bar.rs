@@ -0,0 +1,65 @@
+use mint::Vector2;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::ResourceID,
+	drawable::Model,
+	fields::{BoxField, BoxFieldAspect},
+	items::panel::PanelItem,
+	node::{NodeError, NodeType},
+	spatial::{SpatialAspect, Transform},
+};
+
+const HANDLE_HEIGHT: f32 = 0.006;
+const HANDLE_GAP: f32 = 0.004;
+/// Fraction of the panel width the handle spans
+const HANDLE_WIDTH: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrabMode {
+	/// Grabbing anywhere on the panel moves it
+	#[default]
+	Panel,
+	/// Only the bar beneath the panel moves it, leaving the face for input
+	HandleBar,
+	/// Quick pinches on the panel click, sustained ones grab
+	Arbitrated,
+}
+
+pub struct HandleBar {
+	field: BoxField,
+	model: Model,
+}
+impl HandleBar {
+	pub fn create(panel_item: &PanelItem, thickness: f32) -> Result<Self, NodeError> {
+		let size = [HANDLE_HEIGHT, HANDLE_HEIGHT, thickness];
+		let field = BoxField::create(panel_item, Transform::identity(), size)?;
+		let model = Model::create(
+			panel_item,
+			Transform::from_scale(size),
+			&ResourceID::new_namespaced("orbit", "panel"),
+		)?;
+		Ok(HandleBar { field, model })
+	}
+	pub fn field(&self) -> &BoxField {
+		&self.field
+	}
+	pub fn set_enabled(&self, enabled: bool) {
+		let _ = self.model.set_enabled(enabled);
+	}
+	pub fn resize(&self, panel_size: Vector2<f32>, thickness: f32) {
+		let translation = [
+			0.0,
+			-panel_size.y / 2.0 - HANDLE_GAP - HANDLE_HEIGHT / 2.0,
+			0.0,
+		];
+		let size = [panel_size.x * HANDLE_WIDTH, HANDLE_HEIGHT, thickness];
+		let _ = self
+			.field
+			.set_local_transform(Transform::from_translation(translation));
+		let _ = self.field.set_size(size);
+		let _ = self
+			.model
+			.set_local_transform(Transform::from_translation_scale(translation, size));
+	}
+}
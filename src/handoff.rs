@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use tokio::{
+	io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+	net::{TcpListener, TcpStream},
+	sync::{mpsc, oneshot},
+	task::JoinHandle,
+	time::timeout,
+};
+
+use crate::{
+	ipc::{IpcMessage, Request, Response},
+	layout::Layout,
+};
+
+/// Longest hand-off taken, far more than any real layout needs
+const MAX_HANDOFF_BYTES: u64 = 1 << 20;
+/// How long a connection gets to send its whole hand-off before it's dropped
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandoffConfig {
+	/// Address to take layouts handed off from Orbit on another machine on like `0.0.0.0:7341`, off when unset
+	pub listen: Option<String>,
+	/// Secret shared by both machines, required to listen and sent along with every hand-off
+	pub token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Handoff {
+	token: String,
+	layout: Layout,
+}
+
+/// Take one JSON hand-off per connection and import its layout as if it came over IPC.
+/// Nothing but layouts is accepted here, the rest of IPC stays local.
+pub async fn serve(
+	listen: &str,
+	token: String,
+	requests: mpsc::UnboundedSender<IpcMessage>,
+) -> Result<JoinHandle<()>> {
+	let listener = TcpListener::bind(listen).await?;
+	Ok(tokio::spawn(async move {
+		while let Ok((stream, _)) = listener.accept().await {
+			let token = token.clone();
+			let requests = requests.clone();
+			tokio::spawn(async move {
+				let _ = timeout(HANDOFF_TIMEOUT, receive(stream, &token, requests)).await;
+			});
+		}
+	}))
+}
+async fn receive(stream: TcpStream, token: &str, requests: mpsc::UnboundedSender<IpcMessage>) {
+	let (read, mut write) = stream.into_split();
+	let Ok(Some(line)) = BufReader::new(read.take(MAX_HANDOFF_BYTES))
+		.lines()
+		.next_line()
+		.await
+	else {
+		return;
+	};
+	let response = match serde_json::from_str::<Handoff>(&line) {
+		Ok(handoff) if !tokens_match(&handoff.token, token) => Response::Error {
+			message: "Wrong hand-off token".to_string(),
+		},
+		Ok(Handoff { layout, .. }) => {
+			println!("Taking a hand-off of {} panels", layout.panels.len());
+			let (tx, rx) = oneshot::channel();
+			if requests
+				.send((Request::ImportLayout { layout }, tx))
+				.is_err()
+			{
+				return;
+			}
+			rx.await.unwrap_or_else(|_| Response::Error {
+				message: "Request was dropped".to_string(),
+			})
+		}
+		Err(e) => Response::Error {
+			message: e.to_string(),
+		},
+	};
+	let Ok(mut response) = serde_json::to_string(&response) else {
+		return;
+	};
+	response.push('\n');
+	let _ = write.write_all(response.as_bytes()).await;
+}
+/// Compares every byte whatever the first mismatch, so timing doesn't give the token away.
+fn tokens_match(a: &str, b: &str) -> bool {
+	a.len() == b.len()
+		&& a.bytes()
+			.zip(b.bytes())
+			.fold(0, |diff, (a, b)| diff | (a ^ b))
+			== 0
+}
+
+/// Hand a layout to Orbit listening on `to`, apps not open there yet get placed once they are.
+pub async fn send(to: &str, token: &str, layout: &Layout) -> Result<()> {
+	let stream = TcpStream::connect(to)
+		.await
+		.map_err(|e| eyre!("Could not reach orbit at {to}, is handoff.listen set there? ({e})"))?;
+	let (read, mut write) = stream.into_split();
+	let mut handoff = serde_json::to_string(&Handoff {
+		token: token.to_string(),
+		layout: layout.clone(),
+	})?;
+	handoff.push('\n');
+	write.write_all(handoff.as_bytes()).await?;
+
+	let line = timeout(HANDOFF_TIMEOUT, BufReader::new(read).lines().next_line())
+		.await
+		.map_err(|_| eyre!("Orbit at {to} didn't answer"))??
+		.ok_or_else(|| eyre!("Connection closed without a response"))?;
+	match serde_json::from_str(&line)? {
+		Response::Error { message } => Err(eyre!(message)),
+		_ => Ok(()),
+	}
+}
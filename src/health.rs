@@ -0,0 +1,102 @@
+use std::{fmt, path::Path};
+
+use stardust_xr_fusion::{
+	drawable::{Text, TextStyle},
+	node::NodeError,
+	spatial::{SpatialAspect, Transform},
+};
+
+use crate::{
+	i18n::{tr, tr_with},
+	panel_model::PanelModel,
+};
+
+/// Model parts the panel model has to provide
+const PANEL_MODEL_PARTS: &[&str] = &["Face", "Edge"];
+const PANEL_DISTANCE: f32 = 0.5;
+const TEXT_HEIGHT: f32 = 0.01;
+
+/// Something that keeps Orbit from starting, with what to do about it.
+#[derive(Debug, Clone)]
+pub struct Problem {
+	pub what: String,
+	pub fix: String,
+}
+impl Problem {
+	pub fn new(what: impl fmt::Display, fix: impl fmt::Display) -> Self {
+		Problem {
+			what: what.to_string(),
+			fix: fix.to_string(),
+		}
+	}
+}
+impl fmt::Display for Problem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}\n  → {}", self.what, self.fix)
+	}
+}
+
+/// Check the resources Orbit needs before creating anything with them.
+/// A configured frame model is checked in place of the shipped panel model,
+/// and on success is what panels get their face from.
+pub fn check(resource_dir: &Path, frame_model: Option<&Path>) -> Result<PanelModel, Vec<Problem>> {
+	let model_path =
+		frame_model.map_or_else(|| resource_dir.join("orbit/panel.glb"), Path::to_path_buf);
+	let Ok(model) = std::fs::read(&model_path) else {
+		return Err(vec![Problem::new(
+			tr_with("problem-model-missing", &[("path", &model_path.display())]),
+			match frame_model {
+				Some(_) => tr("fix-config"),
+				None => tr("fix-model-missing"),
+			},
+		)]);
+	};
+	let Some(panel_model) = PanelModel::parse(&model) else {
+		return Err(vec![Problem::new(
+			tr_with("problem-model-invalid", &[("path", &model_path.display())]),
+			tr("fix-model-invalid"),
+		)]);
+	};
+	let problems = PANEL_MODEL_PARTS
+		.iter()
+		.filter(|part| !panel_model.parts.iter().any(|name| name == *part))
+		.map(|part| {
+			Problem::new(
+				tr_with("problem-model-part", &[("part", part)]),
+				tr_with(
+					"fix-model-part",
+					&[("part", part), ("path", &model_path.display())],
+				),
+			)
+		})
+		.collect::<Vec<_>>();
+	if !problems.is_empty() {
+		return Err(problems);
+	}
+	Ok(panel_model)
+}
+
+/// Floats in front of the user listing why Orbit didn't start, since a terminal isn't visible in-headset.
+pub struct ErrorPanel {
+	_text: Text,
+}
+impl ErrorPanel {
+	pub fn create(parent: &impl SpatialAspect, problems: &[Problem]) -> Result<Self, NodeError> {
+		let mut message = tr("error-title");
+		message.push('\n');
+		for problem in problems {
+			message.push('\n');
+			message.push_str(&problem.to_string());
+		}
+		let text = Text::create(
+			parent,
+			Transform::from_translation([0.0, 0.0, -PANEL_DISTANCE]),
+			&message,
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)?;
+		Ok(ErrorPanel { _text: text })
+	}
+}
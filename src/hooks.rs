@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// Shell commands run through `sh -c` when the matching event happens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HooksConfig {
+	pub panel_created: Option<String>,
+	pub panel_destroyed: Option<String>,
+	pub captured: Option<String>,
+	pub released: Option<String>,
+	pub focused: Option<String>,
+	/// After a panel was let go of somewhere new
+	pub moved: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+	PanelCreated,
+	PanelDestroyed,
+	Captured,
+	Released,
+	Focused,
+	Moved,
+}
+impl HookEvent {
+	pub fn name(self) -> &'static str {
+		match self {
+			HookEvent::PanelCreated => "panel_created",
+			HookEvent::PanelDestroyed => "panel_destroyed",
+			HookEvent::Captured => "captured",
+			HookEvent::Released => "released",
+			HookEvent::Focused => "focused",
+			HookEvent::Moved => "moved",
+		}
+	}
+}
+
+pub struct HookContext<'a> {
+	pub uid: &'a str,
+	pub app_id: Option<&'a str>,
+	pub title: Option<&'a str>,
+	pub acceptor_uid: Option<&'a str>,
+}
+
+impl HooksConfig {
+	fn command(&self, event: HookEvent) -> Option<&str> {
+		match event {
+			HookEvent::PanelCreated => self.panel_created.as_deref(),
+			HookEvent::PanelDestroyed => self.panel_destroyed.as_deref(),
+			HookEvent::Captured => self.captured.as_deref(),
+			HookEvent::Released => self.released.as_deref(),
+			HookEvent::Focused => self.focused.as_deref(),
+			HookEvent::Moved => self.moved.as_deref(),
+		}
+	}
+
+	/// Fire and forget, hooks never block the frame loop.
+	pub fn run(&self, event: HookEvent, context: HookContext) {
+		let Some(command) = self.command(event) else {
+			return;
+		};
+		let child = Command::new("sh")
+			.arg("-c")
+			.arg(command)
+			.env("ORBIT_EVENT", event.name())
+			.env("ORBIT_UID", context.uid)
+			.env("ORBIT_APP_ID", context.app_id.unwrap_or_default())
+			.env("ORBIT_TITLE", context.title.unwrap_or_default())
+			.env(
+				"ORBIT_ACCEPTOR_UID",
+				context.acceptor_uid.unwrap_or_default(),
+			)
+			.spawn();
+		match child {
+			Ok(mut child) => {
+				tokio::spawn(async move {
+					let _ = child.wait().await;
+				});
+			}
+			Err(e) => eprintln!("Failed to run {} hook: {e}", event.name()),
+		}
+	}
+}
@@ -0,0 +1,83 @@
+use std::{fmt::Display, sync::OnceLock};
+
+use rustc_hash::FxHashMap;
+
+use crate::config::Config;
+
+/// Catalogs shipped with Orbit, English doubling as the fallback for missing strings
+const BUILTIN: &[(&str, &str)] = &[
+	("en", include_str!("../res/orbit/locales/en.toml")),
+	("de", include_str!("../res/orbit/locales/de.toml")),
+];
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+type Strings = FxHashMap<String, String>;
+
+struct Catalog {
+	strings: Strings,
+	fallback: Strings,
+}
+
+/// The language part of the first locale set in the environment, like `de` for `de_DE.UTF-8`.
+fn system_language() -> Option<String> {
+	["LC_ALL", "LC_MESSAGES", "LANG"]
+		.into_iter()
+		.filter_map(|var| std::env::var(var).ok())
+		.find(|locale| !locale.is_empty() && locale != "C" && locale != "POSIX")
+		.map(|locale| language(&locale).to_string())
+}
+fn language(locale: &str) -> &str {
+	locale.split(['_', '-', '.', '@']).next().unwrap_or(locale)
+}
+
+/// A catalog in `locales/` next to the config file overrides the built in one of the same language.
+fn load(language: &str) -> Option<Strings> {
+	let user = Config::dir()
+		.map(|dir| dir.join("locales").join(format!("{language}.toml")))
+		.and_then(|path| std::fs::read_to_string(path).ok());
+	let builtin = BUILTIN
+		.iter()
+		.find(|(name, _)| *name == language)
+		.map(|(_, file)| file.to_string());
+	let file = user.or(builtin)?;
+	match toml::from_str(&file) {
+		Ok(strings) => Some(strings),
+		Err(e) => {
+			eprintln!("Invalid {language} locale: {e}");
+			None
+		}
+	}
+}
+
+/// Pick the catalog for `locale`, or the system's when unset. Only the first call has any effect.
+pub fn init(locale: Option<&str>) {
+	let language = locale
+		.map(|locale| language(locale).to_string())
+		.or_else(system_language)
+		.unwrap_or_else(|| "en".to_string());
+	let fallback = load("en").unwrap_or_default();
+	let strings = load(&language).unwrap_or_default();
+	let _ = CATALOG.set(Catalog { strings, fallback });
+}
+
+/// The localized string for `key`, with `{name}` placeholders filled in from `args`.
+pub fn tr_with(key: &str, args: &[(&str, &dyn Display)]) -> String {
+	let catalog = CATALOG.get_or_init(|| Catalog {
+		strings: Strings::default(),
+		fallback: load("en").unwrap_or_default(),
+	});
+	let mut text = catalog
+		.strings
+		.get(key)
+		.or_else(|| catalog.fallback.get(key))
+		.cloned()
+		.unwrap_or_else(|| key.to_string());
+	for (name, value) in args {
+		text = text.replace(&format!("{{{name}}}"), &value.to_string());
+	}
+	text
+}
+pub fn tr(key: &str) -> String {
+	tr_with(key, &[])
+}
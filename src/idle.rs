@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, SpatialRef},
+};
+
+use crate::event_log;
+
+/// Seconds between head pose samples
+const CHECK_INTERVAL: f32 = 1.0;
+/// Head movement between samples that counts as activity, in meters
+const HEAD_MOVEMENT: f32 = 0.02;
+/// Head turn between samples that counts as activity, in radians
+const HEAD_TURN: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IdleConfig {
+	/// Seconds without grabs or head movement before all panels are hidden, never when unset
+	pub timeout: Option<f32>,
+}
+
+/// Puts the workspace to sleep once the user has stepped away, waking it when they come back.
+pub struct IdleDetector {
+	config: IdleConfig,
+	reference: Spatial,
+	hmd: SpatialRef,
+	asleep: bool,
+	active: bool,
+	last_activity: f32,
+	last_check: f32,
+	last_pose: Option<(Vec3, Quat)>,
+	sampled: Arc<Mutex<Option<(Vec3, Quat)>>>,
+}
+impl IdleDetector {
+	pub fn new(config: IdleConfig, reference: &Spatial, hmd: &SpatialRef) -> Self {
+		IdleDetector {
+			config,
+			reference: reference.alias(),
+			hmd: hmd.alias(),
+			asleep: false,
+			active: false,
+			last_activity: 0.0,
+			last_check: f32::NEG_INFINITY,
+			last_pose: None,
+			sampled: Default::default(),
+		}
+	}
+	pub fn asleep(&self) -> bool {
+		self.asleep
+	}
+	/// Something was grabbed, which always counts as someone being there.
+	pub fn activity(&mut self) {
+		self.active = true;
+	}
+
+	pub fn update(&mut self, now: f32) {
+		let Some(timeout) = self.config.timeout else {
+			return;
+		};
+		if let Some((position, rotation)) = self.sampled.lock().unwrap().take() {
+			let moved = self
+				.last_pose
+				.map_or(true, |(last_position, last_rotation)| {
+					position.distance(last_position) > HEAD_MOVEMENT
+						|| rotation.angle_between(last_rotation) > HEAD_TURN
+				});
+			self.active |= moved;
+			self.last_pose = Some((position, rotation));
+		}
+		if std::mem::take(&mut self.active) {
+			self.last_activity = now;
+		}
+		if now - self.last_check >= CHECK_INTERVAL {
+			self.last_check = now;
+			self.sample();
+		}
+
+		let asleep = now - self.last_activity > timeout;
+		if asleep != self.asleep {
+			self.asleep = asleep;
+			event_log::record(None, if asleep { "Idle, sleeping" } else { "Awake" });
+		}
+	}
+	fn sample(&self) {
+		let hmd = self.hmd.alias();
+		let reference = self.reference.alias();
+		let sampled = self.sampled.clone();
+		tokio::spawn(async move {
+			let Ok(transform) = hmd.get_transform(&reference).await else {
+				return;
+			};
+			let (Some(translation), Some(rotation)) = (transform.translation, transform.rotation)
+			else {
+				return;
+			};
+			*sampled.lock().unwrap() = Some((translation.into(), rotation.into()));
+		});
+	}
+}
@@ -0,0 +1,575 @@
+use mint::Vector2;
+
+use crate::{
+	cursor::HoverCursor,
+	desk::MouseEvent,
+	dev_overlay::InputLog,
+	hand::Hand,
+	one_euro::OneEuroFilter,
+	panel_model::FaceRect,
+	popups::Popups,
+	scroll::{ScrollConfig, WheelEmulation, WHEEL_STEP},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	input::InputData,
+	items::panel::{PanelItem, SurfaceID},
+	node::{NodeError, NodeType},
+	spatial::{SpatialAspect, Transform},
+};
+use stardust_xr_molecules::touch_plane::TouchPlane;
+
+/// Jitter filter cutoff while holding still, in Hz
+const SMOOTHING_MIN_CUTOFF: f32 = 1.0;
+/// Cutoff increase per pixel per second of movement
+const SMOOTHING_BETA: f32 = 0.005;
+
+/// Width of the scroll strips along the dominant side and bottom edges, in meters
+const EDGE_SCROLL_ZONE: f32 = 0.008;
+
+/// How touches behave on one app's panels, unset fields falling back to the defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputRule {
+	pub app_id: String,
+	pub deadzone: Option<f32>,
+	pub activation_distance: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputConfig {
+	/// Meters a finger has to travel after touching before the app hears it move, 0 sends every move
+	pub deadzone: f32,
+	/// How far in front of the face a poke starts touching, in meters, half the panel's thickness when unset
+	pub activation_distance: Option<f32>,
+	/// Like a hair trigger for games and deliberate pokes for terminals
+	pub rules: Vec<InputRule>,
+	/// Direction, speed and wheel steps of edge strips, two-finger pans and the desk mouse
+	pub scroll: ScrollConfig,
+}
+impl InputConfig {
+	pub fn for_app(&self, app_id: Option<&str>) -> TouchSettings {
+		let rule = self
+			.rules
+			.iter()
+			.find(|rule| Some(rule.app_id.as_str()) == app_id);
+		TouchSettings {
+			deadzone: rule.and_then(|rule| rule.deadzone).unwrap_or(self.deadzone),
+			activation_distance: rule
+				.and_then(|rule| rule.activation_distance)
+				.or(self.activation_distance),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TouchSettings {
+	pub deadzone: f32,
+	pub activation_distance: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScrollAxis {
+	Vertical,
+	Horizontal,
+}
+
+/// Where the face sits on a panel of `physical_size` and how big it is there.
+fn face_geometry(face: FaceRect, physical_size: Vector2<f32>) -> ([f32; 3], Vector2<f32>) {
+	(
+		[
+			face.center[0] * physical_size.x,
+			face.center[1] * physical_size.y,
+			0.0,
+		],
+		Vector2::from([
+			face.size[0] * physical_size.x,
+			face.size[1] * physical_size.y,
+		]),
+	)
+}
+
+/// Forwards hand and pointer input on the panel face to the toplevel as touches.
+pub struct PanelInput {
+	panel_item: PanelItem,
+	thickness: f32,
+	/// Meters, turned into pixels as the panel resizes
+	deadzone: f32,
+	/// Where touches that haven't left the deadzone yet went down
+	held: FxHashMap<usize, Vector2<f32>>,
+	touch_plane: TouchPlane,
+	cursor: HoverCursor,
+	/// Where another hand hovers, since the toplevel only has the one pointer
+	second_cursor: HoverCursor,
+	/// Where a physical mouse left the pointer, until a hand takes it back
+	mouse_position: Option<Vector2<f32>>,
+	/// The input driving the pointer, kept until it stops hovering so two hands don't fight over it
+	pointer_owner: Option<usize>,
+	physical_size: Vector2<f32>,
+	pixel_size: Vector2<u32>,
+	/// Input IDs mapped to the touch IDs the toplevel sees
+	touches: FxHashMap<usize, u32>,
+	/// Touches that started in an edge zone and scroll instead, with their last position
+	scrolls: FxHashMap<usize, (ScrollAxis, Vector2<f32>)>,
+	/// Fingers panning together instead of touching, with their last position once known
+	pans: FxHashMap<usize, Option<Vector2<f32>>>,
+	two_finger_pan: bool,
+	view_only: bool,
+	/// Puts the vertical scroll strip on this hand's side
+	hand: Hand,
+	/// The part of the model the surface is actually drawn on
+	face: FaceRect,
+	/// Inputs a panel in front of this one gets instead
+	occluded: FxHashSet<usize>,
+	/// Per input, so two hands don't smooth into each other
+	smoothing: FxHashMap<usize, OneEuroFilter>,
+	wheel: WheelEmulation,
+	/// Where the pointer was last sent, in surface pixels
+	pointer_position: Option<Vector2<f32>>,
+	log: InputLog,
+	popups: Popups,
+	/// The surface the pointer was last moved over, which gets its button presses
+	pointer_surface: SurfaceID,
+	/// Where the popup a touch started on sits on the toplevel, since its moves are in that popup's coordinates
+	touch_offsets: FxHashMap<usize, Vector2<f32>>,
+}
+impl PanelInput {
+	pub fn create(
+		panel_item: &PanelItem,
+		thickness: f32,
+		hand: Hand,
+		touch: TouchSettings,
+		scroll: ScrollConfig,
+		face: FaceRect,
+	) -> Result<Self, NodeError> {
+		let touch_depth = touch
+			.activation_distance
+			.map_or(thickness, |distance| distance.max(0.0) * 2.0);
+		let touch_plane = Self::touch_plane(
+			panel_item,
+			face,
+			touch_depth,
+			[1.0; 2].into(),
+			[1; 2].into(),
+		)?;
+		Ok(PanelInput {
+			panel_item: panel_item.alias(),
+			thickness,
+			deadzone: touch.deadzone,
+			held: FxHashMap::default(),
+			touch_plane,
+			cursor: HoverCursor::create(panel_item)?,
+			second_cursor: HoverCursor::create(panel_item)?,
+			pointer_owner: None,
+			mouse_position: None,
+			physical_size: [1.0; 2].into(),
+			pixel_size: [1; 2].into(),
+			touches: FxHashMap::default(),
+			scrolls: FxHashMap::default(),
+			pans: FxHashMap::default(),
+			two_finger_pan: false,
+			view_only: false,
+			hand,
+			face,
+			occluded: FxHashSet::default(),
+			smoothing: FxHashMap::default(),
+			wheel: WheelEmulation::new(scroll),
+			pointer_position: None,
+			log: InputLog::default(),
+			popups: Popups::default(),
+			pointer_surface: SurfaceID::Toplevel,
+			touch_offsets: FxHashMap::default(),
+		})
+	}
+
+	/// Covers exactly the modeled face rather than the whole panel, so pixels land where they're drawn.
+	fn touch_plane(
+		panel_item: &PanelItem,
+		face: FaceRect,
+		thickness: f32,
+		physical_size: Vector2<f32>,
+		pixel_size: Vector2<u32>,
+	) -> Result<TouchPlane, NodeError> {
+		let (center, size) = face_geometry(face, physical_size);
+		TouchPlane::create(
+			panel_item,
+			Transform::from_translation(center),
+			size,
+			thickness,
+			0.0..pixel_size.x as f32,
+			0.0..pixel_size.y as f32,
+		)
+	}
+
+	/// Keep the plane covering the face, mapping it onto surface pixels.
+	/// The plane is moved and resized in place, so touches carry on across a resize.
+	pub fn resize(&mut self, physical_size: Vector2<f32>, pixel_size: Vector2<u32>) {
+		if physical_size == self.physical_size && pixel_size == self.pixel_size {
+			return;
+		}
+		let (center, size) = face_geometry(self.face, physical_size);
+		let _ = self
+			.touch_plane
+			.root()
+			.set_local_transform(Transform::from_translation(center));
+		let _ = self.touch_plane.set_size(size);
+		self.touch_plane.x_range = 0.0..pixel_size.x as f32;
+		self.touch_plane.y_range = 0.0..pixel_size.y as f32;
+		self.physical_size = physical_size;
+		self.pixel_size = pixel_size;
+	}
+
+	/// The panel still shows its surface but the toplevel never hears about any input.
+	pub fn set_view_only(&mut self, view_only: bool) {
+		self.view_only = view_only;
+		if view_only {
+			self.cursor.hide();
+			self.second_cursor.hide();
+			self.touches.clear();
+			self.scrolls.clear();
+			self.pans.clear();
+			self.held.clear();
+			self.mouse_position = None;
+			self.pointer_position = None;
+			let _ = self.panel_item.reset_touches();
+		}
+	}
+	pub fn popups(&mut self) -> &mut Popups {
+		&mut self.popups
+	}
+	/// Pointer motion goes to the popup under it, the press that lands outside every open one comes
+	/// right after moving off them so the server sees the grab broken and closes them like on a desktop.
+	fn move_pointer(
+		panel_item: &PanelItem,
+		popups: &Popups,
+		pointer_surface: &mut SurfaceID,
+		position: Vector2<f32>,
+	) {
+		let (surface, local) = popups.surface_at(position);
+		let _ = panel_item.pointer_motion(&surface, local);
+		*pointer_surface = surface;
+	}
+
+	/// Keep the last forwarded events around for the developer overlay.
+	pub fn set_logging(&mut self, logging: bool) {
+		self.log.set_enabled(logging);
+	}
+	pub fn log(&self) -> &InputLog {
+		&self.log
+	}
+	/// The surface pixel the pointer is over, if anything is pointing at the panel.
+	pub fn pointer_position(&self) -> Option<Vector2<f32>> {
+		self.pointer_position
+	}
+	pub fn view_only(&self) -> bool {
+		self.view_only
+	}
+	pub fn hovered(&self) -> bool {
+		!self.touch_plane.hovering_inputs().is_empty() || self.interacting()
+	}
+	/// Whether a touch or an edge scroll is going on right now.
+	pub fn interacting(&self) -> bool {
+		!self.touches.is_empty() || !self.scrolls.is_empty() || !self.pans.is_empty()
+	}
+	/// Every input over the face with how far it is from it, touches in progress at 0 so they're never taken away.
+	pub fn claims(&self) -> Vec<(usize, f32)> {
+		let touching = self
+			.touch_plane
+			.touching()
+			.into_iter()
+			.map(|input| (input.id, 0.0));
+		let hovering = self.touch_plane.hovering_inputs().into_iter().map(|input| {
+			let (_, depth) = self.touch_plane.interact_point(&input);
+			(input.id, depth.abs())
+		});
+		touching.chain(hovering).collect()
+	}
+	/// Ignore these for hovering and new touches, they're closer to another panel.
+	pub fn set_occluded(&mut self, occluded: FxHashSet<usize>) {
+		self.occluded = occluded;
+	}
+	/// A second finger turns both into a scroll, for moving around content that's zoomed in.
+	pub fn set_two_finger_pan(&mut self, two_finger_pan: bool) {
+		self.two_finger_pan = two_finger_pan;
+	}
+
+	/// Surface pixels to a point just in front of the face, y pointing down in pixels and up in meters.
+	fn surface_to_local(&self, position: Vector2<f32>) -> mint::Vector3<f32> {
+		let [width, height] = self.face_size();
+		[
+			(position.x / self.pixel_size.x as f32 - 0.5) * width
+				+ self.face.center[0] * self.physical_size.x,
+			(0.5 - position.y / self.pixel_size.y as f32) * height
+				+ self.face.center[1] * self.physical_size.y,
+			self.thickness / 2.0 + 0.0005,
+		]
+		.into()
+	}
+
+	/// Size of the face in meters.
+	fn face_size(&self) -> [f32; 2] {
+		[
+			self.face.size[0] * self.physical_size.x,
+			self.face.size[1] * self.physical_size.y,
+		]
+	}
+
+	fn edge_zone(&self, position: Vector2<f32>) -> Option<ScrollAxis> {
+		let [width, height] = self.face_size();
+		let zone_x = EDGE_SCROLL_ZONE / width * self.pixel_size.x as f32;
+		let zone_y = EDGE_SCROLL_ZONE / height * self.pixel_size.y as f32;
+		let in_side_strip = match self.hand {
+			Hand::Left => position.x < zone_x,
+			Hand::Right => position.x > self.pixel_size.x as f32 - zone_x,
+		};
+		if in_side_strip {
+			Some(ScrollAxis::Vertical)
+		} else if position.y > self.pixel_size.y as f32 - zone_y {
+			Some(ScrollAxis::Horizontal)
+		} else {
+			None
+		}
+	}
+	/// The lowest ID no finger holds, like touchscreen slots, so each hand keeps its own and they get reused.
+	fn free_touch_id(&self) -> u32 {
+		(0..)
+			.find(|id| !self.touches.values().any(|touch| touch == id))
+			.unwrap_or_default()
+	}
+
+	/// The surface position of an input, smoothed over the frames it's been seen.
+	/// Filtered once per frame into `points`, an input seen hovering and touching reuses it.
+	fn interact_point(
+		touch_plane: &TouchPlane,
+		smoothing: &mut FxHashMap<usize, OneEuroFilter>,
+		points: &mut FxHashMap<usize, Vector2<f32>>,
+		now: f32,
+		input: &InputData,
+	) -> Vector2<f32> {
+		*points.entry(input.id).or_insert_with(|| {
+			let (position, _) = touch_plane.interact_point(input);
+			smoothing
+				.entry(input.id)
+				.or_insert_with(|| OneEuroFilter::new(SMOOTHING_MIN_CUTOFF, SMOOTHING_BETA))
+				.filter(now, position)
+		})
+	}
+
+	/// Drive the pointer from a physical mouse, starting from the middle of the surface.
+	pub fn mouse(&mut self, event: MouseEvent, sensitivity: f32) {
+		if self.view_only {
+			return;
+		}
+		let center = Vector2::from([
+			self.pixel_size.x as f32 / 2.0,
+			self.pixel_size.y as f32 / 2.0,
+		]);
+		let position = self.mouse_position.get_or_insert(center);
+		match event {
+			MouseEvent::Motion { dx, dy } => {
+				position.x = (position.x + dx * sensitivity).clamp(0.0, self.pixel_size.x as f32);
+				position.y = (position.y + dy * sensitivity).clamp(0.0, self.pixel_size.y as f32);
+				let position = *position;
+				self.pointer_position = Some(position);
+				Self::move_pointer(
+					&self.panel_item,
+					&self.popups,
+					&mut self.pointer_surface,
+					position,
+				);
+				self.cursor.show_at(self.surface_to_local(position));
+			}
+			MouseEvent::Button { button, pressed } => {
+				self.log
+					.push(|| format!("button {button:#x} {}", if pressed { "down" } else { "up" }));
+				let _ = self
+					.panel_item
+					.pointer_button(&self.pointer_surface, button, pressed);
+			}
+			MouseEvent::Scroll { dx, dy } => {
+				self.wheel.scroll(
+					&self.panel_item,
+					&mut self.log,
+					[dx * WHEEL_STEP, dy * WHEEL_STEP].into(),
+				);
+			}
+		}
+	}
+
+	/// `now` is the frame time, which the jitter filter goes by.
+	pub fn update(&mut self, now: f32) {
+		self.touch_plane.update();
+		if self.view_only {
+			return;
+		}
+		let hovering = self
+			.touch_plane
+			.hovering_inputs()
+			.into_iter()
+			.filter(|input| !self.occluded.contains(&input.id))
+			.collect::<Vec<_>>();
+		let seen = hovering
+			.iter()
+			.map(|input| input.id)
+			.chain(
+				self.touch_plane
+					.touching()
+					.into_iter()
+					.map(|input| input.id),
+			)
+			.collect::<Vec<_>>();
+		self.smoothing.retain(|id, _| seen.contains(id));
+		let mut points = FxHashMap::default();
+		let panel_item = &self.panel_item;
+		if !self
+			.pointer_owner
+			.is_some_and(|owner| hovering.iter().any(|input| input.id == owner))
+		{
+			self.pointer_owner = hovering.first().map(|input| input.id);
+		}
+		let mut second_hovering = false;
+		for input in &hovering {
+			let position = Self::interact_point(
+				&self.touch_plane,
+				&mut self.smoothing,
+				&mut points,
+				now,
+				input,
+			);
+			let local = self.surface_to_local(position);
+			if Some(input.id) == self.pointer_owner {
+				self.pointer_position = Some(position);
+				Self::move_pointer(
+					panel_item,
+					&self.popups,
+					&mut self.pointer_surface,
+					position,
+				);
+				self.cursor.show_at(local);
+			} else if !second_hovering {
+				second_hovering = true;
+				self.second_cursor.show_at(local);
+			}
+		}
+		if self.pointer_owner.is_some() {
+			self.mouse_position = None;
+		} else if self.mouse_position.is_none() {
+			self.pointer_position = None;
+			self.cursor.hide();
+		}
+		if !second_hovering {
+			self.second_cursor.hide();
+		}
+		for input in self.touch_plane.touch_started() {
+			if self.occluded.contains(&input.id) {
+				continue;
+			}
+			let position = Self::interact_point(
+				&self.touch_plane,
+				&mut self.smoothing,
+				&mut points,
+				now,
+				&input,
+			);
+			if let Some(axis) = self.edge_zone(position) {
+				self.scrolls.insert(input.id, (axis, position));
+				continue;
+			}
+			if self.two_finger_pan && (self.touches.len() == 1 || !self.pans.is_empty()) {
+				// The first finger's touch is taken back so the app doesn't see a drag
+				for (other, id) in self.touches.drain() {
+					self.log.push(|| format!("touch {id} up"));
+					let _ = panel_item.touch_up(id);
+					self.pans.insert(other, None);
+				}
+				self.pans.insert(input.id, Some(position));
+				continue;
+			}
+			let id = self.free_touch_id();
+			self.touches.insert(input.id, id);
+			if self.deadzone > 0.0 {
+				self.held.insert(input.id, position);
+			}
+			let (surface, local) = self.popups.surface_at(position);
+			if matches!(surface, SurfaceID::Toplevel) && self.popups.any_open() {
+				// Taps outside a menu should close it, which needs the pointer off of it first
+				Self::move_pointer(
+					panel_item,
+					&self.popups,
+					&mut self.pointer_surface,
+					position,
+				);
+			}
+			if !matches!(surface, SurfaceID::Toplevel) {
+				self.touch_offsets.insert(
+					input.id,
+					[position.x - local.x, position.y - local.y].into(),
+				);
+			}
+			self.log
+				.push(|| format!("touch {id} down {:.0}, {:.0}", local.x, local.y));
+			let _ = panel_item.touch_down(&surface, id, local);
+		}
+		let mut pan_delta = Vector2::from([0.0; 2]);
+		for input in self.touch_plane.touching() {
+			let position = Self::interact_point(
+				&self.touch_plane,
+				&mut self.smoothing,
+				&mut points,
+				now,
+				&input,
+			);
+			if let Some(last) = self.pans.get_mut(&input.id) {
+				if let Some(last) = last {
+					pan_delta.x += (position.x - last.x) / 2.0;
+					pan_delta.y += (position.y - last.y) / 2.0;
+				}
+				*last = Some(position);
+				continue;
+			}
+			if let Some((axis, last)) = self.scrolls.get_mut(&input.id) {
+				let delta = match axis {
+					ScrollAxis::Vertical => [0.0, position.y - last.y],
+					ScrollAxis::Horizontal => [position.x - last.x, 0.0],
+				};
+				*last = position;
+				self.wheel.scroll(panel_item, &mut self.log, delta.into());
+				continue;
+			}
+			let Some(id) = self.touches.get(&input.id) else {
+				continue;
+			};
+			if let Some(start) = self.held.get(&input.id) {
+				let [width, _] = self.face_size();
+				let deadzone = self.deadzone / width * self.pixel_size.x as f32;
+				if (position.x - start.x).hypot(position.y - start.y) < deadzone {
+					continue;
+				}
+				self.held.remove(&input.id);
+			}
+			let position = match self.touch_offsets.get(&input.id) {
+				Some(offset) => [position.x - offset.x, position.y - offset.y].into(),
+				None => position,
+			};
+			let _ = panel_item.touch_move(*id, position);
+		}
+		if self.pans.len() >= 2 && (pan_delta.x != 0.0 || pan_delta.y != 0.0) {
+			self.wheel.scroll(panel_item, &mut self.log, pan_delta);
+		}
+		for input in self.touch_plane.touch_stopped() {
+			if self.scrolls.remove(&input.id).is_some() {
+				self.wheel.stop(panel_item, &mut self.log);
+			}
+			if self.pans.remove(&input.id).is_some() && self.pans.is_empty() {
+				self.wheel.stop(panel_item, &mut self.log);
+			}
+			self.held.remove(&input.id);
+			self.touch_offsets.remove(&input.id);
+			if let Some(id) = self.touches.remove(&input.id) {
+				self.log.push(|| format!("touch {id} up"));
+				let _ = panel_item.touch_up(id);
+			}
+		}
+	}
+}
@@ -0,0 +1,352 @@
+use crate::{
+	acceptor_registry::AcceptorMetricsSnapshot,
+	commands::WmCommand,
+	hooks::{HookContext, HookEvent},
+	layout::Layout,
+	notes::NoteCommand,
+	profile,
+	theme::Theme,
+	wall::{WallConfig, WallTiling},
+};
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+	fs::DirBuilder,
+	io::ErrorKind,
+	os::unix::fs::{DirBuilderExt, PermissionsExt},
+	path::{Path, PathBuf},
+	sync::OnceLock,
+};
+use tokio::{
+	io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+	net::{unix::OwnedWriteHalf, UnixListener, UnixStream},
+	sync::{
+		broadcast::{self, error::RecvError},
+		mpsc, oneshot,
+	},
+	task::JoinHandle,
+};
+
+/// Events kept for subscribers that fall behind, older ones are skipped
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+	ExportLayout,
+	ImportLayout {
+		layout: Layout,
+	},
+	AcceptorMetrics,
+	Status,
+	ListPanels,
+	/// Replaces the parking wall, or removes it when empty
+	SetWall {
+		wall: Option<WallConfig>,
+	},
+	/// Retunes how the current wall packs its panels, keeping them on it
+	SetWallTiling {
+		tiling: WallTiling,
+	},
+	SetStreamingMode {
+		enabled: bool,
+	},
+	SetTheme {
+		theme: Theme,
+	},
+	/// Measured room light from 0 for dark to 1 for daylight, back to the configured dusk and dawn when unset
+	SetAmbient {
+		level: Option<f32>,
+	},
+	/// Records the current eye height as seated, or goes back to standing
+	SetSeated {
+		seated: bool,
+	},
+	/// Starts the first-run tutorial over, or dismisses it
+	SetTutorial {
+		enabled: bool,
+	},
+	/// Acts on the focused panel when no uid is given
+	Panel {
+		uid: Option<String>,
+		action: PanelAction,
+	},
+	Wm {
+		action: WmCommand,
+	},
+	Note {
+		action: NoteCommand,
+	},
+	/// Runs a panel action on every open panel of an app
+	AppAction {
+		app_id: String,
+		action: PanelAction,
+	},
+	/// Lines up every free panel of an app side by side in front of the head, focusing the first
+	GatherApp {
+		app_id: String,
+	},
+	/// Sets the badge on every open panel of an app
+	SetAppBadge {
+		app_id: String,
+		count: u32,
+	},
+	/// Keeps the connection open, streaming a [`PanelEvent`] line for everything that happens to panels
+	Subscribe,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PanelAction {
+	/// Cover the panel face with the privacy shutter
+	Hide,
+	Reveal,
+	/// Keep showing the panel but drop all input to it
+	ViewOnly,
+	Interactive,
+	/// Ask the toplevel to close, it may still show a dialog first
+	Close,
+	/// Flash the panel until it's focused, for notifiers standing in for a toplevel's own request
+	DemandAttention,
+	/// Show an unread count on the panel, 0 clears it
+	SetBadge {
+		count: u32,
+	},
+	/// Magnify the content by this factor while the panel keeps its physical size, 1 is unzoomed
+	SetZoom {
+		zoom: f32,
+	},
+	/// Put a dark plate behind the panel or take it away, back to what the theme says when unset
+	SetBackingPlate {
+		enabled: Option<bool>,
+	},
+	/// Show only the surface with no edge around it, still grabbable where the edge was
+	SetFrameless {
+		enabled: bool,
+	},
+	/// Show the hovered surface pixel and the last events sent to the toplevel under the panel
+	SetDevOverlay {
+		enabled: bool,
+	},
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelSummary {
+	pub uid: String,
+	pub app_id: Option<String>,
+	pub title: Option<String>,
+	pub focused: bool,
+	pub captured: bool,
+	pub hidden: bool,
+	pub view_only: bool,
+	pub urgent: bool,
+	pub badge: u32,
+	pub suspended: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Response {
+	Ok,
+	Layout {
+		layout: Layout,
+	},
+	AcceptorMetrics {
+		metrics: Vec<AcceptorMetricsSnapshot>,
+	},
+	Panels {
+		panels: Vec<PanelSummary>,
+	},
+	Status {
+		energy_saver: bool,
+		asleep: bool,
+		/// Another shell manages panels, so only requests that leave them alone are answered
+		#[serde(default)]
+		panels_yielded: bool,
+	},
+	Error {
+		message: String,
+	},
+}
+
+pub type IpcMessage = (Request, oneshot::Sender<Response>);
+
+/// Something that happened to a panel, with the same names and details hooks get.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelEvent {
+	pub event: String,
+	pub uid: String,
+	pub app_id: Option<String>,
+	pub title: Option<String>,
+	pub acceptor_uid: Option<String>,
+}
+
+static EVENTS: OnceLock<broadcast::Sender<PanelEvent>> = OnceLock::new();
+fn events() -> &'static broadcast::Sender<PanelEvent> {
+	EVENTS.get_or_init(|| broadcast::channel(EVENT_BUFFER).0)
+}
+/// Stream an event to every subscribed connection, nothing is built while nobody listens.
+pub fn publish(event: HookEvent, context: &HookContext) {
+	let events = events();
+	if events.receiver_count() == 0 {
+		return;
+	}
+	let _ = events.send(PanelEvent {
+		event: event.name().to_string(),
+		uid: context.uid.to_string(),
+		app_id: context.app_id.map(str::to_string),
+		title: context.title.map(str::to_string),
+		acceptor_uid: context.acceptor_uid.map(str::to_string),
+	});
+}
+
+/// Each profile gets its own socket so the CLI talks to the instance running that profile.
+pub fn socket_path() -> PathBuf {
+	let name = match profile::name() {
+		Some(profile) => format!("orbit-{profile}.sock"),
+		None => "orbit.sock".to_string(),
+	};
+	dirs::runtime_dir().unwrap_or_else(fallback_dir).join(name)
+}
+/// A directory of our own in the shared temp dir, for systems without `XDG_RUNTIME_DIR`.
+fn fallback_dir() -> PathBuf {
+	let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+	std::env::temp_dir().join(format!("orbit-{user}"))
+}
+/// Makes sure nobody else can reach into the fallback directory.
+/// One someone else made that way is locked to us too, so binding in it just fails.
+fn private_dir(dir: &Path) -> Result<()> {
+	match DirBuilder::new().mode(0o700).create(dir) {
+		Ok(()) => return Ok(()),
+		Err(e) if e.kind() == ErrorKind::AlreadyExists => (),
+		Err(e) => return Err(e.into()),
+	}
+	let metadata = std::fs::symlink_metadata(dir)?;
+	if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+		return Err(eyre!(
+			"{} has to be a directory only its owner can open",
+			dir.display()
+		));
+	}
+	Ok(())
+}
+
+/// Listen for one JSON request per line, forwarding each to the client loop and writing back its response.
+pub fn serve(requests: mpsc::UnboundedSender<IpcMessage>) -> Result<JoinHandle<()>> {
+	let path = socket_path();
+	if dirs::runtime_dir().is_none() {
+		if let Some(dir) = path.parent() {
+			private_dir(dir)?;
+		}
+	}
+	// A socket left behind by a crash is removed, one that still answers belongs to a running Orbit
+	match std::os::unix::net::UnixStream::connect(&path) {
+		Ok(_) => {
+			return Err(eyre!(
+				"Orbit is already running for this profile on {}",
+				path.display()
+			))
+		}
+		Err(e) if e.kind() == ErrorKind::ConnectionRefused => std::fs::remove_file(&path)?,
+		Err(_) => (),
+	}
+	let listener = UnixListener::bind(&path)?;
+	Ok(tokio::spawn(async move {
+		while let Ok((stream, _)) = listener.accept().await {
+			tokio::spawn(handle_connection(stream, requests.clone()));
+		}
+	}))
+}
+
+async fn write_line(write: &mut OwnedWriteHalf, value: &impl Serialize) -> Result<()> {
+	let mut line = serde_json::to_string(value)?;
+	line.push('\n');
+	write.write_all(line.as_bytes()).await?;
+	Ok(())
+}
+
+async fn stream_events(mut write: OwnedWriteHalf) {
+	let mut events = events().subscribe();
+	if write_line(&mut write, &Response::Ok).await.is_err() {
+		return;
+	}
+	loop {
+		match events.recv().await {
+			Ok(event) => {
+				if write_line(&mut write, &event).await.is_err() {
+					return;
+				}
+			}
+			Err(RecvError::Lagged(_)) => continue,
+			Err(RecvError::Closed) => return,
+		}
+	}
+}
+
+async fn handle_connection(stream: UnixStream, requests: mpsc::UnboundedSender<IpcMessage>) {
+	let (read, mut write) = stream.into_split();
+	let mut lines = BufReader::new(read).lines();
+	while let Ok(Some(line)) = lines.next_line().await {
+		let response = match serde_json::from_str::<Request>(&line) {
+			Ok(Request::Subscribe) => return stream_events(write).await,
+			Ok(request) => {
+				let (tx, rx) = oneshot::channel();
+				if requests.send((request, tx)).is_err() {
+					return;
+				}
+				rx.await.unwrap_or_else(|_| Response::Error {
+					message: "Request was dropped".to_string(),
+				})
+			}
+			Err(e) => Response::Error {
+				message: e.to_string(),
+			},
+		};
+		if write_line(&mut write, &response).await.is_err() {
+			return;
+		}
+	}
+}
+
+/// Send a single request to the running instance and wait for its response.
+pub async fn send(request: &Request) -> Result<Response> {
+	let stream = UnixStream::connect(socket_path())
+		.await
+		.map_err(|e| eyre!("Could not connect to orbit, is it running? ({e})"))?;
+	let (read, mut write) = stream.into_split();
+	let mut request = serde_json::to_string(request)?;
+	request.push('\n');
+	write.write_all(request.as_bytes()).await?;
+
+	let line = BufReader::new(read)
+		.lines()
+		.next_line()
+		.await?
+		.ok_or_else(|| eyre!("Connection closed without a response"))?;
+	match serde_json::from_str(&line)? {
+		Response::Error { message } => Err(eyre!(message)),
+		response => Ok(response),
+	}
+}
+
+/// Subscribe to the running instance's panel events, handing over each JSON line as it comes until it exits.
+pub async fn subscribe(mut on_event: impl FnMut(&str)) -> Result<()> {
+	let stream = UnixStream::connect(socket_path())
+		.await
+		.map_err(|e| eyre!("Could not connect to orbit, is it running? ({e})"))?;
+	let (read, mut write) = stream.into_split();
+	write_line(&mut write, &Request::Subscribe).await?;
+
+	let mut lines = BufReader::new(read).lines();
+	let line = lines
+		.next_line()
+		.await?
+		.ok_or_else(|| eyre!("Connection closed without a response"))?;
+	if let Response::Error { message } = serde_json::from_str(&line)? {
+		return Err(eyre!(message));
+	}
+	while let Some(line) = lines.next_line().await? {
+		on_event(&line);
+	}
+	Ok(())
+}
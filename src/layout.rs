@@ -0,0 +1,49 @@
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Layout {
+	pub panels: Vec<PanelLayout>,
+}
+impl Layout {
+	pub fn load(path: &Path) -> Result<Self> {
+		let file = std::fs::read_to_string(path)?;
+		Ok(serde_json::from_str(&file)?)
+	}
+	pub fn save(&self, path: &Path) -> Result<()> {
+		std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelLayout {
+	pub app_id: Option<String>,
+	pub title: Option<String>,
+	/// Relative to the client root
+	pub translation: [f32; 3],
+	/// Quaternion as `[x, y, z, w]`
+	pub rotation: [f32; 4],
+	/// Toplevel size in pixels
+	pub size: [u32; 2],
+	/// Panels captured by the same acceptor share a group
+	#[serde(default)]
+	pub group: Option<String>,
+}
+/// How big an app's last panel was left, for its next one to start out the same.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppSize {
+	/// Toplevel size in pixels
+	pub size: [u32; 2],
+	pub zoom: f32,
+}
+
+impl PanelLayout {
+	pub fn matches(&self, app_id: Option<&str>) -> bool {
+		self.app_id.is_some() && self.app_id.as_deref() == app_id
+	}
+	pub fn matches_exactly(&self, app_id: Option<&str>, title: Option<&str>) -> bool {
+		self.matches(app_id) && self.title.as_deref() == title
+	}
+}
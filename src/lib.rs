@@ -0,0 +1,411 @@
+pub(crate) mod accent;
+pub mod acceptor_link;
+pub mod acceptor_registry;
+pub(crate) mod aging;
+pub(crate) mod ambient;
+pub(crate) mod anchors;
+pub(crate) mod arbitration;
+pub(crate) mod backing_plate;
+pub(crate) mod capture_preview;
+pub(crate) mod carrier;
+pub mod commands;
+pub mod config;
+pub(crate) mod cursor;
+pub mod decorator;
+pub(crate) mod desk;
+pub(crate) mod desktop_entry;
+pub(crate) mod dev_overlay;
+pub(crate) mod energy;
+pub(crate) mod event_log;
+pub(crate) mod floor_marker;
+pub(crate) mod focus_sweep;
+pub(crate) mod frame_rate;
+pub(crate) mod gestures;
+pub(crate) mod grab_smoothing;
+pub(crate) mod hand;
+pub(crate) mod handle_bar;
+pub mod handoff;
+pub mod health;
+pub(crate) mod hooks;
+pub(crate) mod i18n;
+pub(crate) mod idle;
+pub(crate) mod input;
+pub mod ipc;
+pub mod layout;
+pub(crate) mod links;
+pub(crate) mod material;
+pub(crate) mod measurements;
+pub(crate) mod metrics;
+pub(crate) mod minimap;
+pub mod notes;
+pub mod observer;
+pub mod one_euro;
+pub(crate) mod panel;
+pub(crate) mod panel_model;
+pub(crate) mod pocket;
+pub(crate) mod popups;
+pub mod profile;
+pub(crate) mod proximity_cue;
+pub(crate) mod rails;
+pub(crate) mod rotation_ring;
+pub(crate) mod schedule;
+pub(crate) mod scripting;
+pub(crate) mod scroll;
+pub(crate) mod seated;
+pub(crate) mod settings;
+pub(crate) mod shortcuts;
+pub(crate) mod speech;
+pub(crate) mod state;
+pub(crate) mod theme;
+pub(crate) mod title_bar;
+pub(crate) mod tombstone;
+pub(crate) mod tutorial;
+pub(crate) mod urgency;
+pub mod wall;
+
+use std::{
+	path::{Path, PathBuf},
+	sync::{atomic::Ordering, Arc},
+	time::Instant,
+};
+
+use acceptor_registry::AcceptorRegistry;
+use carrier::{CameraItemUIHandler, EnvironmentItemUIHandler};
+use color_eyre::eyre::{eyre, Result};
+use config::Config;
+use decorator::DecoratorRegistry;
+use health::Problem;
+use i18n::{tr, tr_with};
+use ipc::{IpcMessage, Request, Response};
+use layout::Layout;
+use metrics::Metrics;
+use panel::PanelItemUIHandler;
+use panel_model::FaceRect;
+use scripting::PlacementScript;
+use stardust_xr_fusion::{
+	client::{Client, ClientState, FrameInfo, RootHandler},
+	items::{camera::CameraItem, environment::EnvironmentItem, panel::PanelItem, ItemUI},
+	spatial::{Spatial, Transform},
+	HandlerWrapper,
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// Everything `start` would refuse, reported on the terminal instead of in-headset.
+pub fn check_config(layouts: &[PathBuf]) -> Result<()> {
+	let config = Config::load()?;
+	i18n::init(config.locale.as_deref());
+	let mut problems = config.validate();
+	if let Some(path) = config.placement_script_path().filter(|path| path.exists()) {
+		if let Err(e) = PlacementScript::check(&path) {
+			problems.push(format!("Placement script {}: {e}", path.display()));
+		}
+	}
+	for path in layouts {
+		if let Err(e) = Layout::load(path) {
+			problems.push(format!("Layout {}: {e}", path.display()));
+		}
+	}
+	println!("{}", toml::to_string_pretty(&config)?);
+	if problems.is_empty() {
+		return Ok(());
+	}
+	for problem in &problems {
+		eprintln!("{problem}");
+	}
+	Err(eyre!("{} problems found", problems.len()))
+}
+
+/// Everything that can go wrong here is shown in-headset instead of only in the terminal.
+/// `register_decorators` adds decorators next to the built-in ones, for the config to enable by name.
+pub fn start(
+	client: &Arc<Client>,
+	resource_dir: &Path,
+	ipc_tx: mpsc::UnboundedSender<IpcMessage>,
+	ipc: mpsc::UnboundedReceiver<IpcMessage>,
+	register_decorators: impl FnOnce(&mut DecoratorRegistry),
+) -> Result<Orbit, Vec<Problem>> {
+	let config = Config::load().map_err(|e| {
+		i18n::init(None);
+		vec![Problem::new(
+			tr_with("problem-config", &[("error", &format!("{e:#}"))]),
+			tr("fix-config"),
+		)]
+	})?;
+	i18n::init(config.locale.as_deref());
+	let problems = config
+		.validate()
+		.into_iter()
+		.map(|problem| Problem::new(problem, tr("fix-config")))
+		.collect::<Vec<_>>();
+	if !problems.is_empty() {
+		return Err(problems);
+	}
+	let panel_model = health::check(resource_dir, config.frame_model.as_deref())?;
+	if let (Some(listen), Some(token)) =
+		(config.handoff.listen.clone(), config.handoff.token.clone())
+	{
+		let ipc_tx = ipc_tx.clone();
+		tokio::spawn(async move {
+			if let Err(e) = handoff::serve(&listen, token, ipc_tx).await {
+				eprintln!("Couldn't take hand-offs on {listen}: {e}");
+			}
+		});
+	}
+	schedule::spawn(config.schedule.clone(), ipc_tx).map_err(|e| {
+		vec![Problem::new(
+			tr_with("problem-config", &[("error", &format!("{e:#}"))]),
+			tr("fix-config"),
+		)]
+	})?;
+	Orbit::new(client, config, panel_model.face, register_decorators, ipc).map_err(|e| {
+		vec![Problem::new(
+			tr_with("problem-register", &[("error", &e)]),
+			tr("fix-register"),
+		)]
+	})
+}
+
+/// The root handler for a running shell, built by [`start`].
+pub struct Orbit {
+	/// `None` when another shell already manages these items and Orbit yielded them
+	panel_item_ui: Option<HandlerWrapper<ItemUI<PanelItem>, PanelItemUIHandler>>,
+	environment_item_ui: Option<HandlerWrapper<ItemUI<EnvironmentItem>, EnvironmentItemUIHandler>>,
+	camera_item_ui: Option<HandlerWrapper<ItemUI<CameraItem>, CameraItemUIHandler>>,
+	acceptors: AcceptorRegistry,
+	metrics: Arc<Metrics>,
+	ipc: mpsc::UnboundedReceiver<IpcMessage>,
+}
+impl Orbit {
+	fn new(
+		client: &Arc<Client>,
+		config: Config,
+		face: FaceRect,
+		register_decorators: impl FnOnce(&mut DecoratorRegistry),
+		ipc: mpsc::UnboundedReceiver<IpcMessage>,
+	) -> Result<Self> {
+		let reference = Spatial::create(client.get_root(), Transform::identity(), false)?;
+		let acceptors = AcceptorRegistry::new(config.capture_policy.create(config.accept_distance));
+		let metrics = Arc::new(Metrics::default());
+		if let Some(listen) = config.metrics.listen.clone() {
+			let metrics = metrics.clone();
+			let acceptors = acceptors.clone();
+			tokio::spawn(async move {
+				if let Err(e) = metrics::serve(&listen, metrics, acceptors).await {
+					eprintln!("Couldn't serve metrics on {listen}: {e}");
+				}
+			});
+		}
+		let competing_shell = config.competing_shell;
+		let environment_item_ui = match ItemUI::register(client) {
+			Ok(ui) => Some(ui.wrap(EnvironmentItemUIHandler::new(
+				acceptors.clone(),
+				config.grabbable_settings(),
+			))?),
+			Err(e) => competing_shell.yield_or(e, "environment items")?,
+		};
+		let camera_item_ui = match ItemUI::register(client) {
+			Ok(ui) => Some(ui.wrap(CameraItemUIHandler::new(
+				acceptors.clone(),
+				config.grabbable_settings(),
+			))?),
+			Err(e) => competing_shell.yield_or(e, "camera items")?,
+		};
+		let mut decorators = DecoratorRegistry::with_builtins(&client.get_hmd(), &config.title_bar);
+		register_decorators(&mut decorators);
+		let panel_item_ui = match ItemUI::register(client) {
+			Ok(ui) => Some(ui.wrap(PanelItemUIHandler::new(
+				reference,
+				client.get_hmd().alias(),
+				acceptors.clone(),
+				config,
+				face,
+				decorators,
+			))?),
+			Err(e) => competing_shell.yield_or(e, "panels")?,
+		};
+		Ok(Orbit {
+			panel_item_ui,
+			environment_item_ui,
+			camera_item_ui,
+			acceptors,
+			metrics,
+			ipc,
+		})
+	}
+
+	fn handle_request(&mut self, request: Request, response: oneshot::Sender<Response>) {
+		match request {
+			Request::AcceptorMetrics => {
+				let _ = response.send(Response::AcceptorMetrics {
+					metrics: self.acceptors.metrics(),
+				});
+			}
+			Request::Subscribe => {
+				let _ = response.send(Response::Error {
+					message: "Subscriptions are only served to IPC connections".to_string(),
+				});
+			}
+			Request::Status => {
+				let (energy_saver, asleep) =
+					self.panel_item_ui.as_ref().map_or((false, false), |ui| {
+						let ui = ui.lock_wrapped();
+						(ui.energy_saver_active(), ui.asleep())
+					});
+				let _ = response.send(Response::Status {
+					energy_saver,
+					asleep,
+					panels_yielded: self.panel_item_ui.is_none(),
+				});
+			}
+			request => {
+				let Some(panel_item_ui) = &self.panel_item_ui else {
+					let _ = response.send(Response::Error {
+						message: "Orbit yielded panels to another shell".to_string(),
+					});
+					return;
+				};
+				Self::handle_panel_request(panel_item_ui, request, response);
+			}
+		}
+	}
+	/// Requests that only make sense while Orbit manages panels itself.
+	fn handle_panel_request(
+		panel_item_ui: &HandlerWrapper<ItemUI<PanelItem>, PanelItemUIHandler>,
+		request: Request,
+		response: oneshot::Sender<Response>,
+	) {
+		match request {
+			Request::ExportLayout => {
+				let layout = panel_item_ui.lock_wrapped().export_layout();
+				tokio::spawn(async move {
+					let _ = response.send(Response::Layout {
+						layout: layout.await,
+					});
+				});
+			}
+			Request::ImportLayout { layout } => {
+				panel_item_ui.lock_wrapped().import_layout(layout);
+				let _ = response.send(Response::Ok);
+			}
+			Request::SetStreamingMode { enabled } => {
+				panel_item_ui.lock_wrapped().streaming_mode = enabled;
+				let _ = response.send(Response::Ok);
+			}
+			Request::SetTheme { theme } => {
+				panel_item_ui.lock_wrapped().set_theme(theme);
+				let _ = response.send(Response::Ok);
+			}
+			Request::SetAmbient { level } => {
+				panel_item_ui.lock_wrapped().set_ambient_level(level);
+				let _ = response.send(Response::Ok);
+			}
+			Request::SetSeated { seated } => {
+				panel_item_ui.lock_wrapped().set_seated(seated);
+				let _ = response.send(Response::Ok);
+			}
+			Request::SetTutorial { enabled } => {
+				let result = panel_item_ui.lock_wrapped().set_tutorial(enabled);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(e) => Response::Error {
+						message: e.to_string(),
+					},
+				});
+			}
+			Request::SetWall { wall } => {
+				let result = panel_item_ui.lock_wrapped().set_wall(wall);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(e) => Response::Error {
+						message: e.to_string(),
+					},
+				});
+			}
+			Request::SetWallTiling { tiling } => {
+				let result = panel_item_ui.lock_wrapped().set_wall_tiling(tiling);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::Wm { action } => {
+				let result = panel_item_ui.lock_wrapped().wm_command(action);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::Note { action } => {
+				let result = panel_item_ui.lock_wrapped().note_command(action);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::AppAction { app_id, action } => {
+				let result = panel_item_ui.lock_wrapped().app_action(&app_id, action);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::GatherApp { app_id } => {
+				let result = panel_item_ui.lock_wrapped().gather_app(&app_id);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::SetAppBadge { app_id, count } => {
+				let result = panel_item_ui.lock_wrapped().set_app_badge(&app_id, count);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::ListPanels => {
+				let _ = response.send(Response::Panels {
+					panels: panel_item_ui.lock_wrapped().summaries(),
+				});
+			}
+			Request::Panel { uid, action } => {
+				let result = panel_item_ui
+					.lock_wrapped()
+					.panel_action(uid.as_deref(), action);
+				let _ = response.send(match result {
+					Ok(()) => Response::Ok,
+					Err(message) => Response::Error { message },
+				});
+			}
+			Request::AcceptorMetrics | Request::Subscribe | Request::Status => {
+				unreachable!("answered without the panel UI")
+			}
+		}
+	}
+}
+impl RootHandler for Orbit {
+	fn frame(&mut self, info: FrameInfo) {
+		while let Ok((request, response)) = self.ipc.try_recv() {
+			self.handle_request(request, response);
+		}
+		let started = Instant::now();
+		if let Some(panel_item_ui) = &self.panel_item_ui {
+			let mut panel_item_ui = panel_item_ui.lock_wrapped();
+			panel_item_ui.frame(&info);
+			self.metrics
+				.panels
+				.store(panel_item_ui.panel_count() as u64, Ordering::Relaxed);
+		}
+		if let Some(environment_item_ui) = &self.environment_item_ui {
+			environment_item_ui.lock_wrapped().frame(&info);
+		}
+		if let Some(camera_item_ui) = &self.camera_item_ui {
+			camera_item_ui.lock_wrapped().frame(&info);
+		}
+		self.metrics.frame_duration.observe(started.elapsed());
+	}
+
+	fn save_state(&mut self) -> ClientState {
+		ClientState::default()
+	}
+}
@@ -0,0 +1,156 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines, Text, TextStyle},
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use tokio::sync::mpsc;
+
+const LINK_THICKNESS: f32 = 0.002;
+const LABEL_HEIGHT: f32 = 0.008;
+
+/// A drawn relationship between two apps' panels, like "spec" → "implementation".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanelLink {
+	/// App IDs, so links come back when the apps are opened again
+	pub from: String,
+	pub to: String,
+	#[serde(default)]
+	pub label: Option<String>,
+}
+impl PanelLink {
+	pub fn touches(&self, app_id: &str) -> bool {
+		self.from == app_id || self.to == app_id
+	}
+}
+
+struct LinkVisual {
+	lines: Lines,
+	label: Option<Text>,
+}
+
+/// Lines between linked panels, following them around while both are open.
+pub struct PanelLinks {
+	reference: Spatial,
+	links: Vec<PanelLink>,
+	visuals: Vec<Option<LinkVisual>>,
+	/// Bumped whenever links change so answers to older queries get dropped
+	generation: u64,
+	ends_tx: mpsc::UnboundedSender<(u64, usize, Option<[Vec3; 2]>)>,
+	ends_rx: mpsc::UnboundedReceiver<(u64, usize, Option<[Vec3; 2]>)>,
+}
+impl PanelLinks {
+	pub fn new(reference: &Spatial, links: Vec<PanelLink>) -> Self {
+		let (ends_tx, ends_rx) = mpsc::unbounded_channel();
+		PanelLinks {
+			reference: reference.alias(),
+			visuals: links.iter().map(|_| None).collect(),
+			links,
+			generation: 0,
+			ends_tx,
+			ends_rx,
+		}
+	}
+	pub fn links(&self) -> &[PanelLink] {
+		&self.links
+	}
+
+	/// Replaces an existing link between the same two apps.
+	pub fn add(&mut self, link: PanelLink) {
+		self.links
+			.retain(|l| !(l.from == link.from && l.to == link.to));
+		self.links.push(link);
+		self.changed();
+	}
+	/// Returns whether any link was removed.
+	pub fn remove_touching(&mut self, app_id: &str) -> bool {
+		let len = self.links.len();
+		self.links.retain(|l| !l.touches(app_id));
+		let removed = self.links.len() != len;
+		if removed {
+			self.changed();
+		}
+		removed
+	}
+	fn changed(&mut self) {
+		self.generation += 1;
+		self.visuals = self.links.iter().map(|_| None).collect();
+	}
+
+	/// Stops drawing every link until the next update.
+	pub fn hide(&mut self) {
+		self.generation += 1;
+		self.visuals.iter_mut().for_each(|visual| *visual = None);
+	}
+
+	/// `panel` finds the content parent of an open panel by app ID.
+	pub fn update(&mut self, panel: impl Fn(&str) -> Option<Spatial>) {
+		while let Ok((generation, index, ends)) = self.ends_rx.try_recv() {
+			if generation != self.generation {
+				continue;
+			}
+			match ends {
+				Some([from, to]) => self.show(index, from, to),
+				None => self.visuals[index] = None,
+			}
+		}
+		for (index, link) in self.links.iter().enumerate() {
+			let (Some(from), Some(to)) = (panel(&link.from), panel(&link.to)) else {
+				self.visuals[index] = None;
+				continue;
+			};
+			let reference = self.reference.alias();
+			let ends_tx = self.ends_tx.clone();
+			let generation = self.generation;
+			tokio::spawn(async move {
+				let from = from.get_transform(&reference).await.ok();
+				let to = to.get_transform(&reference).await.ok();
+				let ends = from
+					.and_then(|t| t.translation)
+					.zip(to.and_then(|t| t.translation))
+					.map(|(from, to)| [from.into(), to.into()]);
+				let _ = ends_tx.send((generation, index, ends));
+			});
+		}
+	}
+
+	fn show(&mut self, index: usize, from: Vec3, to: Vec3) {
+		let line = Line {
+			points: [from, to]
+				.into_iter()
+				.map(|point| LinePoint {
+					point: point.into(),
+					thickness: LINK_THICKNESS,
+					color: rgba_linear!(1.0, 1.0, 1.0, 0.6),
+				})
+				.collect(),
+			cyclic: false,
+		};
+		let label_transform = Transform::from_translation((from + to) / 2.0 + Vec3::Y * 0.01);
+		if let Some(visual) = &self.visuals[index] {
+			let _ = visual.lines.set_lines(&[line]);
+			if let Some(label) = &visual.label {
+				let _ = label.set_relative_transform(&self.reference, label_transform);
+			}
+			return;
+		}
+		let Ok(lines) = Lines::create(&self.reference, Transform::identity(), &[line]) else {
+			return;
+		};
+		let label = self.links[index].label.as_ref().and_then(|label| {
+			Text::create(
+				&self.reference,
+				label_transform,
+				label,
+				TextStyle {
+					character_height: LABEL_HEIGHT,
+					..Default::default()
+				},
+			)
+			.ok()
+		});
+		self.visuals[index] = Some(LinkVisual { lines, label });
+	}
+}
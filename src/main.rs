@@ -1,49 +1,141 @@
-pub mod panel;
+use std::path::{Path, PathBuf};
 
-use std::sync::Arc;
-
-use color_eyre::eyre::Result;
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::{eyre, Result};
 use manifest_dir_macros::directory_relative_path;
-use panel::PanelItemUIHandler;
-use stardust_xr_fusion::{
-	client::{Client, ClientState, FrameInfo, RootHandler},
-	items::{panel::PanelItem, ItemUI},
-	HandlerWrapper,
+use orbit::{
+	check_config,
+	commands::WmCommand,
+	config::Config,
+	handoff,
+	health::ErrorPanel,
+	ipc::{self, Request, Response},
+	layout::Layout,
+	notes::NoteCommand,
+	observer::ObserverRoot,
+	profile, start,
 };
+use stardust_xr_fusion::client::Client;
+use tokio::sync::mpsc;
+
+#[derive(Parser)]
+#[command(about = "Floating panel shell for Stardust XR")]
+struct Args {
+	/// Keep config, state and the IPC socket separate under this name
+	#[arg(long, global = true)]
+	profile: Option<String>,
+	/// Only show a map of panels and acceptors, leaving them to whichever shell manages them
+	#[arg(long)]
+	observe: bool,
+	#[command(subcommand)]
+	command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Save the running instance's panel arrangement to a file
+	ExportLayout { file: PathBuf },
+	/// Arrange the running instance's panels from a file
+	ImportLayout { file: PathBuf },
+	/// Send the running instance's panel arrangement to Orbit on another machine, at its handoff.listen address
+	/// and with the same handoff.token
+	Handoff { to: String },
+	/// Print the running instance's panel events as JSON lines, until it exits
+	Subscribe,
+	/// Run a window management command on the running instance
+	Wm {
+		#[command(subcommand)]
+		command: WmCommand,
+	},
+	/// Create and type into Orbit's own sticky notes
+	Note {
+		#[command(subcommand)]
+		command: NoteCommand,
+	},
+	/// Check the config, placement script and any layout files without starting, printing the effective config
+	CheckConfig { layouts: Vec<PathBuf> },
+}
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
 	color_eyre::install()?;
+	let args = Args::parse();
+	profile::init(args.profile)?;
+	match args.command {
+		Some(Command::ExportLayout { file }) => {
+			let Response::Layout { layout } = ipc::send(&Request::ExportLayout).await? else {
+				return Err(eyre!("Unexpected response"));
+			};
+			layout.save(&file)?;
+			println!(
+				"Exported {} panels to {}",
+				layout.panels.len(),
+				file.display()
+			);
+			return Ok(());
+		}
+		Some(Command::ImportLayout { file }) => {
+			let layout = Layout::load(&file)?;
+			ipc::send(&Request::ImportLayout { layout }).await?;
+			return Ok(());
+		}
+		Some(Command::Handoff { to }) => {
+			let token = Config::load()?.handoff.token.ok_or_else(|| {
+				eyre!("No hand-off token is set, set handoff.token to the one {to} uses")
+			})?;
+			let Response::Layout { layout } = ipc::send(&Request::ExportLayout).await? else {
+				return Err(eyre!("Unexpected response"));
+			};
+			handoff::send(&to, &token, &layout).await?;
+			println!("Handed off {} panels to {to}", layout.panels.len());
+			return Ok(());
+		}
+		Some(Command::Subscribe) => {
+			return ipc::subscribe(|line| println!("{line}")).await;
+		}
+		Some(Command::Wm { command }) => {
+			ipc::send(&Request::Wm { action: command }).await?;
+			return Ok(());
+		}
+		Some(Command::Note { command }) => {
+			ipc::send(&Request::Note { action: command }).await?;
+			return Ok(());
+		}
+		Some(Command::CheckConfig { layouts }) => return check_config(&layouts),
+		None => (),
+	}
+
 	let (client, event_loop) = Client::connect_with_async_loop().await?;
-	client.set_base_prefixes(&[directory_relative_path!("res")]);
+	let resource_dir = directory_relative_path!("res");
+	client.set_base_prefixes(&[resource_dir]);
+	if args.observe {
+		let _wrapped_root = client.wrap_root(ObserverRoot::create(&client)?)?;
+		tokio::select! {
+			_ = tokio::signal::ctrl_c() => (),
+			e = event_loop => e??,
+		}
+		return Ok(());
+	}
+
+	let (ipc_tx, ipc_rx) = mpsc::unbounded_channel();
+	let _ipc = ipc::serve(ipc_tx.clone())?;
 
-	let _wrapped_root = client.wrap_root(Orbit::new(&client)?)?;
+	let mut _wrapped_root = None;
+	let mut _error_panel = None;
+	match start(&client, Path::new(resource_dir), ipc_tx, ipc_rx, |_| ()) {
+		Ok(orbit) => _wrapped_root = Some(client.wrap_root(orbit)?),
+		Err(problems) => {
+			for problem in &problems {
+				eprintln!("{problem}");
+			}
+			_error_panel = Some(ErrorPanel::create(client.get_root(), &problems)?);
+		}
+	}
 
 	tokio::select! {
 		_ = tokio::signal::ctrl_c() => (),
 		e = event_loop => e??,
 	}
+	let _ = std::fs::remove_file(ipc::socket_path());
 	Ok(())
 }
-
-struct Orbit {
-	panel_item_ui: HandlerWrapper<ItemUI<PanelItem>, PanelItemUIHandler>,
-}
-impl Orbit {
-	fn new(client: &Arc<Client>) -> Result<Self> {
-		let panel_item_ui = ItemUI::register(client)?;
-		let panel_item_ui_handler = PanelItemUIHandler::new();
-		Ok(Orbit {
-			panel_item_ui: panel_item_ui.wrap(panel_item_ui_handler)?,
-		})
-	}
-}
-impl RootHandler for Orbit {
-	fn frame(&mut self, info: FrameInfo) {
-		self.panel_item_ui.lock_wrapped().frame(&info);
-	}
-
-	fn save_state(&mut self) -> ClientState {
-		ClientState::default()
-	}
-}
@@ -0,0 +1,37 @@
+use rustc_hash::FxHashMap;
+use stardust_xr_fusion::drawable::{MaterialParameter, ModelPart, ModelPartAspect};
+
+/// The material parameters a model part should end up with, sent at most once per frame and only when changed,
+/// since several things can restyle a part in one frame and each call is a message to the server.
+pub struct MaterialCache {
+	part: ModelPart,
+	desired: FxHashMap<&'static str, MaterialParameter>,
+	sent: FxHashMap<&'static str, MaterialParameter>,
+}
+impl MaterialCache {
+	pub fn new(part: ModelPart) -> Self {
+		MaterialCache {
+			part,
+			desired: FxHashMap::default(),
+			sent: FxHashMap::default(),
+		}
+	}
+
+	pub fn part(&self) -> &ModelPart {
+		&self.part
+	}
+
+	/// Only the last value set before `flush` is sent.
+	pub fn set(&mut self, parameter: &'static str, value: MaterialParameter) {
+		self.desired.insert(parameter, value);
+	}
+	pub fn flush(&mut self) {
+		for (parameter, value) in self.desired.drain() {
+			if self.sent.get(parameter) == Some(&value) {
+				continue;
+			}
+			let _ = self.part.set_material_parameter(parameter, value.clone());
+			self.sent.insert(parameter, value);
+		}
+	}
+}
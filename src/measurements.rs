@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeType,
+	spatial::{SpatialAspect, SpatialRef, Transform},
+};
+
+use crate::{
+	decorator::{Decorator, DecoratorContext},
+	i18n::tr_with,
+};
+
+const TEXT_HEIGHT: f32 = 0.004;
+const TEXT_MARGIN: f32 = 0.004;
+/// Seconds between distance queries
+const REFRESH_INTERVAL: f32 = 0.5;
+
+/// Distance from the head, physical size and pixel size under the panel, for setting up and bug reports.
+pub struct Measurements {
+	hmd: SpatialRef,
+	text: Option<Text>,
+	label: String,
+	distance: Arc<Mutex<Option<f32>>>,
+	last_query: f32,
+}
+impl Measurements {
+	pub fn new(hmd: SpatialRef) -> Self {
+		Measurements {
+			hmd,
+			text: None,
+			label: String::new(),
+			distance: Default::default(),
+			last_query: f32::NEG_INFINITY,
+		}
+	}
+	fn transform(ctx: &DecoratorContext) -> Transform {
+		Transform::from_translation([0.0, -ctx.size.y / 2.0 - TEXT_MARGIN - TEXT_HEIGHT, 0.0])
+	}
+	fn label(&self, ctx: &DecoratorContext) -> String {
+		let distance = match *self.distance.lock().unwrap() {
+			Some(distance) => format!("{:.0}", distance * 100.0),
+			None => "?".to_string(),
+		};
+		tr_with(
+			"measurements",
+			&[
+				("distance", &distance),
+				("width", &format!("{:.1}", ctx.size.x * 100.0)),
+				("height", &format!("{:.1}", ctx.size.y * 100.0)),
+				("pixel_width", &ctx.pixel_size.x),
+				("pixel_height", &ctx.pixel_size.y),
+			],
+		)
+	}
+}
+impl Decorator for Measurements {
+	fn created(&mut self, ctx: &DecoratorContext) {
+		self.label = self.label(ctx);
+		self.text = Text::create(
+			ctx.panel_item,
+			Self::transform(ctx),
+			&self.label,
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)
+		.ok();
+	}
+	fn resized(&mut self, ctx: &DecoratorContext) {
+		if let Some(text) = &self.text {
+			let _ = text.set_local_transform(Self::transform(ctx));
+		}
+	}
+	fn frame(&mut self, ctx: &DecoratorContext, info: &FrameInfo) {
+		let now = info.elapsed as f32;
+		if now - self.last_query >= REFRESH_INTERVAL {
+			self.last_query = now;
+			let panel_item = ctx.panel_item.alias();
+			let hmd = self.hmd.alias();
+			let distance = self.distance.clone();
+			tokio::spawn(async move {
+				let Some(translation) = panel_item
+					.get_transform(&hmd)
+					.await
+					.ok()
+					.and_then(|t| t.translation)
+				else {
+					return;
+				};
+				*distance.lock().unwrap() = Some(glam::Vec3::from(translation).length());
+			});
+		}
+		let label = self.label(ctx);
+		if label == self.label {
+			return;
+		}
+		self.label = label;
+		if let Some(text) = &self.text {
+			let _ = text.set_text(&self.label);
+		}
+	}
+}
@@ -0,0 +1,174 @@
+use std::{
+	fmt::Write,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::Duration,
+};
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use tokio::{
+	io::{AsyncReadExt, AsyncWriteExt},
+	net::{TcpListener, TcpStream},
+	task::JoinHandle,
+};
+
+use crate::acceptor_registry::{AcceptorMetricsSnapshot, AcceptorRegistry};
+
+/// Histogram bucket bounds, in seconds
+const BUCKETS: [f64; 8] = [0.0005, 0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+	/// Address to serve Prometheus metrics on like `127.0.0.1:9464`, off when unset
+	pub listen: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Histogram {
+	buckets: [AtomicU64; BUCKETS.len()],
+	count: AtomicU64,
+	sum_micros: AtomicU64,
+}
+impl Histogram {
+	pub fn observe(&self, duration: Duration) {
+		let seconds = duration.as_secs_f64();
+		for (bucket, bound) in self.buckets.iter().zip(BUCKETS) {
+			if seconds <= bound {
+				bucket.fetch_add(1, Ordering::Relaxed);
+			}
+		}
+		self.count.fetch_add(1, Ordering::Relaxed);
+		self.sum_micros
+			.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+	}
+	/// Only the series, the family header is written once by the caller.
+	fn render(&self, out: &mut String, name: &str, labels: &str) {
+		let (le_prefix, braced) = if labels.is_empty() {
+			(String::new(), String::new())
+		} else {
+			(format!("{labels},"), format!("{{{labels}}}"))
+		};
+		for (bucket, bound) in self.buckets.iter().zip(BUCKETS) {
+			let _ = writeln!(
+				out,
+				"{name}_bucket{{{le_prefix}le=\"{bound}\"}} {}",
+				bucket.load(Ordering::Relaxed)
+			);
+		}
+		let count = self.count.load(Ordering::Relaxed);
+		let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+		let _ = writeln!(out, "{name}_bucket{{{le_prefix}le=\"+Inf\"}} {count}");
+		let _ = writeln!(out, "{name}_sum{braced} {sum}");
+		let _ = writeln!(out, "{name}_count{braced} {count}");
+	}
+}
+
+/// Numbers about Orbit itself, as opposed to the acceptor counters kept per item type.
+#[derive(Debug, Default)]
+pub struct Metrics {
+	pub panels: AtomicU64,
+	pub frame_duration: Histogram,
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+	let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+fn render(metrics: &Metrics, acceptors: &AcceptorRegistry) -> String {
+	let mut out = String::new();
+	header(
+		&mut out,
+		"orbit_panels",
+		"Panels Orbit is managing",
+		"gauge",
+	);
+	let _ = writeln!(
+		out,
+		"orbit_panels {}",
+		metrics.panels.load(Ordering::Relaxed)
+	);
+	let name = "orbit_frame_duration_seconds";
+	header(
+		&mut out,
+		name,
+		"Time spent in Orbit's frame handler",
+		"histogram",
+	);
+	metrics.frame_duration.render(&mut out, name, "");
+
+	let snapshots = acceptors.metrics();
+	let mut family =
+		|name: &str, help: &str, kind: &str, value: fn(&AcceptorMetricsSnapshot) -> u64| {
+			header(&mut out, name, help, kind);
+			for snapshot in &snapshots {
+				let _ = writeln!(
+					out,
+					"{name}{{item_type=\"{}\"}} {}",
+					snapshot.item_type,
+					value(snapshot)
+				);
+			}
+		};
+	family(
+		"orbit_acceptors",
+		"Acceptors currently registered",
+		"gauge",
+		|s| s.acceptors as u64,
+	);
+	family(
+		"orbit_capture_requests_total",
+		"Captures Orbit asked an acceptor for",
+		"counter",
+		|s| s.capture_requests,
+	);
+	family("orbit_captures_total", "Items captured", "counter", |s| {
+		s.captures
+	});
+	family("orbit_releases_total", "Items released", "counter", |s| {
+		s.releases
+	});
+
+	let name = "orbit_distance_query_duration_seconds";
+	header(
+		&mut out,
+		name,
+		"Time for one round of distance queries to every acceptor",
+		"histogram",
+	);
+	for (item_type, metrics) in acceptors.raw_metrics() {
+		metrics
+			.distance_query
+			.render(&mut out, name, &format!("item_type=\"{item_type}\""));
+	}
+	out
+}
+
+/// Answer every HTTP request with the current metrics, whatever the path.
+pub async fn serve(
+	listen: &str,
+	metrics: Arc<Metrics>,
+	acceptors: AcceptorRegistry,
+) -> Result<JoinHandle<()>> {
+	let listener = TcpListener::bind(listen).await?;
+	Ok(tokio::spawn(async move {
+		while let Ok((stream, _)) = listener.accept().await {
+			let body = render(&metrics, &acceptors);
+			tokio::spawn(respond(stream, body));
+		}
+	}))
+}
+async fn respond(mut stream: TcpStream, body: String) {
+	let mut request = [0; 1024];
+	if stream.read(&mut request).await.is_err() {
+		return;
+	}
+	let response = format!(
+		"HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+		body.len()
+	);
+	let _ = stream.write_all(response.as_bytes()).await;
+}
@@ -0,0 +1,141 @@
+use std::sync::{Arc, Mutex};
+
+use glam::Vec3;
+use rustc_hash::FxHashMap;
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	fields::UnknownField,
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::button::{Button, ButtonSettings};
+
+use crate::observer::pose;
+
+/// Where the minimap floats relative to the reference space, low and off to the side
+const MINIMAP_POSITION: [f32; 3] = [0.2, -0.2, -0.35];
+/// Meters of map per meter of space
+const MINIMAP_SCALE: f32 = 0.04;
+const DOT_SIZE: f32 = 0.006;
+/// Seconds between position checks
+const REFRESH_INTERVAL: f32 = 0.5;
+
+#[derive(Default)]
+struct Positions {
+	panels: Vec<(String, Vec3)>,
+	acceptors: Vec<Vec3>,
+}
+
+/// A pokeable dot for every panel around the user and a cross for every acceptor.
+pub struct Minimap {
+	root: Spatial,
+	reference: Spatial,
+	dots: FxHashMap<String, Button>,
+	acceptors: Lines,
+	last_refresh: f32,
+	refreshed: Arc<Mutex<Option<Positions>>>,
+}
+impl Minimap {
+	pub fn create(reference: &Spatial) -> Result<Self, NodeError> {
+		let root = Spatial::create(
+			reference,
+			Transform::from_translation(MINIMAP_POSITION),
+			false,
+		)?;
+		let acceptors = Lines::create(&root, Transform::identity(), &[])?;
+		Ok(Minimap {
+			root,
+			reference: reference.alias(),
+			dots: FxHashMap::default(),
+			acceptors,
+			last_refresh: f32::NEG_INFINITY,
+			refreshed: Default::default(),
+		})
+	}
+
+	/// Returns the uid of the panel whose dot was poked.
+	pub fn update(
+		&mut self,
+		now: f32,
+		panels: Vec<(String, Spatial)>,
+		acceptors: Vec<UnknownField>,
+	) -> Option<String> {
+		if let Some(positions) = self.refreshed.lock().unwrap().take() {
+			self.place(positions);
+		}
+		if now - self.last_refresh >= REFRESH_INTERVAL {
+			self.last_refresh = now;
+			self.refresh(panels, acceptors);
+		}
+		let mut poked = None;
+		for (uid, dot) in &mut self.dots {
+			dot.update();
+			if dot.pressed() {
+				poked = Some(uid.clone());
+			}
+		}
+		poked
+	}
+
+	fn refresh(&self, panels: Vec<(String, Spatial)>, acceptors: Vec<UnknownField>) {
+		let reference = self.reference.alias();
+		let refreshed = self.refreshed.clone();
+		tokio::spawn(async move {
+			let mut positions = Positions::default();
+			for (uid, panel) in panels {
+				if let Some((position, _)) = pose(&panel, &reference).await {
+					positions.panels.push((uid, position));
+				}
+			}
+			for field in acceptors {
+				if let Some((position, _)) = pose(&field, &reference).await {
+					positions.acceptors.push(position);
+				}
+			}
+			*refreshed.lock().unwrap() = Some(positions);
+		});
+	}
+
+	fn place(&mut self, positions: Positions) {
+		self.dots
+			.retain(|uid, _| positions.panels.iter().any(|(p, _)| p == uid));
+		for (uid, position) in positions.panels {
+			let transform = Transform::from_translation(position * MINIMAP_SCALE);
+			if let Some(dot) = self.dots.get(&uid) {
+				let _ = dot.touch_plane().root().set_local_transform(transform);
+				continue;
+			}
+			if let Ok(dot) = Button::create(
+				&self.root,
+				transform,
+				[DOT_SIZE; 2],
+				ButtonSettings::default(),
+			) {
+				self.dots.insert(uid, dot);
+			}
+		}
+		let crosses = positions
+			.acceptors
+			.into_iter()
+			.flat_map(|position| {
+				let center = position * MINIMAP_SCALE;
+				[Vec3::X, Vec3::Z].map(|axis| Line {
+					points: [
+						center - axis * DOT_SIZE / 2.0,
+						center + axis * DOT_SIZE / 2.0,
+					]
+					.into_iter()
+					.map(|point| LinePoint {
+						point: point.into(),
+						thickness: 0.0005,
+						color: rgba_linear!(0.2, 0.8, 1.0, 1.0),
+					})
+					.collect(),
+					cyclic: false,
+				})
+			})
+			.collect::<Vec<_>>();
+		let _ = self.acceptors.set_lines(&crosses);
+	}
+}
@@ -0,0 +1,246 @@
+use clap::Subcommand;
+use glam::{Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines, Text, TextAspect, TextStyle},
+	fields::BoxField,
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, SpatialRef, Transform},
+};
+use stardust_xr_molecules::{Grabbable, GrabbableSettings};
+use tokio::sync::mpsc;
+
+use crate::i18n::tr;
+
+const NOTE_SIZE: [f32; 2] = [0.08, 0.06];
+const NOTE_THICKNESS: f32 = 0.005;
+const TEXT_HEIGHT: f32 = 0.005;
+/// How far in front of the head new notes show up, in meters
+const SPAWN_DISTANCE: f32 = 0.4;
+
+/// Edits to notes, meant to be sent by a keyboard relay as keys are typed.
+#[derive(Debug, Clone, Subcommand, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum NoteCommand {
+	/// Put a new note in front of the head and make it the active one
+	New,
+	/// Add text to the end of the active note
+	Type {
+		text: String,
+	},
+	Newline,
+	/// Remove the last character of the active note
+	Backspace,
+	/// Throw away the active note
+	Delete,
+}
+
+/// A note as it's kept in the state file, relative to the client root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteState {
+	pub text: String,
+	pub translation: [f32; 3],
+	/// Quaternion as `[x, y, z, w]`
+	pub rotation: [f32; 4],
+}
+
+/// A text card that can be grabbed around, for when no notes app is running.
+struct StickyNote {
+	state: NoteState,
+	_field: BoxField,
+	grabbable: Grabbable,
+	_outline: Lines,
+	text: Text,
+}
+impl StickyNote {
+	fn create(
+		reference: &Spatial,
+		state: NoteState,
+		settings: GrabbableSettings,
+	) -> Result<Self, NodeError> {
+		let [w, h] = NOTE_SIZE;
+		let field = BoxField::create(reference, Transform::identity(), [w, h, NOTE_THICKNESS])?;
+		let grabbable = Grabbable::create(
+			reference,
+			Transform::from_translation_rotation(
+				state.translation,
+				Quat::from_array(state.rotation),
+			),
+			&field,
+			settings,
+		)?;
+		field.set_spatial_parent(grabbable.content_parent())?;
+		let outline = Lines::create(
+			grabbable.content_parent(),
+			Transform::identity(),
+			&[Line {
+				points: [[-w, h], [w, h], [w, -h], [-w, -h]]
+					.into_iter()
+					.map(|[x, y]| LinePoint {
+						point: [x / 2.0, y / 2.0, 0.0].into(),
+						thickness: 0.001,
+						color: rgba_linear!(1.0, 0.9, 0.4, 1.0),
+					})
+					.collect(),
+				cyclic: true,
+			}],
+		)?;
+		let text = Text::create(
+			grabbable.content_parent(),
+			Transform::from_translation([0.0, 0.0, NOTE_THICKNESS / 2.0]),
+			&Self::display_text(&state.text),
+			TextStyle {
+				character_height: TEXT_HEIGHT,
+				..Default::default()
+			},
+		)?;
+		Ok(StickyNote {
+			state,
+			_field: field,
+			grabbable,
+			_outline: outline,
+			text,
+		})
+	}
+	fn display_text(text: &str) -> String {
+		if text.is_empty() {
+			tr("note-empty")
+		} else {
+			text.to_string()
+		}
+	}
+	fn edit(&mut self, f: impl FnOnce(&mut String)) {
+		f(&mut self.state.text);
+		let _ = self.text.set_text(&Self::display_text(&self.state.text));
+	}
+}
+
+/// Orbit's own sticky notes, kept in the state file.
+pub struct Notes {
+	reference: Spatial,
+	hmd: SpatialRef,
+	settings: GrabbableSettings,
+	notes: Vec<StickyNote>,
+	/// The one typed into, the last one created or grabbed
+	active: Option<usize>,
+	/// Set when a note moved or changed and the state file is out of date
+	dirty: bool,
+	spawn_tx: mpsc::UnboundedSender<NoteState>,
+	spawn_rx: mpsc::UnboundedReceiver<NoteState>,
+	pose_tx: mpsc::UnboundedSender<(usize, [f32; 3], [f32; 4])>,
+	pose_rx: mpsc::UnboundedReceiver<(usize, [f32; 3], [f32; 4])>,
+}
+impl Notes {
+	pub fn new(
+		reference: &Spatial,
+		hmd: &SpatialRef,
+		settings: GrabbableSettings,
+		saved: Vec<NoteState>,
+	) -> Self {
+		let (spawn_tx, spawn_rx) = mpsc::unbounded_channel();
+		let (pose_tx, pose_rx) = mpsc::unbounded_channel();
+		let notes = saved
+			.into_iter()
+			.filter_map(|state| StickyNote::create(reference, state, settings.clone()).ok())
+			.collect();
+		Notes {
+			reference: reference.alias(),
+			hmd: hmd.alias(),
+			settings,
+			notes,
+			active: None,
+			dirty: false,
+			spawn_tx,
+			spawn_rx,
+			pose_tx,
+			pose_rx,
+		}
+	}
+	pub fn states(&self) -> Vec<NoteState> {
+		self.notes.iter().map(|note| note.state.clone()).collect()
+	}
+
+	pub fn command(&mut self, command: NoteCommand) -> Result<(), String> {
+		if let NoteCommand::New = command {
+			self.spawn();
+			return Ok(());
+		}
+		let index = self.active.ok_or("No note is active")?;
+		let note = &mut self.notes[index];
+		match command {
+			NoteCommand::New => unreachable!(),
+			NoteCommand::Type { text } => note.edit(|t| t.push_str(&text)),
+			NoteCommand::Newline => note.edit(|t| t.push('\n')),
+			NoteCommand::Backspace => note.edit(|t| {
+				t.pop();
+			}),
+			NoteCommand::Delete => {
+				self.notes.remove(index);
+				self.active = None;
+			}
+		}
+		self.dirty = true;
+		Ok(())
+	}
+	fn spawn(&self) {
+		let hmd = self.hmd.alias();
+		let reference = self.reference.alias();
+		let spawn_tx = self.spawn_tx.clone();
+		tokio::spawn(async move {
+			let Ok(head) = hmd.get_transform(&reference).await else {
+				return;
+			};
+			let translation = head.translation.map_or(Vec3::ZERO, Vec3::from);
+			let rotation = head.rotation.map_or(Quat::IDENTITY, Quat::from);
+			let _ = spawn_tx.send(NoteState {
+				text: String::new(),
+				translation: (translation + rotation * Vec3::NEG_Z * SPAWN_DISTANCE).into(),
+				rotation: rotation.to_array(),
+			});
+		});
+	}
+
+	/// Returns whether the notes changed since the last save.
+	pub fn update(&mut self, info: &FrameInfo) -> bool {
+		while let Ok(state) = self.spawn_rx.try_recv() {
+			if let Ok(note) = StickyNote::create(&self.reference, state, self.settings.clone()) {
+				self.notes.push(note);
+				self.active = Some(self.notes.len() - 1);
+				self.dirty = true;
+			}
+		}
+		while let Ok((index, translation, rotation)) = self.pose_rx.try_recv() {
+			if let Some(note) = self.notes.get_mut(index) {
+				note.state.translation = translation;
+				note.state.rotation = rotation;
+				self.dirty = true;
+			}
+		}
+		for (index, note) in self.notes.iter_mut().enumerate() {
+			let _ = note.grabbable.update(info);
+			if note.grabbable.grab_action().actor_started() {
+				self.active = Some(index);
+			}
+			if note.grabbable.grab_action().actor_stopped() {
+				let content = note.grabbable.content_parent().alias();
+				let reference = self.reference.alias();
+				let pose_tx = self.pose_tx.clone();
+				tokio::spawn(async move {
+					let Ok(transform) = content.get_transform(&reference).await else {
+						return;
+					};
+					let (Some(translation), Some(rotation)) =
+						(transform.translation, transform.rotation)
+					else {
+						return;
+					};
+					let _ =
+						pose_tx.send((index, translation.into(), Quat::from(rotation).to_array()));
+				});
+			}
+		}
+		std::mem::take(&mut self.dirty)
+	}
+}
@@ -0,0 +1,197 @@
+use std::sync::{Arc, Mutex};
+
+use glam::{Quat, Vec3};
+use rustc_hash::FxHashMap;
+use stardust_xr_fusion::{
+	client::{Client, ClientState, FrameInfo, RootHandler},
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	fields::UnknownField,
+	items::{
+		panel::{PanelItem, PanelItemInitData},
+		ItemAcceptor, ItemUI, ItemUIHandler,
+	},
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, Transform},
+	HandlerWrapper,
+};
+
+/// Where the map floats relative to the client root
+const MAP_POSITION: [f32; 3] = [0.0, -0.15, -0.4];
+/// Meters of map per meter of space
+const MAP_SCALE: f32 = 0.05;
+/// Width of every panel on the map, in meters of map
+const MINIATURE_WIDTH: f32 = 0.006;
+/// Seconds between map redraws
+const REDRAW_INTERVAL: f32 = 0.5;
+
+struct ObservedPanel {
+	item: PanelItem,
+	aspect_ratio: f32,
+	captured: bool,
+}
+
+/// A read-only miniature of every panel and acceptor, for watching what another shell does.
+///
+/// Nothing here is grabbable and nothing ever gets captured, panels are only looked at.
+pub struct Observer {
+	root: Spatial,
+	lines: Lines,
+	panels: FxHashMap<String, ObservedPanel>,
+	acceptors: FxHashMap<String, UnknownField>,
+	last_redraw: f32,
+	redrawn: Arc<Mutex<Option<Vec<Line>>>>,
+}
+impl Observer {
+	fn new(client: &Arc<Client>) -> Result<Self, NodeError> {
+		let root = client.get_root().alias();
+		let lines = Lines::create(&root, Transform::from_translation(MAP_POSITION), &[])?;
+		Ok(Observer {
+			root,
+			lines,
+			panels: FxHashMap::default(),
+			acceptors: FxHashMap::default(),
+			last_redraw: f32::NEG_INFINITY,
+			redrawn: Default::default(),
+		})
+	}
+
+	fn frame(&mut self, info: &FrameInfo) {
+		if let Some(lines) = self.redrawn.lock().unwrap().take() {
+			let _ = self.lines.set_lines(&lines);
+		}
+		let now = info.elapsed as f32;
+		if now - self.last_redraw < REDRAW_INTERVAL {
+			return;
+		}
+		self.last_redraw = now;
+
+		let panels = self
+			.panels
+			.values()
+			.map(|panel| (panel.item.alias(), panel.aspect_ratio, panel.captured))
+			.collect::<Vec<_>>();
+		let acceptors = self
+			.acceptors
+			.values()
+			.map(|field| field.alias())
+			.collect::<Vec<_>>();
+		let root = self.root.alias();
+		let redrawn = self.redrawn.clone();
+		tokio::spawn(async move {
+			let mut lines = Vec::new();
+			for (item, aspect_ratio, captured) in panels {
+				let Some((position, rotation)) = pose(&item, &root).await else {
+					continue;
+				};
+				let color = if captured {
+					[1.0, 0.5, 0.1]
+				} else {
+					[1.0, 1.0, 1.0]
+				};
+				let [w, h] = [MINIATURE_WIDTH / 2.0, MINIATURE_WIDTH * aspect_ratio / 2.0];
+				let corners = [[-w, h], [w, h], [w, -h], [-w, -h]]
+					.map(|[x, y]| position * MAP_SCALE + rotation * Vec3::new(x, y, 0.0));
+				lines.push(line(&corners, color, true));
+			}
+			for field in acceptors {
+				let Some((position, _)) = pose(&field, &root).await else {
+					continue;
+				};
+				let center = position * MAP_SCALE;
+				let color = [0.2, 0.8, 1.0];
+				let arm = MINIATURE_WIDTH / 3.0;
+				lines.push(line(
+					&[center - Vec3::X * arm, center + Vec3::X * arm],
+					color,
+					false,
+				));
+				lines.push(line(
+					&[center - Vec3::Z * arm, center + Vec3::Z * arm],
+					color,
+					false,
+				));
+			}
+			*redrawn.lock().unwrap() = Some(lines);
+		});
+	}
+}
+
+/// Position and rotation of `spatial` relative to `root`.
+pub async fn pose(spatial: &impl SpatialAspect, root: &Spatial) -> Option<(Vec3, Quat)> {
+	let transform = spatial.get_transform(root).await.ok()?;
+	Some((
+		transform.translation?.into(),
+		transform.rotation.map_or(Quat::IDENTITY, Quat::from),
+	))
+}
+fn line(points: &[Vec3], color: [f32; 3], cyclic: bool) -> Line {
+	Line {
+		points: points
+			.iter()
+			.map(|point| LinePoint {
+				point: (*point).into(),
+				thickness: 0.0005,
+				color: rgba_linear!(color[0], color[1], color[2], 1.0),
+			})
+			.collect(),
+		cyclic,
+	}
+}
+
+impl ItemUIHandler<PanelItem> for Observer {
+	fn item_created(&mut self, uid: String, item: PanelItem, init_data: PanelItemInitData) {
+		let size = init_data.toplevel.size;
+		self.panels.insert(
+			uid,
+			ObservedPanel {
+				item,
+				aspect_ratio: size.y as f32 / size.x.max(1) as f32,
+				captured: false,
+			},
+		);
+	}
+	fn item_captured(&mut self, uid: String, _acceptor_uid: String) {
+		if let Some(panel) = self.panels.get_mut(&uid) {
+			panel.captured = true;
+		}
+	}
+	fn item_released(&mut self, uid: String, _acceptor_uid: String) {
+		if let Some(panel) = self.panels.get_mut(&uid) {
+			panel.captured = false;
+		}
+	}
+	fn item_destroyed(&mut self, uid: String) {
+		self.panels.remove(&uid);
+	}
+
+	fn acceptor_created(
+		&mut self,
+		acceptor_uid: String,
+		_acceptor: ItemAcceptor<PanelItem>,
+		field: UnknownField,
+	) {
+		self.acceptors.insert(acceptor_uid, field);
+	}
+	fn acceptor_destroyed(&mut self, acceptor_uid: String) {
+		self.acceptors.remove(&acceptor_uid);
+	}
+}
+
+pub struct ObserverRoot {
+	observer: HandlerWrapper<ItemUI<PanelItem>, Observer>,
+}
+impl ObserverRoot {
+	pub fn create(client: &Arc<Client>) -> color_eyre::Result<Self> {
+		let observer = ItemUI::register(client)?.wrap(Observer::new(client)?)?;
+		Ok(ObserverRoot { observer })
+	}
+}
+impl RootHandler for ObserverRoot {
+	fn frame(&mut self, info: FrameInfo) {
+		self.observer.lock_wrapped().frame(&info);
+	}
+	fn save_state(&mut self) -> ClientState {
+		ClientState::default()
+	}
+}
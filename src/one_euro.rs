@@ -0,0 +1,124 @@
+use std::f32::consts::TAU;
+
+use mint::Vector2;
+
+/// Smooths out hand tracking jitter while barely lagging behind fast, deliberate movement.
+/// See Casiez et al., "1€ Filter: A Simple Speed-based Low-pass Filter for Noisy Input in Interactive Systems".
+#[derive(Debug, Clone, Copy)]
+pub struct OneEuroFilter<const N: usize = 2> {
+	/// Cutoff in Hz while holding still, lower removes more jitter
+	pub min_cutoff: f32,
+	/// How much the cutoff rises with speed, higher lags less when moving fast
+	pub beta: f32,
+	/// Cutoff in Hz for the speed estimate itself
+	pub derivative_cutoff: f32,
+	last: Option<(f32, [f32; N], [f32; N])>,
+}
+impl<const N: usize> OneEuroFilter<N> {
+	pub fn new(min_cutoff: f32, beta: f32) -> Self {
+		OneEuroFilter {
+			min_cutoff,
+			beta,
+			derivative_cutoff: 1.0,
+			last: None,
+		}
+	}
+
+	fn alpha(cutoff: f32, dt: f32) -> f32 {
+		let tau = 1.0 / (TAU * cutoff);
+		1.0 / (1.0 + tau / dt)
+	}
+
+	/// `now` in seconds, the first sample passes through untouched.
+	pub fn filter_array(&mut self, now: f32, value: [f32; N]) -> [f32; N] {
+		let Some((last_time, last_value, last_derivative)) = self.last else {
+			self.last = Some((now, value, [0.0; N]));
+			return value;
+		};
+		let dt = now - last_time;
+		if dt <= 0.0 {
+			return last_value;
+		}
+		let derivative_alpha = Self::alpha(self.derivative_cutoff, dt);
+		let derivative = std::array::from_fn(|i| {
+			let raw = (value[i] - last_value[i]) / dt;
+			last_derivative[i] + derivative_alpha * (raw - last_derivative[i])
+		});
+		let speed = derivative.iter().map(|d| d * d).sum::<f32>().sqrt();
+		let alpha = Self::alpha(self.min_cutoff + self.beta * speed, dt);
+		let filtered = std::array::from_fn(|i| last_value[i] + alpha * (value[i] - last_value[i]));
+		self.last = Some((now, filtered, derivative));
+		filtered
+	}
+	/// Start over, so the next sample passes through untouched.
+	pub fn reset(&mut self) {
+		self.last = None;
+	}
+}
+impl OneEuroFilter<2> {
+	pub fn filter(&mut self, now: f32, value: Vector2<f32>) -> Vector2<f32> {
+		self.filter_array(now, [value.x, value.y]).into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn first_sample_passes_through() {
+		let mut filter = OneEuroFilter::new(1.0, 0.0);
+		assert_eq!(filter.filter_array(0.0, [3.0, -2.0]), [3.0, -2.0]);
+	}
+
+	#[test]
+	fn jitter_is_smoothed_toward_the_last_value() {
+		let mut filter = OneEuroFilter::new(1.0, 0.0);
+		filter.filter_array(0.0, [0.0]);
+		let [filtered] = filter.filter_array(1.0 / 90.0, [1.0]);
+		assert!(filtered > 0.0 && filtered < 0.1, "{filtered}");
+	}
+
+	#[test]
+	fn holding_still_converges() {
+		let mut filter = OneEuroFilter::new(1.0, 0.0);
+		filter.filter_array(0.0, [0.0]);
+		let mut filtered = [0.0];
+		for frame in 1..=900 {
+			filtered = filter.filter_array(frame as f32 / 90.0, [1.0]);
+		}
+		assert!((filtered[0] - 1.0).abs() < 1e-3, "{filtered:?}");
+	}
+
+	#[test]
+	fn fast_movement_lags_less_with_beta() {
+		let follow = |beta: f32| {
+			let mut filter = OneEuroFilter::new(1.0, beta);
+			let mut filtered = [0.0];
+			for frame in 0..10 {
+				let now = frame as f32 / 90.0;
+				filtered = filter.filter_array(now, [now * 2.0]);
+			}
+			filtered[0]
+		};
+		assert!(follow(1.0) > follow(0.0));
+	}
+
+	#[test]
+	fn repeated_timestamps_keep_the_last_value() {
+		let mut filter = OneEuroFilter::new(1.0, 0.0);
+		filter.filter_array(0.0, [0.0]);
+		let filtered = filter.filter_array(0.1, [1.0]);
+		assert_eq!(filter.filter_array(0.1, [5.0]), filtered);
+		assert_eq!(filter.filter_array(0.05, [5.0]), filtered);
+	}
+
+	#[test]
+	fn reset_passes_the_next_sample_through() {
+		let mut filter = OneEuroFilter::new(1.0, 0.0);
+		filter.filter_array(0.0, [0.0]);
+		filter.filter_array(0.1, [1.0]);
+		filter.reset();
+		assert_eq!(filter.filter_array(0.2, [7.0]), [7.0]);
+	}
+}
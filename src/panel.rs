@@ -1,64 +1,1270 @@
-use std::sync::{Arc, Mutex};
+use std::{
+	future::Future,
+	sync::{Arc, Mutex},
+};
 
-use map_range::MapRange;
+use crate::{
+	accent::Accent,
+	acceptor_link::{self, proximity_color, AcceptorLink},
+	acceptor_registry::AcceptorRegistry,
+	aging::Aging,
+	ambient::Ambient,
+	anchors::Anchors,
+	arbitration::GrabArbiter,
+	backing_plate::{BackingPlate, BackingPlateConfig},
+	capture_preview::{CapturePreview, PreviewOutcome},
+	commands::{WmCommand, MOVE_STEP},
+	config::Config,
+	decorator::{Decorator, DecoratorContext, DecoratorRegistry},
+	desk::{self, MouseEvent},
+	dev_overlay::{DevOverlay, IdentityLabel},
+	energy::EnergySaver,
+	event_log::{self, LogPanel},
+	floor_marker::{FloorMarker, FloorMarkerConfig},
+	focus_sweep::FocusSweep,
+	gestures::DoubleTap,
+	grab_smoothing::GrabSmoothing,
+	hand::Hand,
+	handle_bar::{GrabMode, HandleBar},
+	hooks::{HookContext, HookEvent},
+	idle::IdleDetector,
+	input::PanelInput,
+	ipc::{self, PanelAction, PanelSummary},
+	layout::{AppSize, Layout, PanelLayout},
+	links::{PanelLink, PanelLinks},
+	material::MaterialCache,
+	minimap::Minimap,
+	notes::{NoteCommand, Notes},
+	panel_model::FaceRect,
+	pocket::Pocket,
+	proximity_cue::ProximityCue,
+	rails::GrabRails,
+	rotation_ring::RotationRing,
+	scripting::{AppInfo, Placement, PlacementScript},
+	seated::SeatedOffset,
+	settings::SettingsPanel,
+	shortcuts::{self, Shortcuts},
+	speech::Announcement,
+	state::State,
+	theme::{desaturated, dimmed, Theme},
+	tombstone::{self, Tombstone, TombstoneAction},
+	tutorial::{Tutorial, TutorialEvent},
+	urgency::{Attention, UrgencyConfig},
+	wall::{ParkingWall, WallConfig, WallTiling},
+};
+use glam::{EulerRot, Quat, Vec3};
 use mint::Vector2;
 use rustc_hash::FxHashMap;
 use stardust_xr_fusion::{
 	client::FrameInfo,
-	core::values::{rgba_linear, ResourceID},
-	drawable::{MaterialParameter, Model, ModelPartAspect},
-	fields::{BoxField, BoxFieldAspect, FieldAspect, UnknownField},
+	core::values::ResourceID,
+	drawable::{Model, ModelPart, ModelPartAspect, Sound},
+	fields::{BoxField, BoxFieldAspect, UnknownField},
 	items::{
 		panel::{ChildInfo, Geometry, PanelItem, PanelItemHandler, PanelItemInitData, SurfaceID},
-		ItemAcceptor, ItemUIHandler,
+		ItemAcceptor, ItemAspect, ItemUIHandler,
 	},
 	node::{NodeError, NodeType},
-	spatial::{SpatialAspect, Transform},
+	spatial::{Spatial, SpatialAspect, SpatialRef, Transform},
 	HandlerWrapper,
 };
-use stardust_xr_molecules::{multi::multi_node_call, Grabbable, GrabbableSettings};
-use tokio::sync::watch;
+use stardust_xr_molecules::{
+	button::{Button, ButtonSettings},
+	Grabbable,
+};
+use tokio::sync::{mpsc, oneshot};
 
 pub struct PanelItemUIHandler {
 	items: FxHashMap<String, HandlerWrapper<PanelItem, PanelItemUI>>,
-	acceptors_tx: watch::Sender<FxHashMap<String, (ItemAcceptor<PanelItem>, UnknownField)>>,
-	acceptors_rx: watch::Receiver<FxHashMap<String, (ItemAcceptor<PanelItem>, UnknownField)>>,
+	acceptors: AcceptorRegistry,
+	reference: Spatial,
+	pending_layouts: Vec<PanelLayout>,
+	placement_script: Option<PlacementScript>,
+	decorators: DecoratorRegistry,
+	focused: Option<String>,
+	/// Panel uids in the order they appeared, for cycling focus
+	order: Vec<String>,
+	/// Previously focused panel uids, most recent last
+	focus_history: Vec<String>,
+	pub streaming_mode: bool,
+	wall: Option<ParkingWall>,
+	wall_tx: mpsc::UnboundedSender<(String, Option<[f32; 3]>)>,
+	wall_rx: mpsc::UnboundedReceiver<(String, Option<[f32; 3]>)>,
+	pocket: Option<Pocket>,
+	pocket_tx: mpsc::UnboundedSender<String>,
+	pocket_rx: mpsc::UnboundedReceiver<String>,
+	energy_saver: EnergySaver,
+	idle: IdleDetector,
+	proximity_cue: ProximityCue,
+	settings: Option<SettingsPanel>,
+	minimap: Option<Minimap>,
+	tombstones: Vec<Tombstone>,
+	mouse: Option<mpsc::UnboundedReceiver<MouseEvent>>,
+	shortcuts: Option<Shortcuts>,
+	/// Set by hand, on top of the apps that always pass shortcuts through
+	shortcut_passthrough: bool,
+	/// Which panel each input is closest to as of the last frame, and how close
+	input_claims: FxHashMap<usize, (String, f32)>,
+	ambient: Ambient,
+	/// Seconds since startup as of the last frame, for callbacks that don't get a frame
+	now: f32,
+	log_panel: Option<LogPanel>,
+	tutorial: Option<Tutorial>,
+	hmd: SpatialRef,
+	/// Last known place of every panel, to remember once it's closed
+	last_poses: Arc<Mutex<FxHashMap<String, PanelLayout>>>,
+	last_pose_check: f32,
+	last_watchdog: f32,
+	seated: SeatedOffset,
+	links: PanelLinks,
+	notes: Notes,
+	anchors: Anchors,
+	state: State,
+	config: Config,
+	/// Where the surface is drawn on the panel model
+	face: FaceRect,
 }
 impl PanelItemUIHandler {
-	pub fn new() -> Self {
-		let (acceptors_tx, acceptors_rx) = watch::channel(FxHashMap::default());
+	pub fn new(
+		reference: Spatial,
+		hmd: SpatialRef,
+		acceptors: AcceptorRegistry,
+		config: Config,
+		face: FaceRect,
+		decorators: DecoratorRegistry,
+	) -> Self {
+		let wall = config
+			.wall
+			.clone()
+			.and_then(|wall| ParkingWall::create(&reference, wall, config.reduce_motion).ok());
+		let (wall_tx, wall_rx) = mpsc::unbounded_channel();
+		let pocket = config
+			.pocket
+			.and_then(|pocket| Pocket::create(&reference, &hmd, pocket, config.dominant_hand).ok());
+		let (pocket_tx, pocket_rx) = mpsc::unbounded_channel();
+		let state = State::load();
+		let seated = SeatedOffset::new(
+			config.standing_eye_height,
+			state.as_ref().and_then(|state| state.eye_height),
+		);
+		seated.apply(&reference);
+		let links = PanelLinks::new(
+			&reference,
+			state
+				.as_ref()
+				.map(|state| state.links.clone())
+				.unwrap_or_default(),
+		);
+		let anchors = Anchors::new(
+			&reference,
+			state
+				.as_ref()
+				.map(|state| state.anchors.clone())
+				.unwrap_or_default(),
+		);
+		let notes = Notes::new(
+			&reference,
+			&hmd,
+			config.grabbable_settings(),
+			state
+				.as_ref()
+				.map(|state| state.notes.clone())
+				.unwrap_or_default(),
+		);
+		let tutorial = state
+			.is_none()
+			.then(|| Tutorial::create(&reference).ok())
+			.flatten();
 		PanelItemUIHandler {
 			items: FxHashMap::default(),
-			acceptors_tx,
-			acceptors_rx,
+			acceptors,
+			reference,
+			pending_layouts: Vec::new(),
+			placement_script: config.placement_script_path().map(PlacementScript::new),
+			decorators,
+			focused: None,
+			order: Vec::new(),
+			focus_history: Vec::new(),
+			streaming_mode: false,
+			wall,
+			wall_tx,
+			wall_rx,
+			pocket,
+			pocket_tx,
+			pocket_rx,
+			energy_saver: EnergySaver::new(config.energy_saver, hmd.alias()),
+			idle: IdleDetector::new(config.idle, &reference, &hmd),
+			proximity_cue: ProximityCue::new(config.proximity_cue.clone(), hmd.alias()),
+			settings: None,
+			minimap: None,
+			tombstones: Vec::new(),
+			mouse: desk::spawn(&config.desk),
+			shortcuts: shortcuts::spawn(&config.shortcuts),
+			shortcut_passthrough: false,
+			input_claims: FxHashMap::default(),
+			ambient: Ambient::new(config.ambient.clone()),
+			now: 0.0,
+			log_panel: None,
+			tutorial,
+			hmd,
+			last_poses: Default::default(),
+			last_pose_check: f32::NEG_INFINITY,
+			last_watchdog: f32::NEG_INFINITY,
+			seated,
+			links,
+			notes,
+			anchors,
+			state: state.unwrap_or_default(),
+			config,
+			face,
 		}
 	}
 	pub fn frame(&mut self, info: &FrameInfo) {
-		for (_, item) in self.items.iter() {
-			item.lock_wrapped().frame(self, info);
+		self.now = info.elapsed as f32;
+		let mut focus_requested = None;
+		let mut holding = false;
+		let mut orphans = Vec::new();
+		let sweep = self.energy_saver.update(info.elapsed as f32);
+		self.idle.update(info.elapsed as f32);
+		let asleep = self.idle.asleep();
+		let brightness = self
+			.ambient
+			.update(info.elapsed as f32)
+			.then(|| self.ambient.brightness());
+		let (approached, check_reach) = self.proximity_cue.update(info.elapsed as f32);
+		for uid in approached {
+			if let Some(item) = self.items.get(&uid) {
+				item.lock_wrapped().play_proximity_cue();
+			}
+		}
+		// Overlapping panels all see the same inputs, only the closest one gets to act on each
+		let claims = std::mem::take(&mut self.input_claims);
+		let check_poses = info.elapsed as f32 - self.last_pose_check >= POSE_CHECK_INTERVAL;
+		if check_poses {
+			self.last_pose_check = info.elapsed as f32;
+		}
+		let watchdog = info.elapsed as f32 - self.last_watchdog >= WATCHDOG_INTERVAL;
+		if watchdog {
+			self.last_watchdog = info.elapsed as f32;
+		}
+		for (uid, item) in self.items.iter() {
+			let mut item = item.lock_wrapped();
+			if sweep {
+				self.energy_saver
+					.query(uid, item.grabbable.content_parent());
+			}
+			item.set_suspended(asleep || self.energy_saver.suspended(uid));
+			if let Some(brightness) = brightness {
+				item.set_brightness(brightness);
+			}
+			if check_reach && !item.captured {
+				self.proximity_cue
+					.query(uid, item.grabbable.content_parent());
+			}
+			if watchdog {
+				let orphaned_by = item
+					.captured_by
+					.clone()
+					.filter(|acceptor| !self.acceptors.contains::<PanelItem>(acceptor));
+				if item.orphan_expired(orphaned_by.is_some(), info.elapsed as f32) {
+					orphans.extend(orphaned_by.map(|acceptor| (uid.clone(), acceptor)));
+				}
+			}
+			if check_poses {
+				let entry = item.layout_entry(&self.reference);
+				let last_poses = self.last_poses.clone();
+				let uid = uid.clone();
+				tokio::spawn(async move {
+					if let Some(entry) = entry.await {
+						last_poses.lock().unwrap().insert(uid, entry);
+					}
+				});
+			}
+			let streaming_hidden = self.streaming_mode
+				&& item
+					.app_id
+					.as_ref()
+					.is_some_and(|app_id| self.config.streaming_hidden_apps.contains(app_id));
+			item.set_streaming_hidden(streaming_hidden);
+			item.input.set_occluded(
+				claims
+					.iter()
+					.filter(|(_, (owner, _))| owner != uid)
+					.map(|(id, _)| *id)
+					.collect(),
+			);
+			item.frame(info);
+			item.edge.flush();
+			for (id, distance) in item.input.claims() {
+				if self
+					.input_claims
+					.get(&id)
+					.map_or(true, |(_, closest)| distance < *closest)
+				{
+					self.input_claims.insert(id, (uid.clone(), distance));
+				}
+			}
+			holding |= item.grabbable.grab_action().actor_acting();
+			if std::mem::take(&mut item.leveled) {
+				if let Some(tutorial) = &mut self.tutorial {
+					tutorial.event(TutorialEvent::Leveled);
+				}
+			}
+			if std::mem::take(&mut item.focus_requested) {
+				focus_requested = Some(uid.clone());
+				self.idle.activity();
+				self.config
+					.speech
+					.announce(Announcement::Grabbed, item.spoken_name());
+				if let Some(tutorial) = &mut self.tutorial {
+					tutorial.event(TutorialEvent::Grabbed);
+				}
+				if let Some(wall) = &mut self.wall {
+					wall.remove(uid);
+				}
+				if let Some(pocket) = &mut self.pocket {
+					pocket.take(uid, item.grabbable.content_parent());
+				}
+			}
+			if std::mem::take(&mut item.resized) {
+				if let Some(wall) = &mut self.wall {
+					wall.dirty |= wall.panels.contains(uid);
+				}
+			}
+			if std::mem::take(&mut item.grab_stopped) {
+				self.run_hook(HookEvent::Moved, &item);
+				if self.config.face_on_release || self.config.snap_yaw_on_release {
+					orient_on_release(
+						item.grabbable.content_parent(),
+						&self.reference,
+						&self.hmd,
+						self.config.face_on_release,
+						self.config.snap_yaw_on_release,
+					);
+				}
+				if let Some(wall) = &self.wall {
+					let near = wall.near_query(item.grabbable.content_parent());
+					let wall_tx = self.wall_tx.clone();
+					let uid = uid.clone();
+					tokio::spawn(async move {
+						let _ = wall_tx.send((uid, near.await));
+					});
+				}
+				if let Some(pocket) = &self.pocket {
+					let near = pocket.near_query(item.grabbable.content_parent());
+					let pocket_tx = self.pocket_tx.clone();
+					let uid = uid.clone();
+					tokio::spawn(async move {
+						if near.await {
+							let _ = pocket_tx.send(uid);
+						}
+					});
+				}
+			}
+		}
+		// Released the same way the acceptor would have, so metrics and subscribers hear about it too
+		for (uid, acceptor_uid) in orphans {
+			event_log::record(
+				Some(&uid),
+				"Freed, its acceptor went away without releasing it",
+			);
+			self.item_released(uid, acceptor_uid);
+		}
+		if let Some(uid) = focus_requested {
+			self.focus(&uid);
+		}
+		self.update_wall(info);
+		self.update_pocket();
+		self.update_settings();
+		self.update_minimap(info);
+		self.update_tombstones();
+		self.update_mouse();
+		self.update_shortcuts();
+		if let Some(log_panel) = &mut self.log_panel {
+			log_panel.update();
+		}
+		self.update_tutorial();
+		let items = &self.items;
+		if asleep {
+			self.links.hide();
+		} else {
+			self.links.update(|app_id| {
+				items.values().find_map(|item| {
+					let item = item.lock_wrapped();
+					(item.app_id.as_deref() == Some(app_id))
+						.then(|| item.grabbable.content_parent().alias())
+				})
+			});
+		}
+		if self.anchors.update(holding) {
+			self.save_anchors();
+		}
+		if self.notes.update(info) {
+			self.state.notes = self.notes.states();
+			self.save_state();
+		}
+		if let Some(eye_height) = self.seated.update(&self.reference) {
+			self.state.eye_height = Some(eye_height);
+			self.save_state();
+		}
+	}
+
+	pub fn set_seated(&mut self, seated: bool) {
+		if seated {
+			self.seated.calibrate(&self.reference, &self.hmd);
+		} else {
+			self.seated.clear(&self.reference);
+			self.state.eye_height = None;
+			self.save_state();
+		}
+	}
+	fn save_state(&self) {
+		if let Err(e) = self.state.save() {
+			eprintln!("{e:#}");
+		}
+	}
+
+	pub fn set_tutorial(&mut self, enabled: bool) -> Result<(), NodeError> {
+		self.tutorial = enabled
+			.then(|| Tutorial::create(&self.reference))
+			.transpose()?;
+		Ok(())
+	}
+	fn tutorial_event(&mut self, event: TutorialEvent) {
+		if let Some(tutorial) = &mut self.tutorial {
+			tutorial.event(event);
+		}
+	}
+	fn update_tutorial(&mut self) {
+		let Some(tutorial) = &mut self.tutorial else {
+			return;
+		};
+		if !tutorial.update() {
+			return;
+		}
+		self.tutorial = None;
+		self.state.tutorial_completed = true;
+		self.save_state();
+	}
+
+	/// The mouse always works the focused panel, there's no pointing at others with it.
+	fn update_mouse(&mut self) {
+		let Some(mouse) = &mut self.mouse else {
+			return;
+		};
+		let item = self.focused.as_ref().and_then(|uid| self.items.get(uid));
+		while let Ok(event) = mouse.try_recv() {
+			if let Some(item) = item {
+				item.lock_wrapped()
+					.input
+					.mouse(event, self.config.desk.sensitivity);
+			}
+		}
+	}
+
+	/// Lets the keyboard know whether shortcut keys go to the focused app instead, then runs the ones it took.
+	fn update_shortcuts(&mut self) {
+		let Some(shortcuts) = &mut self.shortcuts else {
+			return;
+		};
+		let focused_app = self
+			.focused
+			.as_ref()
+			.and_then(|uid| self.items.get(uid))
+			.and_then(|item| item.lock_wrapped().app_id.clone());
+		shortcuts.set_passthrough(
+			self.shortcut_passthrough
+				|| focused_app
+					.is_some_and(|app_id| self.config.shortcuts.passthrough_apps.contains(&app_id)),
+		);
+		let mut commands = Vec::new();
+		while let Ok(command) = shortcuts.commands.try_recv() {
+			commands.push(command);
+		}
+		for command in commands {
+			if let Err(e) = self.wm_command(command) {
+				eprintln!("{e}");
+			}
+		}
+	}
+
+	fn update_tombstones(&mut self) {
+		self.tombstones
+			.retain_mut(|tombstone| match tombstone.update() {
+				Some(TombstoneAction::Relaunch) => {
+					match tombstone::relaunch(&tombstone.app_id) {
+						Ok(()) => tombstone.set_relaunching(),
+						Err(e) => eprintln!("{e:#}"),
+					}
+					true
+				}
+				Some(TombstoneAction::Dismiss) => false,
+				None => true,
+			});
+	}
+
+	fn update_minimap(&mut self, info: &FrameInfo) {
+		let Some(minimap) = &mut self.minimap else {
+			return;
+		};
+		let panels = self
+			.items
+			.iter()
+			.map(|(uid, item)| {
+				(
+					uid.clone(),
+					item.lock_wrapped().grabbable.content_parent().alias(),
+				)
+			})
+			.collect();
+		let acceptors = self.acceptors.fields::<PanelItem>();
+		if let Some(uid) = minimap.update(info.elapsed as f32, panels, acceptors) {
+			self.recall(&uid);
+		}
+	}
+	/// Bring a panel in front of the user and focus it, for panels parked out of view.
+	fn recall(&mut self, uid: &str) {
+		let Some(item) = self.items.get(uid) else {
+			return;
+		};
+		let item = item.lock_wrapped();
+		if !item.captured {
+			if let Some(wall) = &mut self.wall {
+				wall.remove(uid);
+			}
+			if let Some(pocket) = &mut self.pocket {
+				pocket.take(uid, item.grabbable.content_parent());
+			}
+			item.grabbable.cancel_linear_velocity();
+			item.grabbable.cancel_angular_velocity();
+			let content_parent = item.grabbable.content_parent().alias();
+			let reference = self.reference.alias();
+			let hmd = self.hmd.alias();
+			tokio::spawn(async move {
+				let Ok(head) = hmd.get_transform(&reference).await else {
+					return;
+				};
+				let translation = head.translation.map_or(Vec3::ZERO, Vec3::from);
+				let rotation = head.rotation.map_or(Quat::IDENTITY, Quat::from);
+				let _ = content_parent.set_relative_transform(
+					&reference,
+					Transform::from_translation_rotation(
+						translation + rotation * Vec3::NEG_Z * RECALL_DISTANCE,
+						rotation,
+					),
+				);
+			});
+		}
+		drop(item);
+		self.focus(uid);
+		self.announce_focus(uid);
+	}
+
+	fn update_settings(&mut self) {
+		let Some(settings) = &mut self.settings else {
+			return;
+		};
+		if !settings.update(&mut self.config) {
+			return;
+		}
+		self.acceptors
+			.policy()
+			.set_max_distance(self.config.accept_distance);
+		for item in self.items.values() {
+			let mut item = item.lock_wrapped();
+			item.set_panel_width(self.config.panel_width);
+			item.set_theme(self.config.theme);
+		}
+		if let Err(e) = self.config.save() {
+			eprintln!("{e:#}");
+		}
+	}
+
+	pub fn set_wall(&mut self, wall: Option<WallConfig>) -> Result<(), NodeError> {
+		self.wall = wall
+			.map(|wall| ParkingWall::create(&self.reference, wall, self.config.reduce_motion))
+			.transpose()?;
+		Ok(())
+	}
+	pub fn set_wall_tiling(&mut self, tiling: WallTiling) -> Result<(), String> {
+		let wall = self.wall.as_mut().ok_or("No parking wall is set")?;
+		wall.set_tiling(tiling).map_err(|e| e.to_string())
+	}
+	fn update_wall(&mut self, info: &FrameInfo) {
+		let Some(wall) = &mut self.wall else {
+			return;
+		};
+		while let Ok((uid, near)) = self.wall_rx.try_recv() {
+			let free = self
+				.items
+				.get(&uid)
+				.is_some_and(|item| !item.lock_wrapped().captured);
+			if let Some(position) = near.filter(|_| free) {
+				wall.add(uid, position);
+			}
+		}
+		if wall.dirty {
+			let sizes = wall
+				.panels
+				.iter()
+				.map(|uid| self.items[uid].lock_wrapped().physical_size)
+				.collect::<Vec<_>>();
+			wall.arrange(&sizes, info.elapsed as f32);
+		}
+		for (uid, position) in wall.step(info) {
+			let Some(item) = self.items.get(&uid) else {
+				continue;
+			};
+			let mut item = item.lock_wrapped();
+			item.grabbable.cancel_linear_velocity();
+			item.grabbable.cancel_angular_velocity();
+			let _ = item.grabbable.content_parent().set_relative_transform(
+				wall.spatial(),
+				Transform::from_translation_rotation(position, Quat::IDENTITY),
+			);
+		}
+	}
+
+	fn update_pocket(&mut self) {
+		let Some(pocket) = &mut self.pocket else {
+			return;
+		};
+		while let Ok(uid) = self.pocket_rx.try_recv() {
+			let free = self
+				.items
+				.get(&uid)
+				.is_some_and(|item| !item.lock_wrapped().captured);
+			if free {
+				if let Some(wall) = &mut self.wall {
+					wall.remove(&uid);
+				}
+				pocket.stash(uid);
+			}
+		}
+		for (uid, transform) in pocket.update() {
+			let Some(item) = self.items.get(&uid) else {
+				continue;
+			};
+			let item = item.lock_wrapped();
+			item.grabbable.cancel_linear_velocity();
+			item.grabbable.cancel_angular_velocity();
+			let _ = item
+				.grabbable
+				.content_parent()
+				.set_relative_transform(&self.reference, transform);
+		}
+	}
+
+	fn focus(&mut self, uid: &str) {
+		if self.focused.as_deref() == Some(uid) {
+			return;
+		}
+		if let Some(previous) = self.focused.take() {
+			if let Some(item) = self.items.get(&previous) {
+				item.lock_wrapped().set_focused(false);
+			}
+			self.focus_history.retain(|p| p != &previous);
+			self.focus_history.push(previous);
+			if self.focus_history.len() > FOCUS_HISTORY_LEN {
+				self.focus_history.remove(0);
+			}
+		}
+		self.focus_history.retain(|p| p != uid);
+		let Some(item) = self.items.get(uid) else {
+			return;
+		};
+		let mut item = item.lock_wrapped();
+		item.set_focused(true);
+		self.run_hook(HookEvent::Focused, &item);
+		drop(item);
+		event_log::record(Some(uid), "Focused");
+		self.focused = Some(uid.to_string());
+	}
+
+	pub fn export_layout(&self) -> impl Future<Output = Layout> + Send + 'static {
+		let entries = self
+			.items
+			.values()
+			.map(|item| item.lock_wrapped().layout_entry(&self.reference))
+			.collect::<Vec<_>>();
+		async move {
+			let mut panels = Vec::new();
+			for entry in entries {
+				if let Some(entry) = entry.await {
+					panels.push(entry);
+				}
+			}
+			Layout { panels }
+		}
+	}
+	pub fn set_ambient_level(&mut self, level: Option<f32>) {
+		self.ambient.set_level(level);
+	}
+	pub fn set_theme(&mut self, theme: Theme) {
+		self.config.theme = theme;
+		for item in self.items.values() {
+			item.lock_wrapped().set_theme(theme);
+		}
+	}
+	pub fn energy_saver_active(&self) -> bool {
+		self.energy_saver.active()
+	}
+	pub fn panel_count(&self) -> usize {
+		self.items.len()
+	}
+	pub fn asleep(&self) -> bool {
+		self.idle.asleep()
+	}
+	pub fn summaries(&self) -> Vec<PanelSummary> {
+		self.items
+			.values()
+			.map(|item| item.lock_wrapped().summary())
+			.collect()
+	}
+	pub fn panel_action(&mut self, uid: Option<&str>, action: PanelAction) -> Result<(), String> {
+		let Some(uid) = uid.or(self.focused.as_deref()) else {
+			return Err("No panel is focused".to_string());
+		};
+		let Some(item) = self.items.get(uid) else {
+			return Err(format!("No panel with uid {uid}"));
+		};
+		item.lock_wrapped().apply_action(action);
+		Ok(())
+	}
+	/// For scheduled requests, which can't know panel uids ahead of time.
+	pub fn app_action(&mut self, app_id: &str, action: PanelAction) -> Result<(), String> {
+		let mut matched = false;
+		for item in self.items.values() {
+			let mut item = item.lock_wrapped();
+			if item.app_id.as_deref() == Some(app_id) {
+				item.apply_action(action.clone());
+				matched = true;
+			}
+		}
+		matched
+			.then_some(())
+			.ok_or_else(|| format!("No panels with app id {app_id}"))
+	}
+	/// Captured panels stay where they are, the rest leave the wall for a row at recall distance.
+	pub fn gather_app(&mut self, app_id: &str) -> Result<(), String> {
+		let uids: Vec<String> = self
+			.order
+			.iter()
+			.filter(|uid| {
+				self.items.get(*uid).is_some_and(|item| {
+					let item = item.lock_wrapped();
+					item.app_id.as_deref() == Some(app_id) && !item.captured
+				})
+			})
+			.cloned()
+			.collect();
+		let Some(first) = uids.first().cloned() else {
+			return Err(format!("No free panels with app id {app_id}"));
+		};
+		let spacing = self.config.panel_width + GATHER_GAP;
+		let start = -spacing * (uids.len() - 1) as f32 / 2.0;
+		let mut content_parents = Vec::with_capacity(uids.len());
+		for uid in &uids {
+			if let Some(wall) = &mut self.wall {
+				wall.remove(uid);
+			}
+			let item = self.items[uid].lock_wrapped();
+			if let Some(pocket) = &mut self.pocket {
+				pocket.take(uid, item.grabbable.content_parent());
+			}
+			item.grabbable.cancel_linear_velocity();
+			item.grabbable.cancel_angular_velocity();
+			content_parents.push(item.grabbable.content_parent().alias());
+		}
+		let reference = self.reference.alias();
+		let hmd = self.hmd.alias();
+		tokio::spawn(async move {
+			let Ok(head) = hmd.get_transform(&reference).await else {
+				return;
+			};
+			let translation = head.translation.map_or(Vec3::ZERO, Vec3::from);
+			let rotation = head.rotation.map_or(Quat::IDENTITY, Quat::from);
+			for (index, content_parent) in content_parents.iter().enumerate() {
+				let offset = Vec3::new(start + spacing * index as f32, 0.0, -RECALL_DISTANCE);
+				let _ = content_parent.set_relative_transform(
+					&reference,
+					Transform::from_translation_rotation(translation + rotation * offset, rotation),
+				);
+			}
+		});
+		self.focus(&first);
+		self.announce_focus(&first);
+		Ok(())
+	}
+
+	pub fn wm_command(&mut self, command: WmCommand) -> Result<(), String> {
+		match command {
+			WmCommand::FocusNext | WmCommand::FocusPrevious => {
+				if self.order.is_empty() {
+					return Err("No panels are open".to_string());
+				}
+				let len = self.order.len();
+				let next = match self
+					.focused
+					.as_ref()
+					.and_then(|f| self.order.iter().position(|p| p == f))
+				{
+					Some(i) if matches!(command, WmCommand::FocusNext) => (i + 1) % len,
+					Some(i) => (i + len - 1) % len,
+					None => 0,
+				};
+				let uid = self.order[next].clone();
+				self.focus(&uid);
+				self.announce_focus(&uid);
+			}
+			WmCommand::FocusLast => {
+				let uid = self
+					.focus_history
+					.last()
+					.cloned()
+					.ok_or("No panel was focused before")?;
+				self.focus(&uid);
+				self.announce_focus(&uid);
+			}
+			WmCommand::Move { direction } => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				let item = self.items[&uid].lock_wrapped();
+				if item.captured {
+					return Err("The focused panel is held by an acceptor".to_string());
+				}
+				if let Some(wall) = &mut self.wall {
+					wall.remove(&uid);
+				}
+				let content_parent = item.grabbable.content_parent();
+				let _ = content_parent.set_relative_transform(
+					content_parent,
+					Transform::from_translation(direction.vector().map(|v| v * MOVE_STEP)),
+				);
+			}
+			WmCommand::ToggleShortcutPassthrough => {
+				self.shortcut_passthrough = !self.shortcut_passthrough;
+			}
+			WmCommand::ZoomIn | WmCommand::ZoomOut | WmCommand::ResetZoom => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				let mut item = self.items[&uid].lock_wrapped();
+				let zoom = match command {
+					WmCommand::ZoomIn => item.zoom * ZOOM_STEP,
+					WmCommand::ZoomOut => item.zoom / ZOOM_STEP,
+					_ => 1.0,
+				};
+				item.set_zoom(zoom);
+			}
+			WmCommand::CloseStale => {
+				let mut closed = false;
+				for item in self.items.values() {
+					let mut item = item.lock_wrapped();
+					if item.aging.as_ref().is_some_and(Aging::stale) {
+						item.close();
+						closed = true;
+					}
+				}
+				if !closed {
+					return Err("No panels are stale".to_string());
+				}
+			}
+			WmCommand::ToggleSettings => {
+				self.settings = match self.settings.take() {
+					Some(_) => None,
+					None => Some(
+						SettingsPanel::create(&self.reference, &self.config)
+							.map_err(|e| e.to_string())?,
+					),
+				};
+			}
+			WmCommand::ToggleLog { uid } => {
+				self.log_panel = match self.log_panel.take() {
+					Some(_) => None,
+					None => {
+						Some(LogPanel::create(&self.reference, uid).map_err(|e| e.to_string())?)
+					}
+				};
+			}
+			WmCommand::ToggleIdentityLabels => {
+				self.config.identity_labels = !self.config.identity_labels;
+				for item in self.items.values() {
+					item.lock_wrapped()
+						.set_identity_label(self.config.identity_labels);
+				}
+			}
+			WmCommand::ToggleMinimap => {
+				self.minimap = match self.minimap.take() {
+					Some(_) => None,
+					None => Some(Minimap::create(&self.reference).map_err(|e| e.to_string())?),
+				};
+			}
+			WmCommand::ToggleTiling => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				let wall = self.wall.as_mut().ok_or("No parking wall is set")?;
+				if wall.panels.contains(&uid) {
+					wall.remove(&uid);
+				} else if !self.items[&uid].lock_wrapped().captured {
+					wall.push(uid);
+				}
+			}
+			WmCommand::SaveAnchor { name } => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				let item = self.items[&uid].lock_wrapped();
+				self.anchors.save(name, item.grabbable.content_parent());
+			}
+			WmCommand::SendToAnchor { name } => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				let anchor = self
+					.anchors
+					.get(&name)
+					.ok_or_else(|| format!("No anchor named {name}"))?;
+				let item = self.items[&uid].lock_wrapped();
+				if item.captured {
+					return Err("The focused panel is held by an acceptor".to_string());
+				}
+				if let Some(wall) = &mut self.wall {
+					wall.remove(&uid);
+				}
+				item.grabbable.cancel_linear_velocity();
+				item.grabbable.cancel_angular_velocity();
+				let _ = item
+					.grabbable
+					.content_parent()
+					.set_relative_transform(&self.reference, anchor.transform());
+			}
+			WmCommand::RemoveAnchor { name } => {
+				if !self.anchors.remove(&name) {
+					return Err(format!("No anchor named {name}"));
+				}
+				self.save_anchors();
+			}
+			WmCommand::Link { label } => {
+				let to = self.focused.clone().ok_or("No panel is focused")?;
+				let from = self
+					.focus_history
+					.last()
+					.cloned()
+					.ok_or("No panel was focused before")?;
+				self.links.add(PanelLink {
+					from: self.linkable_app_id(&from)?,
+					to: self.linkable_app_id(&to)?,
+					label,
+				});
+				self.save_links();
+			}
+			WmCommand::Unlink => {
+				let uid = self.focused.clone().ok_or("No panel is focused")?;
+				if !self.links.remove_touching(&self.linkable_app_id(&uid)?) {
+					return Err("The focused panel has no links".to_string());
+				}
+				self.save_links();
+			}
+		}
+		Ok(())
+	}
+
+	pub fn note_command(&mut self, command: NoteCommand) -> Result<(), String> {
+		self.notes.command(command)
+	}
+
+	/// Apps close their windows because the user asked, which takes touching them first.
+	/// A window nobody touched for a while disappearing most likely crashed.
+	fn leave_tombstone(&mut self, uid: &str, entry: PanelLayout) {
+		let Some(app_id) = entry.app_id.clone() else {
+			return;
+		};
+		event_log::record(
+			Some(uid),
+			"Disappeared without being touched, likely crashed",
+		);
+		match Tombstone::create(&self.reference, app_id, entry, self.config.panel_width) {
+			Ok(tombstone) => self.tombstones.push(tombstone),
+			Err(e) => eprintln!("{e}"),
+		}
+	}
+
+	/// The same window coming back, by title if it kept it and otherwise by app ID.
+	fn take_closed_panel(
+		&mut self,
+		app_id: Option<&str>,
+		title: Option<&str>,
+	) -> Option<PanelLayout> {
+		let closed = &mut self.state.closed_panels;
+		let index = closed
+			.iter()
+			.rposition(|entry| entry.matches_exactly(app_id, title))
+			.or_else(|| closed.iter().rposition(|entry| entry.matches(app_id)))?;
+		let entry = closed.remove(index);
+		self.save_state();
+		Some(entry)
+	}
+
+	/// Links are kept by app ID, so panels without one can't be linked.
+	fn linkable_app_id(&self, uid: &str) -> Result<String, String> {
+		self.items
+			.get(uid)
+			.and_then(|item| item.lock_wrapped().app_id.clone())
+			.ok_or_else(|| format!("Panel {uid} has no app id to link by"))
+	}
+	fn save_anchors(&mut self) {
+		self.state.anchors = self.anchors.anchors().to_vec();
+		self.save_state();
+	}
+	fn save_links(&mut self) {
+		self.state.links = self.links.links().to_vec();
+		self.save_state();
+	}
+
+	/// Badge every open panel of an app at once.
+	pub fn set_app_badge(&mut self, app_id: &str, count: u32) -> Result<(), String> {
+		let mut matched = false;
+		for item in self.items.values() {
+			let mut item = item.lock_wrapped();
+			if item.app_id.as_deref() == Some(app_id) {
+				item.badge = count;
+				matched = true;
+			}
+		}
+		matched
+			.then_some(())
+			.ok_or_else(|| format!("No panels with app id {app_id}"))
+	}
+
+	/// Focus changes from commands aren't visible from where the user is looking, so say them.
+	fn announce_focus(&self, uid: &str) {
+		if let Some(item) = self.items.get(uid) {
+			self.config
+				.speech
+				.announce(Announcement::Focused, item.lock_wrapped().spoken_name());
+		}
+	}
+
+	fn run_hook(&self, event: HookEvent, ui: &PanelItemUI) {
+		self.emit(event, ui.hook_context());
+	}
+	/// Runs the event's hook and streams it to IPC subscribers.
+	fn emit(&self, event: HookEvent, context: HookContext) {
+		ipc::publish(event, &context);
+		self.config.hooks.run(event, context);
+	}
+
+	/// Apply entries to matching open panels, keeping the rest until a matching panel shows up.
+	pub fn import_layout(&mut self, layout: Layout) {
+		let mut unclaimed = self.items.values().collect::<Vec<_>>();
+		self.pending_layouts.clear();
+		for entry in layout.panels {
+			let claimed = unclaimed
+				.iter()
+				.position(|item| entry.matches(item.lock_wrapped().app_id.as_deref()));
+			match claimed {
+				Some(index) => unclaimed
+					.swap_remove(index)
+					.lock_wrapped()
+					.apply_layout(&self.reference, &entry),
+				None => self.pending_layouts.push(entry),
+			}
 		}
 	}
 }
+/// Only the yaw changes, so the panel stays where it was put with the same tilt.
+/// Snapping rounds it to steps of [`YAW_SNAP`] from straight ahead of the head.
+fn orient_on_release(
+	content_parent: &Spatial,
+	reference: &Spatial,
+	hmd: &SpatialRef,
+	face: bool,
+	snap: bool,
+) {
+	let content_parent = content_parent.alias();
+	let reference = reference.alias();
+	let hmd = hmd.alias();
+	tokio::spawn(async move {
+		let Ok(panel) = content_parent.get_transform(&reference).await else {
+			return;
+		};
+		let (Some(translation), Some(rotation)) = (panel.translation, panel.rotation) else {
+			return;
+		};
+		let rotation = Quat::from(rotation);
+		let Ok(head) = hmd.get_transform(&reference).await else {
+			return;
+		};
+		let (Some(head_translation), Some(head_rotation)) = (head.translation, head.rotation)
+		else {
+			return;
+		};
+		let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+		let mut target = yaw;
+		if face {
+			let to_head = Vec3::from(head_translation) - Vec3::from(translation);
+			if to_head.x != 0.0 || to_head.z != 0.0 {
+				target = to_head.x.atan2(to_head.z);
+			}
+		}
+		if snap {
+			let (forward, _, _) = Quat::from(head_rotation).to_euler(EulerRot::YXZ);
+			target = forward + ((target - forward) / YAW_SNAP).round() * YAW_SNAP;
+		}
+		let turn = Quat::from_rotation_y(target - yaw);
+		let _ = content_parent.set_relative_transform(
+			&reference,
+			Transform {
+				translation: None,
+				rotation: Some((turn * rotation).into()),
+				scale: None,
+			},
+		);
+	});
+}
+
 impl ItemUIHandler<PanelItem> for PanelItemUIHandler {
 	fn item_created(&mut self, uid: String, item: PanelItem, init_data: PanelItemInitData) {
-		let Ok(ui) = PanelItemUI::new(item.alias(), init_data, self.acceptors_rx.clone()) else {
+		let Ok(ui) = PanelItemUI::new(uid.clone(), item.alias(), init_data, self) else {
 			return;
 		};
 		let Ok(ui) = item.wrap(ui) else { return };
+		ui.lock_wrapped().set_brightness(self.ambient.brightness());
+		let app_id = ui.lock_wrapped().app_id.clone();
+		let title = ui.lock_wrapped().title.clone();
+		let tombstone = self
+			.tombstones
+			.iter()
+			.position(|tombstone| Some(tombstone.app_id.as_str()) == app_id.as_deref())
+			.map(|index| self.tombstones.remove(index));
+		if let Some(Tombstone { entry, .. }) = tombstone {
+			// Taking the crashed panel's slot, so its closed entry would only place a later one on top
+			self.take_closed_panel(entry.app_id.as_deref(), entry.title.as_deref());
+			ui.lock_wrapped().apply_layout(&self.reference, &entry);
+			if let Some(acceptor_uid) = entry.group {
+				self.acceptors
+					.capture(&acceptor_uid, &ui.lock_wrapped().panel_item);
+			}
+			event_log::record(Some(&uid), "Took over a tombstone");
+		} else if let Some(index) = self
+			.pending_layouts
+			.iter()
+			.position(|entry| entry.matches(app_id.as_deref()))
+		{
+			let entry = self.pending_layouts.remove(index);
+			ui.lock_wrapped().apply_layout(&self.reference, &entry);
+		} else if let Some(entry) = self.take_closed_panel(app_id.as_deref(), title.as_deref()) {
+			ui.lock_wrapped().apply_layout(&self.reference, &entry);
+			if let Some(acceptor_uid) = entry.group.filter(|_| self.config.recapture) {
+				self.acceptors
+					.capture(&acceptor_uid, &ui.lock_wrapped().panel_item);
+			}
+		} else {
+			if let Some(app_size) = app_id
+				.as_ref()
+				.and_then(|app_id| self.state.app_sizes.get(app_id))
+			{
+				ui.lock_wrapped().apply_app_size(app_size);
+			}
+			if let Some(script) = &mut self.placement_script {
+				let others = self
+					.items
+					.values()
+					.map(|item| item.lock_wrapped())
+					.collect::<Vec<_>>();
+				let others = others.iter().map(|o| o.app_info()).collect::<Vec<_>>();
+				let mut ui = ui.lock_wrapped();
+				if let Some(placement) = script.place(ui.app_info(), &others) {
+					ui.apply_placement(&self.reference, placement);
+				}
+			}
+		}
+		event_log::record(
+			Some(&uid),
+			format!("Created for {}", app_id.as_deref().unwrap_or("unknown app")),
+		);
+		self.run_hook(HookEvent::PanelCreated, &ui.lock_wrapped());
 		self.items.insert(uid.to_string(), ui);
+		self.order.push(uid.to_string());
 	}
 	fn item_captured(&mut self, uid: String, acceptor_uid: String) {
 		if let Some(ui) = self.items.get(&uid) {
-			ui.lock_wrapped().captured(&acceptor_uid);
+			let mut ui = ui.lock_wrapped();
+			ui.captured(&acceptor_uid);
+			event_log::record(Some(&uid), format!("Captured by {acceptor_uid}"));
+			self.acceptors.captured::<PanelItem>();
+			self.run_hook(HookEvent::Captured, &ui);
+			self.config
+				.speech
+				.announce(Announcement::Captured, ui.spoken_name());
+			drop(ui);
+			self.tutorial_event(TutorialEvent::Captured);
 		}
 	}
 	fn item_released(&mut self, uid: String, acceptor_uid: String) {
 		if let Some(ui) = self.items.get(&uid) {
-			ui.lock_wrapped().released(&acceptor_uid);
+			let mut ui = ui.lock_wrapped();
+			self.emit(
+				HookEvent::Released,
+				HookContext {
+					acceptor_uid: Some(&acceptor_uid),
+					..ui.hook_context()
+				},
+			);
+			ui.released(&acceptor_uid);
+			event_log::record(Some(&uid), format!("Released by {acceptor_uid}"));
+			self.acceptors.released::<PanelItem>();
+			self.config
+				.speech
+				.announce(Announcement::Released, ui.spoken_name());
 		}
 	}
 	fn item_destroyed(&mut self, uid: String) {
-		self.items.remove(&uid);
+		if self.focused.as_deref() == Some(&uid) {
+			self.focused = None;
+		}
+		// The wall picks sizes itself, those aren't the user's choice
+		let on_wall = self
+			.wall
+			.as_ref()
+			.is_some_and(|wall| wall.panels.contains(&uid));
+		if let Some(wall) = &mut self.wall {
+			wall.remove(&uid);
+		}
+		if let Some(pocket) = &mut self.pocket {
+			pocket.forget(&uid);
+		}
+		self.order.retain(|p| p != &uid);
+		self.energy_saver.forget(&uid);
+		self.proximity_cue.forget(&uid);
+		self.focus_history.retain(|p| p != &uid);
+		let mut last_interaction = None;
+		if let Some(ui) = self.items.remove(&uid) {
+			let ui = ui.lock_wrapped();
+			event_log::record(Some(&uid), "Destroyed");
+			self.run_hook(HookEvent::PanelDestroyed, &ui);
+			// Orbit closed it, it could have been quit from the keyboard, or the app is
+			// evidently still running, so only the rest can have crashed
+			let app_running = self
+				.items
+				.values()
+				.any(|item| item.lock_wrapped().app_id == ui.app_id);
+			if !ui.captured && !ui.close_requested && !ui.focused && !app_running {
+				last_interaction = Some(ui.last_interaction);
+			}
+			if let (Some(app_id), false) = (&ui.app_id, ui.captured || on_wall) {
+				self.state.app_sizes.insert(app_id.clone(), ui.app_size());
+				self.save_state();
+			}
+		}
+		let last_pose = self.last_poses.lock().unwrap().remove(&uid);
+		if let (Some(entry), Some(last_interaction)) = (&last_pose, last_interaction) {
+			if self.now - last_interaction > CRASH_GRACE {
+				self.leave_tombstone(&uid, entry.clone());
+			}
+		}
+		if let Some(entry) = last_pose.filter(|entry| entry.app_id.is_some()) {
+			let closed = &mut self.state.closed_panels;
+			closed.retain(|e| !e.matches_exactly(entry.app_id.as_deref(), entry.title.as_deref()));
+			closed.push(entry);
+			if closed.len() > CLOSED_PANELS_LEN {
+				closed.remove(0);
+			}
+			self.save_state();
+		}
 	}
 
 	fn acceptor_created(
@@ -67,191 +1273,993 @@ impl ItemUIHandler<PanelItem> for PanelItemUIHandler {
 		acceptor: ItemAcceptor<PanelItem>,
 		field: UnknownField,
 	) {
-		self.acceptors_tx.send_modify(|a| {
-			a.insert(acceptor_uid, (acceptor, field));
-		});
+		event_log::record(None, format!("Acceptor {acceptor_uid} appeared"));
+		self.acceptors
+			.acceptor_created(acceptor_uid, acceptor, field);
 	}
 	fn acceptor_destroyed(&mut self, acceptor_uid: String) {
-		self.acceptors_tx.send_modify(|a| {
-			a.remove(&acceptor_uid);
-		});
+		event_log::record(None, format!("Acceptor {acceptor_uid} went away"));
+		self.acceptors
+			.acceptor_destroyed::<PanelItem>(&acceptor_uid);
 	}
 }
 
-const PANEL_WIDTH: f32 = 0.1;
 const PANEL_THICKNESS: f32 = 0.01;
-const MAX_ACCEPT_DISTANCE: f32 = 0.05;
+const PRIVACY_BUTTON_SIZE: f32 = 0.01;
+/// How many previously focused panels are remembered
+const FOCUS_HISTORY_LEN: usize = 32;
+/// How far in front of the head recalled panels end up, in meters
+const RECALL_DISTANCE: f32 = 0.5;
+/// Space left between gathered panels, in meters
+const GATHER_GAP: f32 = 0.02;
+/// Step released panels' yaw is rounded to when snapping, from where the head faces
+const YAW_SNAP: f32 = std::f32::consts::FRAC_PI_4;
+/// How many closed panels' places are remembered
+const CLOSED_PANELS_LEN: usize = 32;
+/// Seconds between checks of where every panel is
+const POSE_CHECK_INTERVAL: f32 = 2.0;
+/// Seconds between checks for panels stuck hidden by a capture that's no longer there
+const WATCHDOG_INTERVAL: f32 = 5.0;
+/// Seconds a panel's acceptor has to be gone before the panel is freed, in case the release is just late
+const ORPHAN_GRACE: f32 = 10.0;
+/// Factor each zoom command magnifies or shrinks content by
+const ZOOM_STEP: f32 = 1.25;
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 4.0;
+/// How far edges of panels without focus are pulled toward grey
+const UNFOCUSED_DESATURATION: f32 = 0.35;
+/// Seconds since a panel was last touched or grabbed before its disappearance can count as a crash
+const CRASH_GRACE: f32 = 5.0;
+/// Seconds the edge shows the limit color after a size was clamped
+const LIMIT_CUE: f32 = 0.4;
+/// Seconds the edge shows the rejected color after an acceptor didn't take the panel
+const REJECTED_CUE: f32 = 0.6;
+
+/// Pixels from a toplevel's size hint, where 0 on an axis means that axis is free.
+fn size_hint(hint: Option<Vector2<f32>>, bound: u32) -> Option<[u32; 2]> {
+	let hint = hint?;
+	let side = |side: f32| {
+		if side > 0.0 {
+			side.round() as u32
+		} else {
+			bound
+		}
+	};
+	Some([side(hint.x), side(hint.y)])
+}
+/// Within the toplevel's hints, never under one pixel, with the minimum winning when the hints cross.
+fn clamp_size(size: [u32; 2], min: Option<[u32; 2]>, max: Option<[u32; 2]>) -> [u32; 2] {
+	let min = min.unwrap_or([1; 2]).map(|side| side.max(1));
+	let max = max.unwrap_or([u32::MAX; 2]);
+	[0, 1].map(|i| size[i].clamp(min[i], max[i].max(min[i])))
+}
 struct PanelItemUI {
+	uid: String,
 	captured: bool,
+	focused: bool,
+	focus_requested: bool,
+	grab_stopped: bool,
+	resized: bool,
+	/// Double tapped upright this frame
+	leveled: bool,
+	/// When the panel was last touched or grabbed, in seconds since startup
+	last_interaction: f32,
+	/// Orbit asked the app to close it, so its disappearance is no crash
+	close_requested: bool,
+	/// Content magnification, done by asking for fewer pixels over the same face
+	zoom: f32,
+	/// The toplevel's size hints, never asked to be smaller or bigger than these
+	min_size: Option<[u32; 2]>,
+	max_size: Option<[u32; 2]>,
+	hit_size_limit: bool,
+	limit_cue_until: Option<f32>,
+	rejected_until: Option<f32>,
+	rejection_sound: Option<Sound>,
+	hidden: bool,
+	streaming_hidden: bool,
+	/// Out of range while saving energy, hidden and skipping per-frame work
+	suspended: bool,
+	urgency: UrgencyConfig,
+	attention: Option<Attention>,
+	proximity_sound: Option<Sound>,
+	badge: u32,
+	privacy_button: Button,
+	theme: Theme,
+	/// Standing in for the idle edge color once found, in the standard theme
+	accent: Option<Accent>,
+	accent_rx: Option<oneshot::Receiver<Option<Accent>>>,
+	/// Edge brightness for the room's light
+	brightness: f32,
+	backing_config: BackingPlateConfig,
+	/// Set per panel, otherwise the theme decides
+	backing_override: Option<bool>,
+	backing_plate: Option<BackingPlate>,
+	floor_marker_config: FloorMarkerConfig,
+	floor_marker: Option<FloorMarker>,
+	focus_sweep: Option<FocusSweep>,
+	aging: Option<Aging>,
+	dev_overlay: Option<DevOverlay>,
+	identity_label: Option<IdentityLabel>,
+	hand: Hand,
+	input: PanelInput,
+	captured_by: Option<String>,
+	/// When the watchdog first found the acceptor holding this panel gone
+	orphaned_since: Option<f32>,
+	preview: Option<CapturePreview>,
+	/// Released out of a preview, so it goes back to the hand instead of staying where the acceptor had it
+	returning_to_hand: bool,
+	title: Option<String>,
+	app_id: Option<String>,
+	size: Vector2<u32>,
+	physical_size: Vector2<f32>,
+	panel_width: f32,
+	decorators: Vec<Box<dyn Decorator>>,
 	panel_item: PanelItem,
 	model: Model,
+	/// Looked up once, the model's parts don't change
+	face: ModelPart,
+	edge: MaterialCache,
 	field: BoxField,
+	handle_bar: Option<HandleBar>,
+	rotation_ring: Option<RotationRing>,
+	rails: Option<GrabRails>,
+	grab_smoothing: Option<GrabSmoothing>,
+	arbiter: Option<GrabArbiter>,
+	double_tap: DoubleTap,
 	grabbable: Grabbable,
-	acceptors: watch::Receiver<FxHashMap<String, (ItemAcceptor<PanelItem>, UnknownField)>>,
+	reference: Spatial,
+	acceptor_link: AcceptorLink<PanelItem>,
 	// update_position_task: JoinHandle<()>,
 }
 impl PanelItemUI {
 	fn new(
+		uid: String,
 		panel_item: PanelItem,
 		init_data: PanelItemInitData,
-		acceptors: watch::Receiver<FxHashMap<String, (ItemAcceptor<PanelItem>, UnknownField)>>,
+		handler: &PanelItemUIHandler,
 	) -> Result<Self, NodeError> {
+		let config = &handler.config;
 		let field = BoxField::create(
 			&panel_item,
 			Transform::identity(),
-			[PANEL_WIDTH, PANEL_WIDTH, PANEL_THICKNESS],
+			[config.panel_width, config.panel_width, PANEL_THICKNESS],
 		)?;
+		let handle_bar = match config.grab_mode {
+			GrabMode::HandleBar => Some(HandleBar::create(&panel_item, PANEL_THICKNESS)?),
+			GrabMode::Panel | GrabMode::Arbitrated => None,
+		};
+		let rotation_ring = config
+			.rotation_ring
+			.then(|| RotationRing::create(&panel_item))
+			.transpose()?;
+		let arbiter = (config.grab_mode == GrabMode::Arbitrated)
+			.then(|| GrabArbiter::new(config.arbitration));
 		let grabbable = Grabbable::create(
 			&panel_item,
 			Transform::identity(),
-			&field,
-			GrabbableSettings::default(),
+			handle_bar.as_ref().map_or(&field, HandleBar::field),
+			config.grabbable_settings(),
 		)?;
 		let model = Model::create(
 			&panel_item,
-			Transform::from_scale([PANEL_WIDTH, PANEL_WIDTH, PANEL_THICKNESS]),
-			&ResourceID::new_namespaced("orbit", "panel"),
+			Transform::from_scale([config.panel_width, config.panel_width, PANEL_THICKNESS]),
+			&config
+				.frame_model
+				.as_ref()
+				.and_then(|path| ResourceID::new_direct(path).ok())
+				.unwrap_or_else(|| ResourceID::new_namespaced("orbit", "panel")),
+		)?;
+
+		let privacy_button = Self::privacy_button(&panel_item, config.theme)?;
+
+		let input = PanelInput::create(
+			&panel_item,
+			PANEL_THICKNESS,
+			config.dominant_hand,
+			config.input.for_app(init_data.toplevel.app_id.as_deref()),
+			config.input.scroll,
+			handler.face,
 		)?;
 
 		panel_item.auto_size_toplevel()?;
-		panel_item.apply_surface_material(&SurfaceID::Toplevel, &model.model_part("Face")?)?;
+		let face = model.model_part("Face")?;
+		let edge = model.model_part("Edge")?;
+		panel_item.apply_surface_material(&SurfaceID::Toplevel, &face)?;
 		panel_item.set_spatial_parent_in_place(grabbable.content_parent())?;
 
-		let closest_acceptor_distance = Arc::new(Mutex::new((String::new(), f32::MAX)));
-		let _closest_acceptor_distance = closest_acceptor_distance.clone();
-
 		let mut panel_item_ui = PanelItemUI {
+			uid,
 			captured: false,
+			focused: false,
+			focus_requested: false,
+			grab_stopped: false,
+			resized: false,
+			leveled: false,
+			last_interaction: 0.0,
+			close_requested: false,
+			zoom: 1.0,
+			min_size: size_hint(init_data.toplevel.min_size, 1),
+			max_size: size_hint(init_data.toplevel.max_size, u32::MAX),
+			hit_size_limit: false,
+			limit_cue_until: None,
+			rejected_until: None,
+			rejection_sound: config
+				.rejection_sound
+				.as_ref()
+				.and_then(|path| ResourceID::new_direct(path).ok())
+				.map(|resource| Sound::create(&panel_item, Transform::identity(), &resource))
+				.transpose()?,
+			hidden: false,
+			streaming_hidden: false,
+			suspended: false,
+			urgency: config.urgency.clone(),
+			attention: None,
+			proximity_sound: config.proximity_cue.create_sound(&panel_item)?,
+			badge: 0,
+			privacy_button,
+			theme: config.theme,
+			accent: None,
+			accent_rx: init_data
+				.toplevel
+				.app_id
+				.clone()
+				.filter(|_| config.icon_accent)
+				.map(Accent::from_icon),
+			brightness: 1.0,
+			backing_config: config.backing_plate.clone(),
+			backing_override: None,
+			backing_plate: None,
+			floor_marker_config: config.floor_marker.clone(),
+			floor_marker: None,
+			dev_overlay: None,
+			identity_label: None,
+			focus_sweep: None,
+			aging: Aging::new(&config.aging),
+			hand: config.dominant_hand,
+			input,
+			captured_by: None,
+			orphaned_since: None,
+			preview: None,
+			returning_to_hand: false,
+			title: init_data.toplevel.title.clone(),
+			app_id: init_data.toplevel.app_id.clone(),
+			size: init_data.toplevel.size,
+			physical_size: [config.panel_width, config.panel_width].into(),
+			panel_width: config.panel_width,
+			decorators: handler.decorators.instantiate(&config.decorators),
 			panel_item,
 			model,
+			face,
+			edge: MaterialCache::new(edge),
 			field,
+			handle_bar,
+			rotation_ring,
+			rails: config.grab_rails.then(GrabRails::default),
+			grab_smoothing: config.grab_smoothing.map(GrabSmoothing::new),
+			arbiter,
+			double_tap: DoubleTap::default(),
 			grabbable,
-			acceptors,
+			reference: handler.reference.alias(),
+			acceptor_link: handler
+				.acceptors
+				.link()
+				.with_preview(config.capture_preview),
 			// update_position_task,
 		};
 		panel_item_ui.on_resize(init_data.toplevel.size);
+		panel_item_ui.refresh_backing_plate();
+		panel_item_ui.refresh_floor_marker();
+		panel_item_ui.set_dev_overlay(config.dev_overlay);
+		panel_item_ui.set_identity_label(config.identity_labels);
+		if init_data
+			.toplevel
+			.app_id
+			.as_ref()
+			.is_some_and(|app_id| config.frameless_apps.contains(app_id))
+		{
+			panel_item_ui.set_frameless(true);
+		}
+		if let Some(title) = &init_data.toplevel.title {
+			panel_item_ui.toplevel_title_changed(title);
+		}
+		panel_item_ui.decorate(|d, ctx| d.created(ctx));
 		Ok(panel_item_ui)
 	}
-	fn captured(&mut self, _acceptor_uid: &str) {
+	fn privacy_button(panel_item: &PanelItem, theme: Theme) -> Result<Button, NodeError> {
+		Button::create(
+			panel_item,
+			Transform::identity(),
+			[PRIVACY_BUTTON_SIZE * theme.target_scale(); 2],
+			ButtonSettings::default(),
+		)
+	}
+	fn set_theme(&mut self, theme: Theme) {
+		if self.theme == theme {
+			return;
+		}
+		self.theme = theme;
+		if let Ok(privacy_button) = Self::privacy_button(&self.panel_item, theme) {
+			self.privacy_button = privacy_button;
+		}
+		self.on_resize(self.size);
+		self.refresh_edge_color();
+		self.refresh_backing_plate();
+		self.refresh_floor_marker();
+	}
+	fn refresh_floor_marker(&mut self) {
+		let enabled = self.floor_marker_config.themes.contains(&self.theme);
+		if enabled == self.floor_marker.is_some() {
+			return;
+		}
+		self.floor_marker = if enabled {
+			FloorMarker::create(&self.reference, self.panel_width / 2.0).ok()
+		} else {
+			None
+		};
+	}
+	fn refresh_backing_plate(&mut self) {
+		let enabled = self
+			.backing_override
+			.unwrap_or_else(|| self.backing_config.themes.contains(&self.theme));
+		if enabled == self.backing_plate.is_some() {
+			return;
+		}
+		self.backing_plate = if enabled {
+			BackingPlate::create(&self.model, self.physical_size, self.backing_config.opacity).ok()
+		} else {
+			None
+		};
+	}
+	/// The edge is scaled away rather than hidden, model parts can't be disabled on their own.
+	fn set_frameless(&mut self, frameless: bool) {
+		let scale = if frameless { 0.0 } else { 1.0 };
+		let _ = self
+			.edge
+			.part()
+			.set_local_transform(Transform::from_scale([scale; 3]));
+	}
+	fn set_dev_overlay(&mut self, enabled: bool) {
+		if enabled == self.dev_overlay.is_some() {
+			return;
+		}
+		self.input.set_logging(enabled);
+		self.dev_overlay = if enabled {
+			DevOverlay::create(&self.panel_item, self.physical_size).ok()
+		} else {
+			None
+		};
+	}
+	fn set_identity_label(&mut self, enabled: bool) {
+		if enabled == self.identity_label.is_some() {
+			return;
+		}
+		self.identity_label = if enabled {
+			IdentityLabel::create(
+				&self.panel_item,
+				self.physical_size,
+				&self.uid,
+				self.app_id.as_deref(),
+			)
+			.ok()
+		} else {
+			None
+		};
+	}
+	fn captured(&mut self, acceptor_uid: &str) {
 		println!("Captured");
+		self.acceptor_link.captured();
+		self.captured_by = Some(acceptor_uid.to_string());
 		self.update_state(true);
 		self.grabbable.cancel_linear_velocity();
 		self.grabbable.cancel_angular_velocity();
+		if self.grabbable.grab_action().actor_acting() {
+			self.preview = Some(CapturePreview::default());
+			let _ = self.grabbable.set_enabled(true);
+		}
 	}
 	fn released(&mut self, _acceptor_uid: &str) {
 		println!("Released");
+		self.captured_by = None;
+		self.preview = None;
 		self.update_state(false);
-		let _ = self
-			.grabbable
-			.content_parent()
-			.set_relative_transform(&self.panel_item, Transform::identity());
+		if !std::mem::take(&mut self.returning_to_hand) {
+			let _ = self
+				.grabbable
+				.content_parent()
+				.set_relative_transform(&self.panel_item, Transform::identity());
+		}
 		let _ = self.panel_item.set_local_transform(Transform::identity());
 	}
+	/// Whether the panel has been left captured by a missing acceptor for [`ORPHAN_GRACE`],
+	/// as lost release events would otherwise leave it hidden and ungrabbable for good.
+	fn orphan_expired(&mut self, orphaned: bool, now: f32) -> bool {
+		if !orphaned {
+			self.orphaned_since = None;
+			return false;
+		}
+		let since = *self.orphaned_since.get_or_insert(now);
+		if now - since < ORPHAN_GRACE {
+			return false;
+		}
+		self.orphaned_since = None;
+		true
+	}
 	fn update_state(&mut self, captured: bool) {
 		self.captured = captured;
-		let _ = self.model.set_enabled(!captured);
+		let _ = self.model.set_enabled(!captured && !self.suspended);
 		let _ = self.grabbable.set_enabled(!captured);
+		if let Some(handle_bar) = &self.handle_bar {
+			handle_bar.set_enabled(!captured);
+		}
+		if let Some(rotation_ring) = &self.rotation_ring {
+			rotation_ring.set_enabled(!captured);
+		}
+		if let (true, Some(floor_marker)) = (captured, &mut self.floor_marker) {
+			floor_marker.hide();
+		}
 	}
-	fn frame(&mut self, handler: &PanelItemUIHandler, info: &FrameInfo) {
+	fn frame(&mut self, info: &FrameInfo) {
 		if self.captured {
+			self.update_preview(info);
 			return;
 		}
 		self.grabbable.update(info).unwrap();
-		self.update_distances(
-			handler,
-			!self.grabbable.grab_action().actor_acting() && self.grabbable.linear_speed().is_some()
-				|| self.grabbable.grab_action().actor_stopped(),
+		if self.grabbable.grab_action().actor_started() {
+			self.focus_requested = true;
+		}
+		if self.grabbable.grab_action().actor_stopped() {
+			self.grab_stopped = true;
+		}
+		if self.suspended {
+			return;
+		}
+		if self.double_tap.update(
+			self.grabbable.grab_action().actor_started(),
+			self.grabbable.grab_action().actor_stopped(),
+			info.elapsed as f32,
+		) {
+			self.leveled = true;
+			self.reset_transform();
+		}
+		if let Some(rotation_ring) = &mut self.rotation_ring {
+			rotation_ring.update(info, self.grabbable.content_parent(), &self.reference);
+		}
+		if let Some(rails) = &mut self.rails {
+			rails.update(&self.grabbable, &self.reference, info.elapsed as f32);
+		}
+		if let Some(grab_smoothing) = &mut self.grab_smoothing {
+			grab_smoothing.update(&self.grabbable, &self.reference, info.elapsed as f32);
+		}
+		if let Some(floor_marker) = &mut self.floor_marker {
+			floor_marker.update(self.grabbable.content_parent(), &self.reference);
+		}
+		if let Some(arbiter) = &mut self.arbiter {
+			arbiter.update(
+				&self.grabbable,
+				&self.panel_item,
+				&self.reference,
+				info.elapsed as f32,
+			);
+		}
+		self.privacy_button.update();
+		if self.privacy_button.pressed() {
+			self.set_hidden(!self.shuttered());
+		}
+		self.input.update(info.elapsed as f32);
+		if std::mem::take(&mut self.hit_size_limit) {
+			self.limit_cue_until = Some(info.elapsed as f32 + LIMIT_CUE);
+			self.refresh_edge_color();
+		} else if self
+			.limit_cue_until
+			.is_some_and(|until| info.elapsed as f32 >= until)
+		{
+			self.limit_cue_until = None;
+			self.refresh_edge_color();
+		}
+		if let Some(dev_overlay) = &mut self.dev_overlay {
+			dev_overlay.update(self.input.pointer_position(), self.input.log());
+		}
+		self.poll_accent();
+		if let Some(aging) = &mut self.aging {
+			if aging.update(self.focused, info.elapsed as f32) {
+				self.refresh_edge_color();
+			}
+		}
+		if let Some(focus_sweep) = &mut self.focus_sweep {
+			if !focus_sweep.update(info.elapsed as f32) {
+				self.focus_sweep = None;
+			}
+		}
+		if self.input.interacting() || self.grabbable.grab_action().actor_acting() {
+			self.last_interaction = info.elapsed as f32;
+		}
+		self.decorate(|d, ctx| d.frame(ctx, info));
+		self.acceptor_link.update(
+			&self.panel_item,
+			self.grabbable.grab_action().actor_acting(),
+			acceptor_link::released(
+				self.grabbable.grab_action().actor_acting(),
+				self.grabbable.linear_speed().is_some(),
+				self.grabbable.grab_action().actor_stopped(),
+			),
 		);
+		self.update_rejection(info);
+		if let Some(attention) = &mut self.attention {
+			let color = attention.update(info.elapsed as f32);
+			self.edge.set("color", dimmed(color, self.brightness));
+		} else {
+			self.update_edge_color();
+		}
 	}
 
-	fn update_distances(&self, handler: &PanelItemUIHandler, accept: bool) {
-		if self.captured {
+	/// Stops a thrown panel where it was let go instead of drifting past an acceptor that turned it down.
+	fn update_rejection(&mut self, info: &FrameInfo) {
+		if self.acceptor_link.take_rejection(&self.panel_item) {
+			event_log::record(Some(&self.uid), "Capture turned down");
+			self.rejected_until = Some(info.elapsed as f32 + REJECTED_CUE);
+			self.grabbable.cancel_linear_velocity();
+			self.grabbable.cancel_angular_velocity();
+			if let Some(sound) = &self.rejection_sound {
+				let _ = sound.play();
+			}
+			self.refresh_edge_color();
+		} else if self
+			.rejected_until
+			.is_some_and(|until| info.elapsed as f32 >= until)
+		{
+			self.rejected_until = None;
+			self.refresh_edge_color();
+		}
+	}
+
+	fn update_preview(&mut self, info: &FrameInfo) {
+		let Some(preview) = &mut self.preview else {
 			return;
+		};
+		self.grabbable.update(info).unwrap();
+		match preview.update(&self.grabbable, &self.panel_item, info.elapsed as f32) {
+			PreviewOutcome::Pending => (),
+			PreviewOutcome::Keep => {
+				self.preview = None;
+				let _ = self.grabbable.set_enabled(false);
+			}
+			PreviewOutcome::Return => {
+				self.preview = None;
+				self.returning_to_hand = true;
+				let _ = self.panel_item.release();
+			}
 		}
-		if self.acceptors.borrow().is_empty() {
+	}
+
+	fn set_panel_width(&mut self, panel_width: f32) {
+		self.panel_width = panel_width;
+		self.on_resize(self.size);
+	}
+
+	fn set_suspended(&mut self, suspended: bool) {
+		if self.suspended == suspended {
 			return;
 		}
-		let keys = handler
-			.acceptors_tx
-			.borrow()
-			.keys()
-			.cloned()
-			.collect::<Vec<String>>();
-		let acceptors = self.acceptors.clone();
+		self.suspended = suspended;
+		let _ = self.model.set_enabled(!suspended && !self.captured);
+		if let (true, Some(floor_marker)) = (suspended, &mut self.floor_marker) {
+			floor_marker.hide();
+		}
+	}
 
-		let model = self.model.alias();
+	fn set_brightness(&mut self, brightness: f32) {
+		if self.brightness == brightness {
+			return;
+		}
+		self.brightness = brightness;
+		if self.attention.is_none() {
+			self.refresh_edge_color();
+		}
+	}
+
+	fn poll_accent(&mut self) {
+		let Some(accent_rx) = &mut self.accent_rx else {
+			return;
+		};
+		match accent_rx.try_recv() {
+			Ok(accent) => {
+				self.accent = accent;
+				self.accent_rx = None;
+				self.refresh_edge_color();
+			}
+			Err(oneshot::error::TryRecvError::Empty) => (),
+			Err(oneshot::error::TryRecvError::Closed) => self.accent_rx = None,
+		}
+	}
+
+	fn update_edge_color(&mut self) {
+		if !self.acceptor_link.closest().has_changed().unwrap_or(false) {
+			return;
+		}
+		self.acceptor_link.closest().borrow_and_update();
+		self.refresh_edge_color();
+	}
+	fn refresh_edge_color(&mut self) {
+		let color = if self.shuttered() {
+			self.theme.shuttered_edge()
+		} else if self.rejected_until.is_some() {
+			self.theme.rejected_edge()
+		} else if self.limit_cue_until.is_some() {
+			self.theme.limit_edge()
+		} else {
+			match self.acceptor_link.closest().borrow().as_ref() {
+				Some(closest) => proximity_color(Some(closest)),
+				None => match self.accent {
+					Some(accent) if self.theme == Theme::Standard => accent.edge(),
+					_ => self.theme.idle_edge(),
+				},
+			}
+		};
+		let color = if self.focused {
+			color
+		} else {
+			desaturated(color, UNFOCUSED_DESATURATION)
+		};
+		let brightness = self.brightness * self.aging.as_ref().map_or(1.0, Aging::brightness);
+		self.edge.set("color", dimmed(color, brightness));
+	}
+
+	/// The face's physical size only follows the panel width, so a smaller toplevel shows its content bigger.
+	/// Apps lay themselves out again for the new size, there's no way to map part of a texture onto the face.
+	fn set_zoom(&mut self, zoom: f32) {
+		let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+		if zoom == self.zoom {
+			return;
+		}
+		let scale = self.zoom / zoom;
+		self.zoom = zoom;
+		self.input.set_two_finger_pan(zoom > 1.0);
+		let size = [
+			((self.size.x as f32 * scale).round() as u32).max(1),
+			((self.size.y as f32 * scale).round() as u32).max(1),
+		];
+		self.request_size(size);
+	}
+	/// Every size Orbit picks goes through here, clamped to the toplevel's hints with a cue when that changed it.
+	fn request_size(&mut self, size: [u32; 2]) {
+		let clamped = clamp_size(size, self.min_size, self.max_size);
+		if clamped != size {
+			self.hit_size_limit = true;
+		}
+		let _ = self.panel_item.set_toplevel_size(clamped.into());
+	}
+
+	fn shuttered(&self) -> bool {
+		self.hidden || self.streaming_hidden
+	}
+	fn set_hidden(&mut self, hidden: bool) {
+		let was_shuttered = self.shuttered();
+		self.hidden = hidden;
+		self.update_shutter(was_shuttered);
+	}
+	/// Kept apart from `hidden` so leaving streaming mode doesn't reveal panels hidden by hand.
+	fn set_streaming_hidden(&mut self, streaming_hidden: bool) {
+		let was_shuttered = self.shuttered();
+		self.streaming_hidden = streaming_hidden;
+		self.update_shutter(was_shuttered);
+	}
+	/// Swaps the face for a holdout so nothing of the surface shows, even in passthrough captures.
+	fn update_shutter(&mut self, was_shuttered: bool) {
+		let shuttered = self.shuttered();
+		if shuttered == was_shuttered {
+			return;
+		}
+		let _ = if shuttered {
+			self.face.apply_holdout_material()
+		} else {
+			self.panel_item
+				.apply_surface_material(&SurfaceID::Toplevel, &self.face)
+		};
+		self.refresh_edge_color();
+	}
+	fn spoken_name(&self) -> Option<&str> {
+		self.app_id.as_deref().or(self.title.as_deref())
+	}
+	fn summary(&self) -> PanelSummary {
+		PanelSummary {
+			uid: self.uid.clone(),
+			app_id: self.app_id.clone(),
+			title: self.title.clone(),
+			focused: self.focused,
+			captured: self.captured,
+			hidden: self.shuttered(),
+			view_only: self.input.view_only(),
+			urgent: self.attention.is_some(),
+			badge: self.badge,
+			suspended: self.suspended,
+		}
+	}
+
+	fn set_focused(&mut self, focused: bool) {
+		if focused && !self.focused {
+			self.focus_sweep =
+				FocusSweep::create(&self.panel_item, self.physical_size, PANEL_THICKNESS).ok();
+		}
+		self.focused = focused;
+		if focused {
+			self.set_urgent(false);
+		}
+		self.refresh_edge_color();
+		let _ = self.panel_item.set_toplevel_focused_visuals(focused);
+		self.decorate(|d, ctx| d.focus_changed(ctx));
+	}
+
+	fn apply_action(&mut self, action: PanelAction) {
+		match action {
+			PanelAction::Hide => self.set_hidden(true),
+			PanelAction::Reveal => self.set_hidden(false),
+			PanelAction::ViewOnly => self.input.set_view_only(true),
+			PanelAction::Interactive => self.input.set_view_only(false),
+			PanelAction::Close => self.close(),
+			PanelAction::DemandAttention => self.set_urgent(true),
+			PanelAction::SetBadge { count } => self.badge = count,
+			PanelAction::SetZoom { zoom } => self.set_zoom(zoom),
+			PanelAction::SetBackingPlate { enabled } => {
+				self.backing_override = enabled;
+				self.refresh_backing_plate();
+			}
+			PanelAction::SetFrameless { enabled } => self.set_frameless(enabled),
+			PanelAction::SetDevOverlay { enabled } => self.set_dev_overlay(enabled),
+		}
+	}
+	fn play_proximity_cue(&self) {
+		if let Some(sound) = &self.proximity_sound {
+			let _ = sound.play();
+		}
+	}
+	/// Every close Orbit asks for goes through here, so it doesn't leave a tombstone.
+	fn close(&mut self) {
+		self.close_requested = true;
+		let _ = self.panel_item.close_toplevel();
+	}
+	/// Toplevels can't ask for attention over the panel protocol, so this is driven by title rules and IPC.
+	fn set_urgent(&mut self, urgent: bool) {
+		if urgent == self.attention.is_some() {
+			return;
+		}
+		self.attention = if urgent {
+			Attention::create(&self.panel_item, &self.urgency).ok()
+		} else {
+			None
+		};
+		self.refresh_edge_color();
+	}
+
+	fn decorate(&mut self, mut f: impl FnMut(&mut dyn Decorator, &DecoratorContext)) {
+		let ctx = DecoratorContext {
+			uid: &self.uid,
+			app_id: self.app_id.as_deref(),
+			title: self.title.as_deref(),
+			badge: self.badge,
+			panel_item: &self.panel_item,
+			model: &self.model,
+			size: self.physical_size,
+			pixel_size: self.size,
+			focused: self.focused,
+			hovered: self.input.hovered(),
+			theme: self.theme,
+		};
+		for decorator in &mut self.decorators {
+			f(decorator.as_mut(), &ctx);
+		}
+	}
+
+	fn hook_context(&self) -> HookContext {
+		HookContext {
+			uid: &self.uid,
+			app_id: self.app_id.as_deref(),
+			title: self.title.as_deref(),
+			acceptor_uid: self.captured_by.as_deref(),
+		}
+	}
+
+	fn layout_entry(&self, reference: &Spatial) -> impl Future<Output = Option<PanelLayout>> {
 		let panel_item = self.panel_item.alias();
-		let fields = acceptors
-			.borrow()
-			.values()
-			.map(|(_, f)| f.alias())
-			.collect::<Vec<_>>();
-		tokio::spawn(async move {
-			let distances = multi_node_call(fields.into_iter(), |f| {
-				let panel_item = panel_item.alias();
-				Ok(async move { f.distance(&panel_item, [0.0; 3]).await })
+		let reference = reference.alias();
+		let app_id = self.app_id.clone();
+		let title = self.title.clone();
+		let size = [self.size.x, self.size.y];
+		let group = self.captured_by.clone();
+		async move {
+			let transform = panel_item.get_transform(&reference).await.ok()?;
+			let translation = transform.translation?;
+			let rotation = transform.rotation?;
+			Some(PanelLayout {
+				app_id,
+				title,
+				translation: translation.into(),
+				rotation: [rotation.v.x, rotation.v.y, rotation.v.z, rotation.s],
+				size,
+				group,
 			})
-			.await;
-			// dbg!(&distances);
-			let Some((uid, distance)) = keys
-				.into_iter()
-				.zip(distances.into_iter().map(|d| d.map(|d| d.abs())))
-				.filter_map(|(k, v)| Some((k, v.ok()?)))
-				.reduce(
-					|(ak, av), (bk, bv)| {
-						if av > bv {
-							(bk, bv)
-						} else {
-							(ak, av)
-						}
-					},
-				)
-			else {
-				let _ = model.model_part("Edge").unwrap().set_material_parameter(
-					"color",
-					MaterialParameter::Color(rgba_linear!(1.0, 1.0, 1.0, 1.0)),
-				);
+		}
+	}
+	/// Back to the default size for the current pixel size, upright but still facing the same way.
+	fn reset_transform(&mut self) {
+		self.on_resize(self.size);
+		let content_parent = self.grabbable.content_parent().alias();
+		let reference = self.reference.alias();
+		tokio::spawn(async move {
+			let Ok(transform) = content_parent.get_transform(&reference).await else {
 				return;
 			};
-
-			let gradient = colorgrad::magma();
-			let color = gradient.at(distance.map_range(0.25..MAX_ACCEPT_DISTANCE, 0.0..1.0) as f64);
-			let _ = model.model_part("Edge").unwrap().set_material_parameter(
-				"color",
-				MaterialParameter::Color(rgba_linear!(
-					color.r as f32,
-					color.g as f32,
-					color.b as f32,
-					color.a as f32
-				)),
+			let rotation = transform.rotation.map(Quat::from).unwrap_or_default();
+			let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+			let _ = content_parent.set_relative_transform(
+				&reference,
+				Transform {
+					translation: None,
+					rotation: Some(Quat::from_rotation_y(yaw).into()),
+					scale: Some([1.0; 3].into()),
+				},
 			);
-			if accept && distance < MAX_ACCEPT_DISTANCE {
-				let Some(acceptor) = acceptors.borrow().get(&uid).map(|(a, _)| a.alias()) else {
-					return;
-				};
-				let _ = acceptor.capture(&panel_item);
-			}
 		});
 	}
+	fn app_info(&self) -> AppInfo {
+		AppInfo {
+			app_id: self.app_id.as_deref(),
+			title: self.title.as_deref(),
+			size: [self.size.x, self.size.y],
+			min_size: self.min_size,
+			max_size: self.max_size,
+		}
+	}
+	fn apply_placement(&mut self, reference: &Spatial, placement: Placement) {
+		if self.captured {
+			return;
+		}
+		if placement.position.is_some() || placement.yaw.is_some() {
+			let _ = self.grabbable.content_parent().set_relative_transform(
+				reference,
+				Transform {
+					translation: placement.position.map(Into::into),
+					rotation: placement
+						.yaw
+						.map(|yaw| Quat::from_rotation_y(yaw.to_radians()).into()),
+					scale: None,
+				},
+			);
+		}
+		if let Some(size) = placement.size {
+			self.request_size(size);
+		}
+	}
+	fn app_size(&self) -> AppSize {
+		AppSize {
+			size: [self.size.x, self.size.y],
+			zoom: self.zoom,
+		}
+	}
+	/// The size already has the zoom in it, the zoom is only set back so later zoom steps and pans start from it.
+	fn apply_app_size(&mut self, app_size: &AppSize) {
+		self.zoom = app_size.zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+		self.input.set_two_finger_pan(self.zoom > 1.0);
+		self.request_size(app_size.size);
+	}
+	fn apply_layout(&mut self, reference: &Spatial, entry: &PanelLayout) {
+		if self.captured {
+			return;
+		}
+		let _ = self.grabbable.content_parent().set_relative_transform(
+			reference,
+			Transform::from_translation_rotation(entry.translation, entry.rotation),
+		);
+		self.request_size(entry.size);
+	}
 
 	fn on_resize(&mut self, size: Vector2<u32>) {
+		self.size = size;
+		self.resized = true;
 		let aspect_ratio = size.y as f32 / size.x as f32;
-		let size = [PANEL_WIDTH, PANEL_WIDTH * aspect_ratio, PANEL_THICKNESS];
+		let size = [
+			self.panel_width,
+			self.panel_width * aspect_ratio,
+			PANEL_THICKNESS,
+		];
+		self.physical_size = [size[0], size[1]].into();
 		let _ = self.model.set_local_transform(Transform::from_scale(size));
 		let _ = self.field.set_size(size);
+		self.input.resize(self.physical_size, self.size);
+		if let Some(handle_bar) = &self.handle_bar {
+			handle_bar.resize(self.physical_size, PANEL_THICKNESS);
+		}
+		if let Some(rotation_ring) = &self.rotation_ring {
+			rotation_ring.resize(self.physical_size);
+		}
+		if let Some(backing_plate) = &self.backing_plate {
+			backing_plate.resize(self.physical_size);
+		}
+		if let Some(identity_label) = &self.identity_label {
+			identity_label.resize(self.physical_size);
+		}
+		if let Some(dev_overlay) = &self.dev_overlay {
+			dev_overlay.resize(self.physical_size);
+		}
+		let button_size = PRIVACY_BUTTON_SIZE * self.theme.target_scale();
+		let _ = self
+			.privacy_button
+			.touch_plane()
+			.root()
+			.set_local_transform(Transform::from_translation([
+				self.hand.mirror(size[0] / 2.0 - button_size / 2.0),
+				-size[1] / 2.0 - button_size,
+				0.0,
+			]));
+		self.decorate(|d, ctx| d.resized(ctx));
 	}
 }
 impl PanelItemHandler for PanelItemUI {
 	fn toplevel_size_changed(&mut self, size: mint::Vector2<u32>) {
 		self.on_resize(size);
 	}
+	fn toplevel_title_changed(&mut self, title: &str) {
+		self.title = Some(title.to_string());
+		if !self.focused && self.urgency.matches(title) {
+			self.set_urgent(true);
+		}
+	}
+	fn toplevel_app_id_changed(&mut self, app_id: &str) {
+		self.app_id = Some(app_id.to_string());
+		if let Some(identity_label) = &self.identity_label {
+			identity_label.set_app_id(app_id);
+		}
+	}
 
-	fn new_child(&mut self, _uid: &str, _info: ChildInfo) {}
-	fn reposition_child(&mut self, _uid: &str, _geometry: Geometry) {}
-	fn drop_child(&mut self, _uid: &str) {}
+	fn new_child(&mut self, uid: &str, info: ChildInfo) {
+		self.input.popups().add(uid, info);
+	}
+	fn reposition_child(&mut self, uid: &str, geometry: Geometry) {
+		self.input.popups().reposition(uid, geometry);
+	}
+	fn drop_child(&mut self, uid: &str) {
+		self.input.popups().remove(uid);
+	}
 }
 impl Drop for PanelItemUI {
 	fn drop(&mut self) {
 		// self.update_position_task.abort();
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn size_hints_leave_zero_axes_free() {
+		assert_eq!(size_hint(None, u32::MAX), None);
+		assert_eq!(
+			size_hint(Some([640.4, 0.0].into()), u32::MAX),
+			Some([640, u32::MAX])
+		);
+		assert_eq!(size_hint(Some([0.0, 479.6].into()), 1), Some([1, 480]));
+		assert_eq!(size_hint(Some([f32::NAN, -3.0].into()), 1), Some([1, 1]));
+	}
+
+	#[test]
+	fn sizes_stay_within_hints() {
+		assert_eq!(clamp_size([800, 600], None, None), [800, 600]);
+		assert_eq!(clamp_size([0, 0], None, None), [1, 1]);
+		assert_eq!(
+			clamp_size([100, 5000], Some([320, 240]), Some([1920, 1080])),
+			[320, 1080]
+		);
+		assert_eq!(
+			clamp_size([800, 600], Some([400, 0]), Some([u32::MAX, 300])),
+			[800, 300]
+		);
+	}
+
+	#[test]
+	fn crossed_hints_keep_the_minimum() {
+		assert_eq!(
+			clamp_size([800, 600], Some([1000, 700]), Some([900, 500])),
+			[1000, 700]
+		);
+	}
+}
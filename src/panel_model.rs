@@ -0,0 +1,137 @@
+/// Where the face actually is on the unit panel model, as a center and size in the XY plane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceRect {
+	pub center: [f32; 2],
+	pub size: [f32; 2],
+}
+impl Default for FaceRect {
+	fn default() -> Self {
+		FaceRect {
+			center: [0.0; 2],
+			size: [1.0; 2],
+		}
+	}
+}
+
+/// What Orbit needs to know about a panel model before the server loads it, read straight from the glTF.
+pub struct PanelModel {
+	/// Names of every node, which the server turns into model parts
+	pub parts: Vec<String>,
+	/// The face as modeled, the whole unit square when it couldn't be measured
+	pub face: FaceRect,
+}
+impl PanelModel {
+	/// `None` when `glb` isn't a binary glTF with nodes in it.
+	pub fn parse(glb: &[u8]) -> Option<Self> {
+		let json = glb_json(glb)?;
+		Some(PanelModel {
+			parts: node_names(&json)?,
+			face: node_rect(&json, "Face").unwrap_or_default(),
+		})
+	}
+}
+
+/// JSON chunk of a binary glTF.
+fn glb_json(glb: &[u8]) -> Option<serde_json::Value> {
+	let length = u32::from_le_bytes(glb.get(12..16)?.try_into().ok()?) as usize;
+	if glb.get(16..20)? != b"JSON" {
+		return None;
+	}
+	serde_json::from_slice(glb.get(20..20 + length)?).ok()
+}
+fn node_names(json: &serde_json::Value) -> Option<Vec<String>> {
+	Some(
+		json.get("nodes")?
+			.as_array()?
+			.iter()
+			.filter_map(|node| Some(node.get("name")?.as_str()?.to_string()))
+			.collect(),
+	)
+}
+/// Bounds of the named node's mesh from its position accessor, which glTF requires to have them.
+/// Only translation and scale of the node are applied, the face isn't expected to be rotated.
+fn node_rect(json: &serde_json::Value, name: &str) -> Option<FaceRect> {
+	let node = json
+		.get("nodes")?
+		.as_array()?
+		.iter()
+		.find(|node| node.get("name").and_then(|n| n.as_str()) == Some(name))?;
+	let mesh = &json.get("meshes")?[node.get("mesh")?.as_u64()? as usize];
+	let accessor = mesh.get("primitives")?[0]
+		.get("attributes")?
+		.get("POSITION")?
+		.as_u64()?;
+	let accessor = &json.get("accessors")?[accessor as usize];
+	let bound = |key: &str| -> Option<[f32; 2]> {
+		let bound = accessor.get(key)?.as_array()?;
+		Some([
+			bound.first()?.as_f64()? as f32,
+			bound.get(1)?.as_f64()? as f32,
+		])
+	};
+	let vector = |key: &str, default: f32| -> [f32; 2] {
+		let value = node.get(key).and_then(|v| v.as_array());
+		[0, 1].map(|i| {
+			value
+				.and_then(|v| v.get(i)?.as_f64())
+				.map_or(default, |v| v as f32)
+		})
+	};
+	let [min, max] = [bound("min")?, bound("max")?];
+	let [translation, scale] = [vector("translation", 0.0), vector("scale", 1.0)];
+	// the model is scaled to the panel's size, so anything degenerate would only break input
+	let size = [0, 1].map(|i| (max[i] - min[i]) * scale[i]);
+	(size[0] > 0.0 && size[1] > 0.0).then(|| FaceRect {
+		center: [0, 1].map(|i| (min[i] + max[i]) / 2.0 * scale[i] + translation[i]),
+		size,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn glb(json: &serde_json::Value) -> Vec<u8> {
+		let json = serde_json::to_vec(json).unwrap();
+		let mut glb = b"glTF".to_vec();
+		glb.extend(2u32.to_le_bytes());
+		glb.extend(((20 + json.len()) as u32).to_le_bytes());
+		glb.extend((json.len() as u32).to_le_bytes());
+		glb.extend(b"JSON");
+		glb.extend(json);
+		glb
+	}
+
+	#[test]
+	fn face_comes_from_its_bounds_and_node_transform() {
+		let model = PanelModel::parse(&glb(&serde_json::json!({
+			"nodes": [
+				{"name": "Edge"},
+				{"name": "Face", "mesh": 0, "translation": [0.0, 0.1, 0.0], "scale": [0.5, 1.0, 1.0]},
+			],
+			"meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+			"accessors": [{"min": [-0.8, -0.4, 0.0], "max": [0.8, 0.2, 0.0]}],
+		})))
+		.unwrap();
+		assert_eq!(model.parts, ["Edge", "Face"]);
+		let [x, y] = model.face.center;
+		let [width, height] = model.face.size;
+		assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+		assert!((width - 0.8).abs() < 1e-6 && (height - 0.6).abs() < 1e-6);
+	}
+
+	#[test]
+	fn unmeasurable_faces_cover_the_whole_panel() {
+		let model = PanelModel::parse(&glb(&serde_json::json!({
+			"nodes": [{"name": "Face"}],
+		})))
+		.unwrap();
+		assert_eq!(model.face, FaceRect::default());
+	}
+
+	#[test]
+	fn other_files_are_rejected() {
+		assert!(PanelModel::parse(b"not a model").is_none());
+		assert!(PanelModel::parse(&glb(&serde_json::json!({}))).is_none());
+	}
+}
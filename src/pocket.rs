@@ -0,0 +1,205 @@
+use std::{
+	f32::consts::TAU,
+	future::Future,
+	sync::{Arc, Mutex},
+};
+
+use glam::{EulerRot, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, SpatialRef, Transform},
+};
+
+use crate::hand::Hand;
+
+/// How far out to the dominant side the pocket sits from the middle of the belt, in meters
+const HIP_SIDE: f32 = 0.2;
+/// How far forward of the body the belt runs, in meters
+const BELT_FORWARD: f32 = 0.15;
+/// Size of stashed panels compared to their normal size
+const STASH_SCALE: f32 = 0.2;
+/// Space between stashed panels along the belt, in meters
+const STASH_SPACING: f32 = 0.035;
+const SEGMENTS: usize = 24;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PocketConfig {
+	/// How far below the eyes the belt hangs, in meters
+	pub drop: f32,
+	/// How close to the pocket a let go panel has to be to go into it, in meters
+	pub reach: f32,
+}
+impl Default for PocketConfig {
+	fn default() -> Self {
+		PocketConfig {
+			drop: 0.6,
+			reach: 0.1,
+		}
+	}
+}
+
+/// A pocket on the dominant hip that shrinks panels dropped into it onto a belt following the user around.
+pub struct Pocket {
+	reference: Spatial,
+	hmd: SpatialRef,
+	config: PocketConfig,
+	hand: Hand,
+	ring: Lines,
+	/// Where the head was last measured, relative to the reference
+	head: Arc<Mutex<Option<(Vec3, Quat)>>>,
+	/// The belt's place as of the last time stashed panels were moved
+	belt: Option<(Vec3, Quat)>,
+	/// Panel uids in the order they were stashed
+	pub panels: Vec<String>,
+	dirty: bool,
+}
+impl Pocket {
+	pub fn create(
+		reference: &Spatial,
+		hmd: &SpatialRef,
+		config: PocketConfig,
+		hand: Hand,
+	) -> Result<Self, NodeError> {
+		let ring = Line {
+			points: (0..SEGMENTS)
+				.map(|i| {
+					let angle = i as f32 / SEGMENTS as f32 * TAU;
+					LinePoint {
+						point: [angle.cos() * config.reach, 0.0, angle.sin() * config.reach].into(),
+						thickness: 0.003,
+						color: rgba_linear!(1.0, 1.0, 1.0, 0.25),
+					}
+				})
+				.collect(),
+			cyclic: true,
+		};
+		let ring = Lines::create(reference, Transform::identity(), &[ring])?;
+		ring.set_enabled(false)?;
+		Ok(Pocket {
+			reference: reference.alias(),
+			hmd: hmd.alias(),
+			config,
+			hand,
+			ring,
+			head: Arc::default(),
+			belt: None,
+			panels: Vec::new(),
+			dirty: false,
+		})
+	}
+
+	pub fn stash(&mut self, uid: String) {
+		if !self.panels.contains(&uid) {
+			self.panels.push(uid);
+			self.dirty = true;
+		}
+	}
+	/// Takes a panel out of the pocket at its normal size again.
+	pub fn take(&mut self, uid: &str, content_parent: &Spatial) {
+		if self.panels.iter().any(|p| p == uid) {
+			self.forget(uid);
+			let _ = content_parent.set_local_transform(Transform::from_scale([1.0; 3]));
+		}
+	}
+	/// For closed panels, which have nothing left to resize.
+	pub fn forget(&mut self, uid: &str) {
+		let len = self.panels.len();
+		self.panels.retain(|p| p != uid);
+		self.dirty |= self.panels.len() != len;
+	}
+
+	/// Keeps yaw only, so the belt stays level whichever way the head tilts.
+	fn belt((head, rotation): (Vec3, Quat), drop: f32) -> (Vec3, Quat) {
+		let (yaw, _, _) = rotation.to_euler(EulerRot::YXZ);
+		(head - Vec3::Y * drop, Quat::from_rotation_y(yaw))
+	}
+	fn slot(&self, index: usize) -> Vec3 {
+		Vec3::new(
+			self.hand
+				.mirror(HIP_SIDE - STASH_SPACING * (index + 1) as f32),
+			0.0,
+			-BELT_FORWARD,
+		)
+	}
+	fn pocket(&self) -> Vec3 {
+		Vec3::new(self.hand.mirror(HIP_SIDE), 0.0, -BELT_FORWARD)
+	}
+
+	/// Whether a panel at this spot drops into the pocket, going by where the belt was last frame.
+	pub fn near_query(&self, panel: &Spatial) -> impl Future<Output = bool> {
+		let pocket = self
+			.belt
+			.map(|(position, rotation)| position + rotation * self.pocket());
+		let reach = self.config.reach;
+		let panel = panel.alias();
+		let reference = self.reference.alias();
+		async move {
+			let Some(pocket) = pocket else {
+				return false;
+			};
+			let Some(position) = panel
+				.get_transform(&reference)
+				.await
+				.ok()
+				.and_then(|t| t.translation)
+			else {
+				return false;
+			};
+			Vec3::from(position).distance(pocket) < reach
+		}
+	}
+
+	/// Where every stashed panel goes this frame relative to the reference, none while the belt stays put.
+	pub fn update(&mut self) -> Vec<(String, Transform)> {
+		{
+			let head = self.head.clone();
+			let hmd = self.hmd.alias();
+			let reference = self.reference.alias();
+			tokio::spawn(async move {
+				let Ok(transform) = hmd.get_transform(&reference).await else {
+					return;
+				};
+				if let (Some(translation), Some(rotation)) =
+					(transform.translation, transform.rotation)
+				{
+					*head.lock().unwrap() = Some((translation.into(), rotation.into()));
+				}
+			});
+		}
+		let Some(head) = *self.head.lock().unwrap() else {
+			return Vec::new();
+		};
+		let belt = Self::belt(head, self.config.drop);
+		if self.belt == Some(belt) && !self.dirty {
+			return Vec::new();
+		}
+		if self.belt.is_none() {
+			let _ = self.ring.set_enabled(true);
+		}
+		self.belt = Some(belt);
+		self.dirty = false;
+		let (position, rotation) = belt;
+		let _ = self.ring.set_relative_transform(
+			&self.reference,
+			Transform::from_translation_rotation(position + rotation * self.pocket(), rotation),
+		);
+		self.panels
+			.iter()
+			.enumerate()
+			.map(|(index, uid)| {
+				(
+					uid.clone(),
+					Transform::from_translation_rotation_scale(
+						position + rotation * self.slot(index),
+						rotation,
+						[STASH_SCALE; 3],
+					),
+				)
+			})
+			.collect()
+	}
+}
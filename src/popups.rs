@@ -0,0 +1,69 @@
+use mint::Vector2;
+use stardust_xr_fusion::items::panel::{ChildInfo, Geometry, SurfaceID};
+
+/// How deep popups may nest in each other, so a parent loop can't hang the lookup
+const MAX_DEPTH: usize = 16;
+
+/// Child surfaces of a toplevel, in the order they opened, to send input to whichever one is on top.
+#[derive(Default)]
+pub struct Popups {
+	children: Vec<(String, ChildInfo)>,
+}
+impl Popups {
+	pub fn add(&mut self, uid: &str, info: ChildInfo) {
+		self.children.retain(|(child, _)| child != uid);
+		self.children.push((uid.to_string(), info));
+	}
+	pub fn reposition(&mut self, uid: &str, geometry: Geometry) {
+		if let Some((_, info)) = self.children.iter_mut().find(|(child, _)| child == uid) {
+			info.geometry = geometry;
+		}
+	}
+	pub fn remove(&mut self, uid: &str) {
+		self.children.retain(|(child, _)| child != uid);
+	}
+	/// Whether a popup is open that should close when something else is clicked.
+	pub fn any_open(&self) -> bool {
+		self.children.iter().any(|(_, info)| info.receives_input)
+	}
+
+	/// Where a child's top left corner is on the toplevel, through all its parents.
+	fn origin(&self, uid: &str) -> Option<Vector2<f32>> {
+		let mut origin = Vector2::from([0.0; 2]);
+		let mut surface = SurfaceID::Child(uid.to_string());
+		for _ in 0..MAX_DEPTH {
+			let SurfaceID::Child(uid) = surface else {
+				return Some(origin);
+			};
+			let (_, info) = self.children.iter().find(|(child, _)| *child == uid)?;
+			origin.x += info.geometry.origin.x as f32;
+			origin.y += info.geometry.origin.y as f32;
+			surface = info.parent.clone();
+		}
+		None
+	}
+
+	/// The topmost surface under a toplevel position and the position on it, the toplevel itself when no popup is there.
+	pub fn surface_at(&self, position: Vector2<f32>) -> (SurfaceID, Vector2<f32>) {
+		let mut children = self
+			.children
+			.iter()
+			.enumerate()
+			.filter(|(_, (_, info))| info.receives_input)
+			.collect::<Vec<_>>();
+		// Later ones opened on top of earlier ones with the same z order
+		children.sort_by_key(|(index, (_, info))| std::cmp::Reverse((info.z_order, *index)));
+		for (_, (uid, info)) in children {
+			let Some(origin) = self.origin(uid) else {
+				continue;
+			};
+			let local = Vector2::from([position.x - origin.x, position.y - origin.y]);
+			if (0.0..info.geometry.size.x as f32).contains(&local.x)
+				&& (0.0..info.geometry.size.y as f32).contains(&local.y)
+			{
+				return (SurfaceID::Child(uid.clone()), local);
+			}
+		}
+		(SurfaceID::Toplevel, position)
+	}
+}
@@ -0,0 +1,34 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+use color_eyre::eyre::{eyre, Result};
+
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Select the profile for this process. Without one, the default directories are used.
+pub fn init(profile: Option<String>) -> Result<()> {
+	if let Some(name) = &profile {
+		let valid = !name.is_empty()
+			&& name
+				.chars()
+				.all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+		if !valid {
+			return Err(eyre!(
+				"Profile names may only contain letters, digits, '-' and '_'"
+			));
+		}
+	}
+	let _ = PROFILE.set(profile);
+	Ok(())
+}
+pub fn name() -> Option<&'static str> {
+	PROFILE.get().and_then(Option::as_deref)
+}
+
+/// `base/orbit`, or `base/orbit/profiles/<name>` for a named profile.
+pub fn dir(base: PathBuf) -> PathBuf {
+	let dir = base.join("orbit");
+	match name() {
+		Some(name) => dir.join("profiles").join(name),
+		None => dir,
+	}
+}
@@ -0,0 +1,107 @@
+use std::path::PathBuf;
+
+use glam::Vec3;
+use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::ResourceID,
+	drawable::Sound,
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, SpatialRef, Transform},
+};
+use tokio::sync::mpsc;
+
+/// Seconds between checks of which panels are within reach
+const CHECK_INTERVAL: f32 = 0.5;
+/// Panels further than this from straight ahead count as out of view, in radians
+const VIEW_HALF_ANGLE: f32 = 1.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProximityCueConfig {
+	/// Played from a panel out of view when walking up to it, off when unset
+	pub sound: Option<PathBuf>,
+	/// How close counts as within reach, in meters
+	pub reach: f32,
+}
+impl Default for ProximityCueConfig {
+	fn default() -> Self {
+		ProximityCueConfig {
+			sound: None,
+			reach: 0.7,
+		}
+	}
+}
+impl ProximityCueConfig {
+	pub fn create_sound(&self, panel: &impl SpatialAspect) -> Result<Option<Sound>, NodeError> {
+		self.sound
+			.as_ref()
+			.and_then(|path| ResourceID::new_direct(path).ok())
+			.map(|resource| Sound::create(panel, Transform::identity(), &resource))
+			.transpose()
+	}
+}
+
+/// Finds panels the user just walked up to without looking at, so forgotten ones get noticed.
+pub struct ProximityCue {
+	config: ProximityCueConfig,
+	hmd: SpatialRef,
+	last_check: f32,
+	near: FxHashSet<String>,
+	near_tx: mpsc::UnboundedSender<(String, bool)>,
+	near_rx: mpsc::UnboundedReceiver<(String, bool)>,
+}
+impl ProximityCue {
+	pub fn new(config: ProximityCueConfig, hmd: SpatialRef) -> Self {
+		let (near_tx, near_rx) = mpsc::unbounded_channel();
+		ProximityCue {
+			config,
+			hmd,
+			last_check: f32::NEG_INFINITY,
+			near: FxHashSet::default(),
+			near_tx,
+			near_rx,
+		}
+	}
+
+	/// Returns the panels that came within reach out of view since the last update, and
+	/// whether panels should be checked again this frame.
+	pub fn update(&mut self, now: f32) -> (Vec<String>, bool) {
+		let mut entered = Vec::new();
+		while let Ok((uid, near)) = self.near_rx.try_recv() {
+			if !near {
+				self.near.remove(&uid);
+			} else if self.near.insert(uid.clone()) {
+				entered.push(uid);
+			}
+		}
+		if self.config.sound.is_none() || now - self.last_check < CHECK_INTERVAL {
+			return (entered, false);
+		}
+		self.last_check = now;
+		(entered, true)
+	}
+	pub fn query(&self, uid: &str, panel: &Spatial) {
+		let panel = panel.alias();
+		let hmd = self.hmd.alias();
+		let near_tx = self.near_tx.clone();
+		let uid = uid.to_string();
+		let reach = self.config.reach;
+		tokio::spawn(async move {
+			let Some(translation) = panel
+				.get_transform(&hmd)
+				.await
+				.ok()
+				.and_then(|t| t.translation)
+			else {
+				return;
+			};
+			let offset = Vec3::from(translation);
+			let out_of_view = offset.angle_between(Vec3::NEG_Z) > VIEW_HALF_ANGLE;
+			let _ = near_tx.send((uid, offset.length() < reach && out_of_view));
+		});
+	}
+	pub fn forget(&mut self, uid: &str) {
+		self.near.remove(uid);
+	}
+}
@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+
+use glam::{Quat, Vec3};
+use stardust_xr_fusion::{
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::Grabbable;
+
+/// Seconds the panel has to be held still after grabbing to arm the rails
+const ARM_DELAY: f32 = 0.5;
+/// Meters the panel may drift while arming
+const DWELL_TOLERANCE: f32 = 0.01;
+/// Meters of movement after arming that decide which rail to use
+const PICK_DISTANCE: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rail {
+	/// Straight towards or away from the viewer along the panel's normal
+	Depth,
+	/// Anywhere in the panel's own plane
+	Plane,
+}
+
+/// Constrains a grab to one axis or plane: hold the panel still briefly after grabbing,
+/// then the first movement picks between sliding along its normal and sliding in its plane.
+#[derive(Default)]
+pub struct GrabRails {
+	started_at: f32,
+	start_pose: Arc<Mutex<Option<(Vec3, Quat)>>>,
+	current: Arc<Mutex<Option<Vec3>>>,
+	/// Moved too early, so this grab stays free
+	disqualified: bool,
+	rail: Option<Rail>,
+}
+impl GrabRails {
+	pub fn update(&mut self, grabbable: &Grabbable, reference: &Spatial, elapsed: f32) {
+		let grab_action = grabbable.grab_action();
+		let content_parent = grabbable.content_parent();
+		if grab_action.actor_started() {
+			self.started_at = elapsed;
+			self.disqualified = false;
+			self.rail = None;
+			*self.current.lock().unwrap() = None;
+			let start_pose = self.start_pose.clone();
+			*start_pose.lock().unwrap() = None;
+			let content_parent = content_parent.alias();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				let Ok(pose) = content_parent.get_transform(&reference).await else {
+					return;
+				};
+				*start_pose.lock().unwrap() = Some((
+					pose.translation.map(Vec3::from).unwrap_or_default(),
+					pose.rotation.map(Quat::from).unwrap_or_default(),
+				));
+			});
+			return;
+		}
+		if !grab_action.actor_acting() || self.disqualified {
+			return;
+		}
+		let Some((start_position, start_rotation)) = *self.start_pose.lock().unwrap() else {
+			return;
+		};
+		{
+			let current = self.current.clone();
+			let content_parent = content_parent.alias();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				if let Some(translation) = content_parent
+					.get_transform(&reference)
+					.await
+					.ok()
+					.and_then(|t| t.translation)
+				{
+					*current.lock().unwrap() = Some(translation.into());
+				}
+			});
+		}
+		let Some(position) = *self.current.lock().unwrap() else {
+			return;
+		};
+		let local = start_rotation.inverse() * (position - start_position);
+		let armed = elapsed - self.started_at >= ARM_DELAY;
+		if !armed {
+			self.disqualified = local.length() > DWELL_TOLERANCE;
+			return;
+		}
+		if self.rail.is_none() && local.length() > PICK_DISTANCE {
+			self.rail = Some(if local.z.abs() > local.truncate().length() {
+				Rail::Depth
+			} else {
+				Rail::Plane
+			});
+		}
+		let constrained = match self.rail {
+			Some(Rail::Depth) => Vec3::new(0.0, 0.0, local.z),
+			Some(Rail::Plane) => local.truncate().extend(0.0),
+			None => Vec3::ZERO,
+		};
+		let _ = content_parent.set_relative_transform(
+			reference,
+			Transform::from_translation_rotation(
+				start_position + start_rotation * constrained,
+				start_rotation,
+			),
+		);
+	}
+}
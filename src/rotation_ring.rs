@@ -0,0 +1,143 @@
+use std::{
+	f32::consts::TAU,
+	sync::{Arc, Mutex},
+};
+
+use glam::{EulerRot, Quat};
+use mint::Vector2;
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	fields::TorusField,
+	items::panel::PanelItem,
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::{Grabbable, GrabbableSettings};
+
+/// Turns snap to multiples of this, in radians
+const SNAP_ANGLE: f32 = TAU / 24.0;
+/// How far the ring sits outside the panel's edge
+const RING_CLEARANCE: f32 = 0.02;
+const RING_THICKNESS: f32 = 0.004;
+const RING_SEGMENTS: usize = 48;
+
+fn yaw(transform: &Transform) -> f32 {
+	let rotation = transform.rotation.map(Quat::from).unwrap_or_default();
+	rotation.to_euler(EulerRot::YXZ).0
+}
+
+/// A horizontal ring around the panel that turns it about its vertical axis in snapped steps.
+pub struct RotationRing {
+	field: TorusField,
+	lines: Lines,
+	grabbable: Grabbable,
+	/// Panel and ring yaw relative to the reference when the grab started
+	start: Arc<Mutex<Option<(f32, f32)>>>,
+	/// Latest ring yaw relative to the reference
+	current: Arc<Mutex<Option<f32>>>,
+	applied: Option<f32>,
+}
+impl RotationRing {
+	pub fn create(panel_item: &PanelItem) -> Result<Self, NodeError> {
+		let field = TorusField::create(panel_item, Transform::identity(), 1.0, RING_THICKNESS)?;
+		let lines = Lines::create(panel_item, Transform::identity(), &[Self::circle(1.0)])?;
+		let grabbable = Grabbable::create(
+			panel_item,
+			Transform::identity(),
+			&field,
+			GrabbableSettings::default(),
+		)?;
+		Ok(RotationRing {
+			field,
+			lines,
+			grabbable,
+			start: Default::default(),
+			current: Default::default(),
+			applied: None,
+		})
+	}
+	fn circle(radius: f32) -> Line {
+		let points = (0..RING_SEGMENTS)
+			.map(|i| {
+				let angle = i as f32 / RING_SEGMENTS as f32 * TAU;
+				LinePoint {
+					point: [angle.cos() * radius, 0.0, angle.sin() * radius].into(),
+					thickness: RING_THICKNESS,
+					color: rgba_linear!(1.0, 1.0, 1.0, 0.6),
+				}
+			})
+			.collect();
+		Line {
+			points,
+			cyclic: true,
+		}
+	}
+
+	pub fn resize(&self, panel_size: Vector2<f32>) {
+		let radius = panel_size.x.max(panel_size.y) / 2.0 + RING_CLEARANCE;
+		let _ = self.field.set_size(radius, RING_THICKNESS);
+		let _ = self.lines.set_lines(&[Self::circle(radius)]);
+	}
+	pub fn set_enabled(&self, enabled: bool) {
+		let _ = self.lines.set_enabled(enabled);
+		let _ = self.grabbable.set_enabled(enabled);
+	}
+
+	pub fn update(&mut self, info: &FrameInfo, panel: &Spatial, reference: &Spatial) {
+		let _ = self.grabbable.update(info);
+		let grab_action = self.grabbable.grab_action();
+		let ring = self.grabbable.content_parent().alias();
+		if grab_action.actor_started() {
+			*self.start.lock().unwrap() = None;
+			self.applied = None;
+			let start = self.start.clone();
+			let panel = panel.alias();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				let (Ok(panel), Ok(ring)) = (
+					panel.get_transform(&reference).await,
+					ring.get_transform(&reference).await,
+				) else {
+					return;
+				};
+				*start.lock().unwrap() = Some((yaw(&panel), yaw(&ring)));
+			});
+		} else if grab_action.actor_acting() {
+			let current = self.current.clone();
+			let reference = reference.alias();
+			tokio::spawn(async move {
+				if let Ok(ring) = ring.get_transform(&reference).await {
+					*current.lock().unwrap() = Some(yaw(&ring));
+				}
+			});
+		}
+		if grab_action.actor_stopped() {
+			// keep the ring itself fixed around the panel
+			self.grabbable.cancel_linear_velocity();
+			self.grabbable.cancel_angular_velocity();
+			let _ = self
+				.grabbable
+				.content_parent()
+				.set_local_transform(Transform::identity());
+			return;
+		}
+
+		let Some((panel_yaw, ring_yaw)) = *self.start.lock().unwrap() else {
+			return;
+		};
+		let Some(current) = self.current.lock().unwrap().take() else {
+			return;
+		};
+		let target = panel_yaw + ((current - ring_yaw) / SNAP_ANGLE).round() * SNAP_ANGLE;
+		if self.applied == Some(target) {
+			return;
+		}
+		self.applied = Some(target);
+		let _ = panel.set_relative_transform(
+			reference,
+			Transform::from_rotation(Quat::from_rotation_y(target)),
+		);
+	}
+}
@@ -0,0 +1,55 @@
+use chrono::{Local, NaiveTime};
+use color_eyre::eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::ipc::{IpcMessage, Request, Response};
+
+/// An IPC request sent to Orbit by itself every day at a set time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledRequest {
+	/// Local time of day as `HH:MM`
+	pub at: String,
+	pub request: Request,
+}
+impl ScheduledRequest {
+	pub fn time(&self) -> Result<NaiveTime> {
+		NaiveTime::parse_from_str(&self.at, "%H:%M")
+			.wrap_err_with(|| format!("Scheduled time {:?} isn't HH:MM", self.at))
+	}
+}
+
+/// Check every entry's time up front so typos show up at startup, then run them all.
+pub fn spawn(
+	schedule: Vec<ScheduledRequest>,
+	requests: mpsc::UnboundedSender<IpcMessage>,
+) -> Result<()> {
+	let entries = schedule
+		.into_iter()
+		.map(|entry| Ok((entry.time()?, entry.request)))
+		.collect::<Result<Vec<_>>>()?;
+	for (time, request) in entries {
+		tokio::spawn(run(time, request, requests.clone()));
+	}
+	Ok(())
+}
+
+async fn run(time: NaiveTime, request: Request, requests: mpsc::UnboundedSender<IpcMessage>) {
+	loop {
+		let now = Local::now().naive_local();
+		let mut next = now.date().and_time(time);
+		if next <= now {
+			next += chrono::Duration::days(1);
+		}
+		let wait = (next - now).to_std().unwrap_or_default();
+		tokio::time::sleep(wait).await;
+
+		let (tx, rx) = oneshot::channel();
+		if requests.send((request.clone(), tx)).is_err() {
+			return;
+		}
+		if let Ok(Response::Error { message }) = rx.await {
+			eprintln!("Scheduled request at {time} failed: {message}");
+		}
+	}
+}
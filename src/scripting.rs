@@ -0,0 +1,239 @@
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::{
+	path::{Path, PathBuf},
+	time::SystemTime,
+};
+
+/// Scripts run on the frame thread, so one stuck in a loop or recursing forever is cut off as an error
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_FUNCTION_EXPR_DEPTH: usize = 32;
+
+fn engine() -> Engine {
+	let mut engine = Engine::new();
+	engine
+		.set_max_operations(MAX_OPERATIONS)
+		.set_max_call_levels(MAX_CALL_LEVELS)
+		.set_max_expr_depths(MAX_EXPR_DEPTH, MAX_FUNCTION_EXPR_DEPTH);
+	engine
+}
+
+/// What a placement script asked for, relative to the client root.
+#[derive(Debug, Clone, Default)]
+pub struct Placement {
+	pub position: Option<[f32; 3]>,
+	/// Degrees around the vertical axis
+	pub yaw: Option<f32>,
+	pub size: Option<[u32; 2]>,
+}
+
+pub struct AppInfo<'a> {
+	pub app_id: Option<&'a str>,
+	pub title: Option<&'a str>,
+	pub size: [u32; 2],
+	/// Limits the toplevel asked for, in pixels
+	pub min_size: Option<[u32; 2]>,
+	pub max_size: Option<[u32; 2]>,
+}
+impl AppInfo<'_> {
+	fn to_map(&self) -> Map {
+		let mut map = Map::new();
+		map.insert(
+			"app_id".into(),
+			self.app_id
+				.map(|s| Dynamic::from(s.to_string()))
+				.unwrap_or(Dynamic::UNIT),
+		);
+		map.insert(
+			"title".into(),
+			self.title
+				.map(|s| Dynamic::from(s.to_string()))
+				.unwrap_or(Dynamic::UNIT),
+		);
+		map.insert("width".into(), Dynamic::from(self.size[0] as i64));
+		map.insert("height".into(), Dynamic::from(self.size[1] as i64));
+		for (prefix, size) in [("min", self.min_size), ("max", self.max_size)] {
+			let [width, height] = size.map_or([Dynamic::UNIT; 2], |size| {
+				size.map(|side| Dynamic::from(side as i64))
+			});
+			map.insert(format!("{prefix}_width").into(), width);
+			map.insert(format!("{prefix}_height").into(), height);
+		}
+		map
+	}
+}
+
+/// A user script defining `fn place(app, panels)`, recompiled whenever the file changes on disk.
+pub struct PlacementScript {
+	path: PathBuf,
+	engine: Engine,
+	ast: Option<AST>,
+	modified: Option<SystemTime>,
+}
+impl PlacementScript {
+	pub fn new(path: PathBuf) -> Self {
+		let mut script = PlacementScript {
+			path,
+			engine: engine(),
+			ast: None,
+			modified: None,
+		};
+		script.reload_if_changed();
+		script
+	}
+
+	fn reload_if_changed(&mut self) {
+		let modified = std::fs::metadata(&self.path)
+			.and_then(|m| m.modified())
+			.ok();
+		if modified == self.modified {
+			return;
+		}
+		self.modified = modified;
+		if modified.is_none() {
+			self.ast = None;
+			return;
+		}
+		match self.engine.compile_file(self.path.clone()) {
+			Ok(ast) => {
+				println!("Loaded placement script {}", self.path.display());
+				self.ast = Some(ast);
+			}
+			Err(e) => {
+				eprintln!("Placement script {}: {e}", self.path.display());
+				self.ast = None;
+			}
+		}
+	}
+
+	/// Compile the script once, for checking it without a running instance.
+	pub fn check(path: &Path) -> Result<(), String> {
+		let ast = engine()
+			.compile_file(path.to_path_buf())
+			.map_err(|e| e.to_string())?;
+		if !ast.iter_functions().any(|f| f.name == "place") {
+			return Err("No place(app, panels) function".to_string());
+		}
+		Ok(())
+	}
+
+	pub fn place(&mut self, app: AppInfo, panels: &[AppInfo]) -> Option<Placement> {
+		self.reload_if_changed();
+		let ast = self.ast.as_ref()?;
+		let panels = panels
+			.iter()
+			.map(|p| Dynamic::from_map(p.to_map()))
+			.collect::<Array>();
+		let result =
+			self.engine
+				.call_fn::<Dynamic>(&mut Scope::new(), ast, "place", (app.to_map(), panels));
+		let result = match result {
+			Ok(result) => result,
+			Err(e) => {
+				eprintln!("Placement script {}: {e}", self.path.display());
+				return None;
+			}
+		};
+		let result = result.try_cast::<Map>()?;
+
+		let floats = |key: &str| -> Option<Vec<f32>> {
+			result
+				.get(key)?
+				.clone()
+				.try_cast::<Array>()?
+				.into_iter()
+				.map(number)
+				.collect()
+		};
+		let path = self.path.display();
+		let position = floats("position").and_then(|p| <[f32; 3]>::try_from(p).ok());
+		let position = match position {
+			Some(position) if position.iter().all(|c| c.is_finite()) => Some(position),
+			Some(position) => {
+				eprintln!("Placement script {path}: position {position:?} isn't finite");
+				None
+			}
+			None => None,
+		};
+		let yaw = match result.get("yaw").cloned().and_then(number) {
+			Some(yaw) if yaw.is_finite() => Some(yaw),
+			Some(yaw) => {
+				eprintln!("Placement script {path}: yaw {yaw} isn't finite");
+				None
+			}
+			None => None,
+		};
+		let size = match floats("size").and_then(|s| <[f32; 2]>::try_from(s).ok()) {
+			Some(size) if size.iter().all(|side| side.is_finite() && *side >= 1.0) => {
+				Some(size.map(|side| side as u32))
+			}
+			Some(size) => {
+				eprintln!("Placement script {path}: size {size:?} isn't at least 1 by 1 pixels");
+				None
+			}
+			None => None,
+		};
+		Some(Placement {
+			position,
+			yaw,
+			size,
+		})
+	}
+}
+
+fn number(value: Dynamic) -> Option<f32> {
+	value
+		.as_float()
+		.map(|f| f as f32)
+		.or_else(|_| value.as_int().map(|i| i as f32))
+		.ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn place(name: &str, source: &str) -> Option<Placement> {
+		let path = std::env::temp_dir().join(format!("orbit-{}-{name}.rhai", std::process::id()));
+		std::fs::write(&path, source).unwrap();
+		let app = AppInfo {
+			app_id: Some("editor"),
+			title: None,
+			size: [800, 600],
+			min_size: None,
+			max_size: None,
+		};
+		let placement = PlacementScript::new(path.clone()).place(app, &[]);
+		let _ = std::fs::remove_file(path);
+		placement
+	}
+
+	#[test]
+	fn endless_scripts_are_cut_off() {
+		assert!(place("loop", "fn place(app, panels) { loop {} }").is_none());
+		assert!(place("recursion", "fn place(app, panels) { place(app, panels) }").is_none());
+	}
+
+	#[test]
+	fn unusable_numbers_are_dropped() {
+		let placement = place(
+			"numbers",
+			"fn place(app, panels) { #{ position: [0.0, parse_float(\"NaN\"), 0.0], yaw: 90, size: [-20, 0.5] } }",
+		)
+		.unwrap();
+		assert_eq!(placement.position, None);
+		assert_eq!(placement.yaw, Some(90.0));
+		assert_eq!(placement.size, None);
+	}
+
+	#[test]
+	fn sizes_are_taken_in_pixels() {
+		let placement = place(
+			"size",
+			"fn place(app, panels) { #{ size: [app.width / 2, 300.7] } }",
+		)
+		.unwrap();
+		assert_eq!(placement.size, Some([400, 300]));
+	}
+}
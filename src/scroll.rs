@@ -0,0 +1,90 @@
+use mint::Vector2;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::items::panel::{PanelItem, SurfaceID};
+
+use crate::dev_overlay::InputLog;
+
+/// Pixels one wheel step scrolls
+pub const WHEEL_STEP: f32 = 15.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WheelMode {
+	/// Pixel deltas as they come, like a touchpad
+	#[default]
+	Smooth,
+	/// Whole wheel steps, for apps that only scroll by line
+	Discrete,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrollConfig {
+	/// Content follows the finger instead of moving against it
+	pub natural: bool,
+	/// Multiplies the distance of every scroll
+	pub speed: f32,
+	pub wheel: WheelMode,
+}
+impl Default for ScrollConfig {
+	fn default() -> Self {
+		ScrollConfig {
+			natural: false,
+			speed: 1.0,
+			wheel: WheelMode::Smooth,
+		}
+	}
+}
+
+/// Turns scroll distances into what the toplevel gets, keeping partial wheel steps for the next frame.
+pub struct WheelEmulation {
+	config: ScrollConfig,
+	remainder: Vector2<f32>,
+}
+impl WheelEmulation {
+	pub fn new(config: ScrollConfig) -> Self {
+		WheelEmulation {
+			config,
+			remainder: [0.0; 2].into(),
+		}
+	}
+
+	/// `delta` is in surface pixels.
+	pub fn scroll(&mut self, panel_item: &PanelItem, log: &mut InputLog, delta: Vector2<f32>) {
+		let sign = if self.config.natural { -1.0 } else { 1.0 };
+		let delta = Vector2::from([
+			delta.x * self.config.speed * sign,
+			delta.y * self.config.speed * sign,
+		]);
+		match self.config.wheel {
+			WheelMode::Smooth => {
+				log.push(|| format!("scroll {:.1}, {:.1}", delta.x, delta.y));
+				let _ = panel_item.pointer_scroll(&SurfaceID::Toplevel, delta, [0.0; 2].into());
+			}
+			WheelMode::Discrete => {
+				self.remainder.x += delta.x;
+				self.remainder.y += delta.y;
+				let steps = Vector2::from([
+					(self.remainder.x / WHEEL_STEP).trunc(),
+					(self.remainder.y / WHEEL_STEP).trunc(),
+				]);
+				if steps.x == 0.0 && steps.y == 0.0 {
+					return;
+				}
+				self.remainder.x -= steps.x * WHEEL_STEP;
+				self.remainder.y -= steps.y * WHEEL_STEP;
+				log.push(|| format!("scroll {} {} steps", steps.x, steps.y));
+				let _ = panel_item.pointer_scroll(
+					&SurfaceID::Toplevel,
+					[steps.x * WHEEL_STEP, steps.y * WHEEL_STEP].into(),
+					steps,
+				);
+			}
+		}
+	}
+	pub fn stop(&mut self, panel_item: &PanelItem, log: &mut InputLog) {
+		self.remainder = [0.0; 2].into();
+		log.push(|| "stop scroll".to_string());
+		let _ = panel_item.pointer_stop_scroll(&SurfaceID::Toplevel);
+	}
+}
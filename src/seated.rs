@@ -0,0 +1,64 @@
+use std::sync::{Arc, Mutex};
+
+use stardust_xr_fusion::{
+	node::NodeType,
+	spatial::{Spatial, SpatialAspect, SpatialRef, Transform},
+};
+
+/// Moves everything placed relative to the reference space up or down to match a seated user's eyes.
+///
+/// Layouts, placement scripts and the wall are written for a standing user, so rather than
+/// adjusting each one the whole reference space shifts by the difference in eye height.
+pub struct SeatedOffset {
+	standing_eye_height: f32,
+	eye_height: Option<f32>,
+	measured: Arc<Mutex<Option<f32>>>,
+}
+impl SeatedOffset {
+	pub fn new(standing_eye_height: f32, eye_height: Option<f32>) -> Self {
+		SeatedOffset {
+			standing_eye_height,
+			eye_height,
+			measured: Default::default(),
+		}
+	}
+	fn offset(&self) -> f32 {
+		self.eye_height
+			.map_or(0.0, |height| height - self.standing_eye_height)
+	}
+	pub fn apply(&self, reference: &Spatial) {
+		let _ =
+			reference.set_local_transform(Transform::from_translation([0.0, self.offset(), 0.0]));
+	}
+
+	/// Measure where the user's eyes are now and take that as their seated height.
+	pub fn calibrate(&self, reference: &Spatial, hmd: &SpatialRef) {
+		let current_offset = self.offset();
+		let reference = reference.alias();
+		let hmd = hmd.alias();
+		let measured = self.measured.clone();
+		tokio::spawn(async move {
+			let Some(translation) = hmd
+				.get_transform(&reference)
+				.await
+				.ok()
+				.and_then(|t| t.translation)
+			else {
+				return;
+			};
+			*measured.lock().unwrap() = Some(translation.y + current_offset);
+		});
+	}
+	pub fn clear(&mut self, reference: &Spatial) {
+		self.eye_height = None;
+		self.apply(reference);
+	}
+
+	/// Returns the new eye height once a calibration has come back.
+	pub fn update(&mut self, reference: &Spatial) -> Option<f32> {
+		let eye_height = self.measured.lock().unwrap().take()?;
+		self.eye_height = Some(eye_height);
+		self.apply(reference);
+		Some(eye_height)
+	}
+}
@@ -0,0 +1,163 @@
+use stardust_xr_fusion::{
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeError,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::button::{Button, ButtonSettings};
+
+use crate::{config::Config, handle_bar::GrabMode, i18n::tr_with, theme::Theme};
+
+/// Where the panel shows up relative to the reference space
+const SETTINGS_POSITION: [f32; 3] = [0.0, -0.1, -0.35];
+const ROW_HEIGHT: f32 = 0.03;
+const TEXT_HEIGHT: f32 = 0.008;
+const BUTTON_SIZE: f32 = 0.02;
+/// Step for length settings, in meters
+const LENGTH_STEP: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy)]
+enum Setting {
+	PanelWidth,
+	AcceptDistance,
+	GrabMode,
+	Theme,
+}
+impl Setting {
+	const ALL: [Setting; 4] = [
+		Setting::PanelWidth,
+		Setting::AcceptDistance,
+		Setting::GrabMode,
+		Setting::Theme,
+	];
+
+	fn label(self, config: &Config) -> String {
+		let cm = |meters: f32| format!("{:.0}", meters * 100.0);
+		match self {
+			Setting::PanelWidth => {
+				tr_with("settings-panel-width", &[("cm", &cm(config.panel_width))])
+			}
+			Setting::AcceptDistance => tr_with(
+				"settings-accept-distance",
+				&[("cm", &cm(config.accept_distance))],
+			),
+			Setting::GrabMode => tr_with(
+				"settings-grab-mode",
+				&[("mode", &format!("{:?}", config.grab_mode))],
+			),
+			Setting::Theme => tr_with(
+				"settings-theme",
+				&[("theme", &format!("{:?}", config.theme))],
+			),
+		}
+	}
+	fn adjust(self, config: &mut Config, step: i32) {
+		match self {
+			Setting::PanelWidth => {
+				config.panel_width =
+					(config.panel_width + step as f32 * LENGTH_STEP).max(LENGTH_STEP)
+			}
+			Setting::AcceptDistance => {
+				config.accept_distance =
+					(config.accept_distance + step as f32 * LENGTH_STEP).max(LENGTH_STEP)
+			}
+			Setting::GrabMode => {
+				const MODES: [GrabMode; 3] =
+					[GrabMode::Panel, GrabMode::HandleBar, GrabMode::Arbitrated];
+				let index = MODES
+					.iter()
+					.position(|m| *m == config.grab_mode)
+					.unwrap_or(0);
+				config.grab_mode = MODES[(index as i32 + step).rem_euclid(3) as usize];
+			}
+			Setting::Theme => {
+				let themes = Theme::ALL;
+				let index = themes.iter().position(|t| *t == config.theme).unwrap_or(0);
+				config.theme =
+					themes[(index as i32 + step).rem_euclid(themes.len() as i32) as usize];
+			}
+		}
+	}
+}
+
+struct Row {
+	setting: Setting,
+	label: Text,
+	decrease: Button,
+	increase: Button,
+	_signs: [Text; 2],
+}
+
+/// A few common options as rows of −/+ buttons, changed in place and saved back to the config file.
+pub struct SettingsPanel {
+	_root: Spatial,
+	rows: Vec<Row>,
+}
+impl SettingsPanel {
+	pub fn create(reference: &Spatial, config: &Config) -> Result<Self, NodeError> {
+		let root = Spatial::create(
+			reference,
+			Transform::from_translation(SETTINGS_POSITION),
+			false,
+		)?;
+		let style = || TextStyle {
+			character_height: TEXT_HEIGHT,
+			..Default::default()
+		};
+		let rows = Setting::ALL
+			.into_iter()
+			.enumerate()
+			.map(|(i, setting)| {
+				let y = -(i as f32) * ROW_HEIGHT;
+				let label = Text::create(
+					&root,
+					Transform::from_translation([-0.1, y, 0.0]),
+					&setting.label(config),
+					style(),
+				)?;
+				let button = |x: f32| {
+					Button::create(
+						&root,
+						Transform::from_translation([x, y, 0.0]),
+						[BUTTON_SIZE; 2],
+						ButtonSettings::default(),
+					)
+				};
+				let decrease = button(0.08)?;
+				let increase = button(0.08 + BUTTON_SIZE * 1.5)?;
+				let sign = |button: &Button, sign: &str| {
+					Text::create(
+						button.touch_plane().root(),
+						Transform::identity(),
+						sign,
+						style(),
+					)
+				};
+				Ok(Row {
+					setting,
+					_signs: [sign(&decrease, "−")?, sign(&increase, "+")?],
+					label,
+					decrease,
+					increase,
+				})
+			})
+			.collect::<Result<Vec<_>, NodeError>>()?;
+		Ok(SettingsPanel { _root: root, rows })
+	}
+
+	/// Returns whether anything in the config was changed.
+	pub fn update(&mut self, config: &mut Config) -> bool {
+		let mut changed = false;
+		for row in &mut self.rows {
+			row.decrease.update();
+			row.increase.update();
+			let step = row.increase.pressed() as i32 - row.decrease.pressed() as i32;
+			if step == 0 {
+				continue;
+			}
+			row.setting.adjust(config, step);
+			let _ = row.label.set_text(&row.setting.label(config));
+			changed = true;
+		}
+		changed
+	}
+}
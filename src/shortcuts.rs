@@ -0,0 +1,367 @@
+use std::{
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc,
+	},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::commands::WmCommand;
+
+/// A key with the modifiers that have to be held for it, written like `super+shift+tab`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chord {
+	pub super_: bool,
+	pub ctrl: bool,
+	pub alt: bool,
+	pub shift: bool,
+	/// Lowercase evdev key name without `KEY_`, like `tab` or `1`
+	pub key: String,
+}
+impl Chord {
+	pub fn parse(keys: &str) -> Result<Self, String> {
+		let mut chord = Chord::default();
+		let mut parts = keys
+			.split('+')
+			.map(|part| part.trim().to_lowercase())
+			.peekable();
+		while let Some(part) = parts.next() {
+			if parts.peek().is_none() {
+				chord.key = part;
+				break;
+			}
+			match part.as_str() {
+				"super" | "meta" | "logo" => chord.super_ = true,
+				"ctrl" | "control" => chord.ctrl = true,
+				"alt" => chord.alt = true,
+				"shift" => chord.shift = true,
+				_ => return Err(format!("Unknown modifier {part} in shortcut {keys}")),
+			}
+		}
+		if chord.key.is_empty() {
+			return Err(format!("Shortcut {keys} has no key"));
+		}
+		Ok(chord)
+	}
+}
+impl std::fmt::Display for Chord {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (held, name) in [
+			(self.super_, "super+"),
+			(self.ctrl, "ctrl+"),
+			(self.alt, "alt+"),
+			(self.shift, "shift+"),
+		] {
+			if held {
+				f.write_str(name)?;
+			}
+		}
+		f.write_str(&self.key)
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Shortcut {
+	pub keys: String,
+	pub command: WmCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShortcutsConfig {
+	/// Evdev device of a physical keyboard to take shortcuts from. Orbit grabs it and passes every
+	/// other key on to a virtual keyboard named `Orbit keyboard`, which the keyboard relay should read instead
+	pub keyboard: Option<PathBuf>,
+	pub bindings: Vec<Shortcut>,
+	/// Apps that need these keys themselves, shortcuts do nothing while one of them is focused
+	pub passthrough_apps: Vec<String>,
+}
+impl Default for ShortcutsConfig {
+	fn default() -> Self {
+		let shortcut = |keys: &str, command| Shortcut {
+			keys: keys.to_string(),
+			command,
+		};
+		ShortcutsConfig {
+			keyboard: None,
+			bindings: vec![
+				shortcut("super+tab", WmCommand::FocusNext),
+				shortcut("super+shift+tab", WmCommand::FocusPrevious),
+				shortcut("super+grave", WmCommand::FocusLast),
+				shortcut("super+esc", WmCommand::ToggleShortcutPassthrough),
+			],
+			passthrough_apps: Vec::new(),
+		}
+	}
+}
+impl ShortcutsConfig {
+	/// Every binding that doesn't parse or is bound twice, as a message.
+	pub fn problems(&self) -> Vec<String> {
+		let mut chords = Vec::new();
+		let mut problems = Vec::new();
+		for binding in &self.bindings {
+			match Chord::parse(&binding.keys) {
+				Ok(chord) if chords.contains(&chord) => {
+					problems.push(format!("Shortcut {chord} is bound more than once"))
+				}
+				Ok(chord) => chords.push(chord),
+				Err(e) => problems.push(e),
+			}
+		}
+		problems
+	}
+}
+
+/// Which key events a grabbed keyboard keeps from the focused app, apart from evdev so it only needs key names.
+struct Interceptor {
+	bindings: Vec<(Chord, WmCommand)>,
+	held: Chord,
+	/// Keys whose press triggered a shortcut, so their repeats and release are kept back too
+	swallowed: Vec<String>,
+}
+impl Interceptor {
+	fn new(bindings: Vec<(Chord, WmCommand)>) -> Self {
+		Interceptor {
+			bindings,
+			held: Chord::default(),
+			swallowed: Vec::new(),
+		}
+	}
+
+	/// The command a key event triggers and whether the event is kept from the focused app.
+	/// `value` is evdev's, 0 for a release, 1 for a press and 2 for a repeat.
+	/// While passing through only the passthrough toggle is taken, so there's a way back out.
+	fn key(&mut self, key: &str, value: i32, passthrough: bool) -> (Option<WmCommand>, bool) {
+		let pressed = value != 0;
+		match key {
+			"leftmeta" | "rightmeta" => self.held.super_ = pressed,
+			"leftctrl" | "rightctrl" => self.held.ctrl = pressed,
+			"leftalt" | "rightalt" => self.held.alt = pressed,
+			"leftshift" | "rightshift" => self.held.shift = pressed,
+			_ if value == 1 => {
+				self.held.key = key.to_string();
+				let command = self
+					.bindings
+					.iter()
+					.find(|(chord, command)| {
+						*chord == self.held
+							&& (!passthrough
+								|| matches!(command, WmCommand::ToggleShortcutPassthrough))
+					})
+					.map(|(_, command)| command.clone());
+				if command.is_some() {
+					self.swallowed.push(key.to_string());
+				}
+				let swallow = command.is_some();
+				return (command, swallow);
+			}
+			_ => {
+				let swallowed = self.swallowed.iter().any(|k| k == key);
+				if value == 0 {
+					self.swallowed.retain(|k| k != key);
+				}
+				return (None, swallowed);
+			}
+		}
+		(None, false)
+	}
+}
+
+/// Commands from the keyboard's shortcuts, and the switch for letting the focused app have them instead.
+pub struct Shortcuts {
+	pub commands: mpsc::UnboundedReceiver<WmCommand>,
+	passthrough: Arc<AtomicBool>,
+}
+impl Shortcuts {
+	pub fn set_passthrough(&self, passthrough: bool) {
+		self.passthrough.store(passthrough, Ordering::Relaxed);
+	}
+}
+
+/// Grab the configured keyboard for bindings, `None` when there isn't one or Orbit was built without evdev.
+pub fn spawn(config: &ShortcutsConfig) -> Option<Shortcuts> {
+	let path = config.keyboard.clone()?;
+	let (tx, rx) = mpsc::unbounded_channel();
+	let passthrough = Arc::new(AtomicBool::new(false));
+	#[cfg(feature = "evdev")]
+	{
+		let interceptor = Interceptor::new(
+			config
+				.bindings
+				.iter()
+				.filter_map(|binding| {
+					Some((Chord::parse(&binding.keys).ok()?, binding.command.clone()))
+				})
+				.collect(),
+		);
+		let passthrough = passthrough.clone();
+		tokio::spawn(async move {
+			if let Err(e) = evdev_keyboard::read(&path, interceptor, passthrough, tx).await {
+				eprintln!("Couldn't grab keyboard at {}: {e}", path.display());
+			}
+		});
+	}
+	#[cfg(not(feature = "evdev"))]
+	{
+		drop(tx);
+		eprintln!(
+			"Orbit was built without the evdev feature, ignoring keyboard {}",
+			path.display()
+		);
+	}
+	Some(Shortcuts {
+		commands: rx,
+		passthrough,
+	})
+}
+
+#[cfg(feature = "evdev")]
+mod evdev_keyboard {
+	use std::{
+		path::Path,
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc,
+		},
+	};
+
+	use evdev::{uinput::VirtualDeviceBuilder, AttributeSet, Device, InputEventKind};
+	use tokio::sync::mpsc;
+
+	use super::Interceptor;
+	use crate::commands::WmCommand;
+
+	pub async fn read(
+		path: &Path,
+		mut interceptor: Interceptor,
+		passthrough: Arc<AtomicBool>,
+		tx: mpsc::UnboundedSender<WmCommand>,
+	) -> std::io::Result<()> {
+		let mut device = Device::open(path)?;
+		let keys = device
+			.supported_keys()
+			.map(|keys| keys.iter().collect::<AttributeSet<_>>())
+			.unwrap_or_default();
+		let mut forward = VirtualDeviceBuilder::new()?
+			.name("Orbit keyboard")
+			.with_keys(&keys)?
+			.build()?;
+		// Everything from here on goes through Orbit, which is what lets it keep shortcuts to itself
+		device.grab()?;
+		let mut events = device.into_event_stream()?;
+		loop {
+			let event = events.next_event().await?;
+			let InputEventKind::Key(key) = event.kind() else {
+				continue;
+			};
+			let name = format!("{key:?}");
+			let name = name.strip_prefix("KEY_").unwrap_or(&name).to_lowercase();
+			let (command, swallow) =
+				interceptor.key(&name, event.value(), passthrough.load(Ordering::Relaxed));
+			if let Some(command) = command {
+				if tx.send(command).is_err() {
+					return Ok(());
+				}
+			}
+			if !swallow {
+				forward.emit(&[event])?;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bindings() -> Vec<(Chord, WmCommand)> {
+		ShortcutsConfig::default()
+			.bindings
+			.into_iter()
+			.map(|binding| (Chord::parse(&binding.keys).unwrap(), binding.command))
+			.collect()
+	}
+
+	#[test]
+	fn chords_parse_modifiers_in_any_order_and_case() {
+		let chord = Chord::parse("Shift + SUPER+Tab").unwrap();
+		assert_eq!(
+			chord,
+			Chord {
+				super_: true,
+				shift: true,
+				key: "tab".to_string(),
+				..Default::default()
+			}
+		);
+		assert_eq!(chord, Chord::parse("meta+shift+tab").unwrap());
+		assert_eq!(Chord::parse("f1").unwrap().key, "f1");
+	}
+
+	#[test]
+	fn chords_reject_unknown_modifiers_and_missing_keys() {
+		assert!(Chord::parse("hyper+tab").is_err());
+		assert!(Chord::parse("super+").is_err());
+		assert!(Chord::parse("").is_err());
+	}
+
+	#[test]
+	fn chords_display_in_a_fixed_order_and_parse_back() {
+		let chord = Chord::parse("shift+alt+ctrl+logo+1").unwrap();
+		assert_eq!(chord.to_string(), "super+ctrl+alt+shift+1");
+		assert_eq!(Chord::parse(&chord.to_string()).unwrap(), chord);
+	}
+
+	#[test]
+	fn problems_cover_bad_and_duplicate_bindings() {
+		assert!(ShortcutsConfig::default().problems().is_empty());
+		let mut config = ShortcutsConfig::default();
+		config.bindings.push(Shortcut {
+			keys: "logo+Tab".to_string(),
+			command: WmCommand::FocusLast,
+		});
+		config.bindings.push(Shortcut {
+			keys: "hyper+q".to_string(),
+			command: WmCommand::FocusLast,
+		});
+		let problems = config.problems();
+		assert_eq!(problems.len(), 2);
+		assert!(problems[0].contains("super+tab"));
+		assert!(problems[1].contains("hyper"));
+	}
+
+	#[test]
+	fn shortcuts_are_kept_from_the_app_with_their_repeats_and_release() {
+		let mut interceptor = Interceptor::new(bindings());
+		assert!(matches!(
+			interceptor.key("leftmeta", 1, false),
+			(None, false)
+		));
+		assert!(matches!(
+			interceptor.key("tab", 1, false),
+			(Some(WmCommand::FocusNext), true)
+		));
+		assert!(matches!(interceptor.key("tab", 2, false), (None, true)));
+		assert!(matches!(interceptor.key("tab", 0, false), (None, true)));
+		assert!(matches!(
+			interceptor.key("leftmeta", 0, false),
+			(None, false)
+		));
+		assert!(matches!(interceptor.key("tab", 1, false), (None, false)));
+	}
+
+	#[test]
+	fn passthrough_forwards_everything_but_its_own_toggle() {
+		let mut interceptor = Interceptor::new(bindings());
+		interceptor.key("rightmeta", 1, true);
+		assert!(matches!(interceptor.key("tab", 1, true), (None, false)));
+		assert!(matches!(interceptor.key("tab", 0, true), (None, false)));
+		assert!(matches!(
+			interceptor.key("esc", 1, true),
+			(Some(WmCommand::ToggleShortcutPassthrough), true)
+		));
+	}
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::i18n::tr_with;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeechConfig {
+	pub enabled: bool,
+	/// Program and leading arguments, the announcement is passed as the last argument
+	pub command: Vec<String>,
+}
+impl Default for SpeechConfig {
+	fn default() -> Self {
+		SpeechConfig {
+			enabled: false,
+			command: vec!["spd-say".to_string()],
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Announcement {
+	Grabbed,
+	Captured,
+	Released,
+	Focused,
+}
+impl Announcement {
+	fn key(self) -> &'static str {
+		match self {
+			Announcement::Grabbed => "speech-grabbed",
+			Announcement::Captured => "speech-captured",
+			Announcement::Released => "speech-released",
+			Announcement::Focused => "speech-focused",
+		}
+	}
+}
+
+impl SpeechConfig {
+	/// Speak what just happened to a window, named by its app id or title.
+	pub fn announce(&self, announcement: Announcement, name: Option<&str>) {
+		if !self.enabled {
+			return;
+		}
+		let Some((program, args)) = self.command.split_first() else {
+			return;
+		};
+		let name = name.unwrap_or_default();
+		let text = tr_with(announcement.key(), &[("name", &name)]);
+		match Command::new(program).args(args).arg(text).spawn() {
+			Ok(mut child) => {
+				tokio::spawn(async move {
+					let _ = child.wait().await;
+				});
+			}
+			Err(e) => eprintln!("Failed to run {program} for speech: {e}"),
+		}
+	}
+}
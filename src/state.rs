@@ -0,0 +1,148 @@
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+	anchors::Anchor,
+	layout::{AppSize, PanelLayout},
+	links::PanelLink,
+	notes::NoteState,
+	profile,
+};
+
+/// Written into every state file, bumped whenever a field changes shape
+const STATE_VERSION: u64 = 1;
+/// Each step brings a file from version `index` to `index + 1`
+const MIGRATIONS: &[fn(&mut Value)] = &[
+	// Files from before versioning only ever gained fields, which default when missing
+	|_| {},
+];
+
+/// What Orbit remembers between runs on its own, as opposed to what the user configures.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+	pub tutorial_completed: bool,
+	/// Seated eye height relative to the client root, standing when unset
+	pub eye_height: Option<f32>,
+	pub links: Vec<PanelLink>,
+	pub notes: Vec<NoteState>,
+	pub anchors: Vec<Anchor>,
+	/// Where recently closed panels were, so a window that comes back takes its old spot
+	pub closed_panels: Vec<PanelLayout>,
+	/// The size each app's panels were last left at by app ID, for new windows that have no spot to go back to
+	pub app_sizes: BTreeMap<String, AppSize>,
+}
+impl State {
+	pub fn path() -> Option<PathBuf> {
+		let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+		Some(profile::dir(dir).join("state.json"))
+	}
+	/// `None` when there's no state file yet, meaning this is the first run.
+	pub fn load() -> Option<Self> {
+		let path = Self::path()?;
+		let file = std::fs::read_to_string(&path).ok()?;
+		let state = serde_json::from_str(&file)
+			.map_err(|e| e.to_string())
+			.and_then(|value| Self::migrate(value).map_err(|e| e.to_string()));
+		Some(state.unwrap_or_else(|e| {
+			// Starting over would overwrite it on the next save, so keep what's there to recover by hand
+			let backup = path.with_extension("json.bak");
+			eprintln!(
+				"Couldn't read state at {} ({e}), starting fresh and keeping it at {}",
+				path.display(),
+				backup.display()
+			);
+			let _ = std::fs::copy(&path, backup);
+			State::default()
+		}))
+	}
+	/// Brings a file written by any older Orbit up to the current shape.
+	fn migrate(mut value: Value) -> serde_json::Result<Self> {
+		let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+		if version > STATE_VERSION {
+			eprintln!(
+				"State was written by a newer Orbit (version {version}), reading what's known"
+			);
+		}
+		for migration in MIGRATIONS.iter().skip(version as usize) {
+			migration(&mut value);
+		}
+		serde_json::from_value(value)
+	}
+	pub fn save(&self) -> Result<()> {
+		let path = Self::path().ok_or_else(|| eyre!("No state directory"))?;
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+		std::fs::write(path, serde_json::to_string_pretty(&self.versioned()?)?)?;
+		Ok(())
+	}
+	fn versioned(&self) -> serde_json::Result<Value> {
+		let mut value = serde_json::to_value(self)?;
+		value["version"] = STATE_VERSION.into();
+		Ok(value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	#[test]
+	fn files_from_before_versioning_load() {
+		let state = State::migrate(json!({
+			"tutorial_completed": true,
+			"eye_height": 1.2,
+		}))
+		.unwrap();
+		assert!(state.tutorial_completed);
+		assert_eq!(state.eye_height, Some(1.2));
+		assert!(state.app_sizes.is_empty());
+	}
+
+	#[test]
+	fn saved_state_reads_back() {
+		let mut state = State {
+			tutorial_completed: true,
+			..Default::default()
+		};
+		state.app_sizes.insert(
+			"editor".to_string(),
+			AppSize {
+				size: [1280, 720],
+				zoom: 1.5,
+			},
+		);
+		let value = state.versioned().unwrap();
+		assert_eq!(value["version"], STATE_VERSION);
+		let read = State::migrate(value).unwrap();
+		assert!(read.tutorial_completed);
+		assert_eq!(read.app_sizes, state.app_sizes);
+	}
+
+	#[test]
+	fn newer_files_keep_what_is_known() {
+		let state = State::migrate(json!({
+			"version": STATE_VERSION + 1,
+			"tutorial_completed": true,
+			"from_the_future": [1, 2, 3],
+		}))
+		.unwrap();
+		assert!(state.tutorial_completed);
+	}
+
+	#[test]
+	fn misshapen_files_are_errors() {
+		assert!(State::migrate(json!({ "tutorial_completed": "yes" })).is_err());
+		assert!(State::migrate(json!("state")).is_err());
+	}
+
+	#[test]
+	fn every_version_has_a_migration() {
+		assert_eq!(MIGRATIONS.len() as u64, STATE_VERSION);
+	}
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{core::values::rgba_linear, drawable::MaterialParameter};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+	#[default]
+	Standard,
+	/// Saturated edge colors, larger text and larger buttons
+	HighContrast,
+}
+impl Theme {
+	pub const ALL: [Theme; 2] = [Theme::Standard, Theme::HighContrast];
+
+	/// Multiplier for text heights
+	pub fn text_scale(self) -> f32 {
+		match self {
+			Theme::Standard => 1.0,
+			Theme::HighContrast => 1.6,
+		}
+	}
+	/// Multiplier for button sizes
+	pub fn target_scale(self) -> f32 {
+		match self {
+			Theme::Standard => 1.0,
+			Theme::HighContrast => 1.5,
+		}
+	}
+
+	/// Edge color with no acceptor nearby
+	pub fn idle_edge(self) -> MaterialParameter {
+		MaterialParameter::Color(match self {
+			Theme::Standard => rgba_linear!(1.0, 1.0, 1.0, 1.0),
+			Theme::HighContrast => rgba_linear!(1.0, 1.0, 0.0, 1.0),
+		})
+	}
+	/// Flashed when a size Orbit asked for was past what the toplevel allows
+	pub fn limit_edge(self) -> MaterialParameter {
+		MaterialParameter::Color(match self {
+			Theme::Standard => rgba_linear!(1.0, 0.55, 0.1, 1.0),
+			Theme::HighContrast => rgba_linear!(1.0, 0.0, 0.0, 1.0),
+		})
+	}
+	/// Flashed when an acceptor didn't take a panel it was asked to capture
+	pub fn rejected_edge(self) -> MaterialParameter {
+		MaterialParameter::Color(match self {
+			Theme::Standard => rgba_linear!(1.0, 0.15, 0.1, 1.0),
+			Theme::HighContrast => rgba_linear!(1.0, 0.0, 0.0, 1.0),
+		})
+	}
+	pub fn shuttered_edge(self) -> MaterialParameter {
+		MaterialParameter::Color(match self {
+			Theme::Standard => rgba_linear!(0.1, 0.1, 0.1, 1.0),
+			Theme::HighContrast => rgba_linear!(1.0, 0.0, 1.0, 1.0),
+		})
+	}
+}
+
+/// Scales a color's brightness, leaving other parameters alone.
+pub fn dimmed(parameter: MaterialParameter, brightness: f32) -> MaterialParameter {
+	match parameter {
+		MaterialParameter::Color(color) => MaterialParameter::Color(rgba_linear!(
+			color.c.r * brightness,
+			color.c.g * brightness,
+			color.c.b * brightness,
+			color.a
+		)),
+		parameter => parameter,
+	}
+}
+
+/// Moves a color part of the way toward grey of the same luminance.
+pub fn desaturated(parameter: MaterialParameter, amount: f32) -> MaterialParameter {
+	match parameter {
+		MaterialParameter::Color(color) => {
+			let grey = 0.2126 * color.c.r + 0.7152 * color.c.g + 0.0722 * color.c.b;
+			let mix = |c: f32| c + (grey - c) * amount;
+			MaterialParameter::Color(rgba_linear!(
+				mix(color.c.r),
+				mix(color.c.g),
+				mix(color.c.b),
+				color.a
+			))
+		}
+		parameter => parameter,
+	}
+}
@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeType,
+	spatial::{SpatialAspect, Transform},
+};
+
+use crate::{
+	decorator::{Decorator, DecoratorContext},
+	theme::Theme,
+};
+
+const TITLE_HEIGHT: f32 = 0.006;
+const TITLE_MARGIN: f32 = 0.003;
+/// Average glyph width relative to the character height, for fitting titles without measuring text
+const GLYPH_ASPECT: f32 = 0.55;
+/// Characters per second a focused panel's long title scrolls by
+const MARQUEE_SPEED: f32 = 4.0;
+const MARQUEE_GAP: &str = "   ";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleBarPosition {
+	#[default]
+	Top,
+	Bottom,
+	Hidden,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleBarRule {
+	pub app_id: String,
+	pub position: TitleBarPosition,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TitleBarConfig {
+	/// Where the title goes for particular apps, like hidden for video players
+	pub rules: Vec<TitleBarRule>,
+	/// Seconds without the panel being hovered before its title fades out, always shown when unset
+	pub auto_hide: Option<f32>,
+}
+impl TitleBarConfig {
+	fn position(&self, app_id: Option<&str>) -> TitleBarPosition {
+		self.rules
+			.iter()
+			.find(|rule| Some(rule.app_id.as_str()) == app_id)
+			.map(|rule| rule.position)
+			.unwrap_or_default()
+	}
+}
+
+/// As much of the label as fits in `max_chars`, scrolling through it while focused and cut off with an ellipsis otherwise.
+fn fitted(label: &str, max_chars: usize, focused: bool, elapsed: f32) -> String {
+	let chars = label.chars().collect::<Vec<_>>();
+	if chars.len() <= max_chars {
+		return label.to_string();
+	}
+	if !focused {
+		let mut shown = chars[..max_chars - 1].iter().collect::<String>();
+		shown.push('…');
+		return shown;
+	}
+	let looped = chars
+		.into_iter()
+		.chain(MARQUEE_GAP.chars())
+		.collect::<Vec<_>>();
+	let offset = (elapsed * MARQUEE_SPEED) as usize % looped.len();
+	looped.iter().cycle().skip(offset).take(max_chars).collect()
+}
+
+pub struct TitleBar {
+	config: TitleBarConfig,
+	position: TitleBarPosition,
+	text: Option<Text>,
+	label: String,
+	/// What the text shows of the label right now
+	shown: String,
+	theme: Theme,
+	last_hovered: f32,
+	visible: bool,
+}
+impl TitleBar {
+	pub fn new(config: TitleBarConfig) -> Self {
+		TitleBar {
+			config,
+			position: TitleBarPosition::Top,
+			text: None,
+			label: String::new(),
+			shown: String::new(),
+			theme: Theme::default(),
+			last_hovered: 0.0,
+			visible: true,
+		}
+	}
+	fn max_chars(ctx: &DecoratorContext) -> usize {
+		let glyph_width = TITLE_HEIGHT * ctx.theme.text_scale() * GLYPH_ASPECT;
+		((ctx.size.x / glyph_width) as usize).max(2)
+	}
+	/// The title, led by the badge count when there is one.
+	fn label(ctx: &DecoratorContext) -> String {
+		let title = ctx.title.unwrap_or_default();
+		match ctx.badge {
+			0 => title.to_string(),
+			badge => format!("({badge}) {title}"),
+		}
+	}
+	fn transform(&self, ctx: &DecoratorContext) -> Transform {
+		let height = TITLE_HEIGHT * ctx.theme.text_scale();
+		let offset = ctx.size.y / 2.0 + TITLE_MARGIN + height;
+		let y = match self.position {
+			TitleBarPosition::Bottom => -offset,
+			TitleBarPosition::Top | TitleBarPosition::Hidden => offset,
+		};
+		Transform::from_translation([0.0, y, 0.0])
+	}
+}
+impl Decorator for TitleBar {
+	fn created(&mut self, ctx: &DecoratorContext) {
+		self.position = self.config.position(ctx.app_id);
+		if self.position == TitleBarPosition::Hidden {
+			return;
+		}
+		self.label = Self::label(ctx);
+		self.shown = fitted(&self.label, Self::max_chars(ctx), ctx.focused, 0.0);
+		self.theme = ctx.theme;
+		self.text = Text::create(
+			ctx.panel_item,
+			self.transform(ctx),
+			&self.shown,
+			TextStyle {
+				character_height: TITLE_HEIGHT * ctx.theme.text_scale(),
+				..Default::default()
+			},
+		)
+		.ok();
+		self.visible = true;
+	}
+	fn resized(&mut self, ctx: &DecoratorContext) {
+		if let Some(text) = &self.text {
+			let _ = text.set_local_transform(self.transform(ctx));
+		}
+	}
+	fn frame(&mut self, ctx: &DecoratorContext, info: &FrameInfo) {
+		if self.position == TitleBarPosition::Hidden {
+			return;
+		}
+		// text styles are fixed once created
+		if ctx.theme != self.theme {
+			self.created(ctx);
+			return;
+		}
+		if let Some(auto_hide) = self.config.auto_hide {
+			let now = info.elapsed as f32;
+			if ctx.hovered {
+				self.last_hovered = now;
+			}
+			let visible = now - self.last_hovered < auto_hide;
+			if visible != self.visible {
+				self.visible = visible;
+				if let Some(text) = &self.text {
+					let _ = text.set_enabled(visible);
+				}
+			}
+		}
+		self.label = Self::label(ctx);
+		let shown = fitted(
+			&self.label,
+			Self::max_chars(ctx),
+			ctx.focused,
+			info.elapsed as f32,
+		);
+		if shown == self.shown {
+			return;
+		}
+		self.shown = shown;
+		if let Some(text) = &self.text {
+			let _ = text.set_text(&self.shown);
+		}
+	}
+}
@@ -0,0 +1,145 @@
+use color_eyre::eyre::{eyre, Result};
+use glam::Quat;
+use stardust_xr_fusion::{
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines, Text, TextStyle},
+	node::NodeError,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::button::{Button, ButtonSettings};
+use tokio::process::Command;
+
+use crate::{
+	desktop_entry::DesktopEntry,
+	i18n::{tr, tr_with},
+	layout::PanelLayout,
+};
+
+const TEXT_HEIGHT: f32 = 0.006;
+const BUTTON_SIZE: f32 = 0.015;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TombstoneAction {
+	Relaunch,
+	Dismiss,
+}
+
+/// An outline where a crashed app's panel was, keeping its spot until it's relaunched or dismissed.
+pub struct Tombstone {
+	pub app_id: String,
+	/// Where the panel was and how big, for the app's next panel to take over
+	pub entry: PanelLayout,
+	_root: Spatial,
+	_outline: Lines,
+	text: Text,
+	relaunch: Button,
+	dismiss: Button,
+	_labels: [Text; 2],
+}
+impl Tombstone {
+	pub fn create(
+		reference: &Spatial,
+		app_id: String,
+		entry: PanelLayout,
+		panel_width: f32,
+	) -> Result<Self, NodeError> {
+		let root = Spatial::create(
+			reference,
+			Transform::from_translation_rotation(
+				entry.translation,
+				Quat::from_array(entry.rotation),
+			),
+			false,
+		)?;
+		let aspect_ratio = entry.size[1] as f32 / entry.size[0].max(1) as f32;
+		let [w, h] = [panel_width / 2.0, panel_width * aspect_ratio / 2.0];
+		let outline = Lines::create(
+			&root,
+			Transform::identity(),
+			&[Line {
+				points: [[-w, h], [w, h], [w, -h], [-w, -h]]
+					.into_iter()
+					.map(|[x, y]| LinePoint {
+						point: [x, y, 0.0].into(),
+						thickness: 0.001,
+						color: rgba_linear!(0.6, 0.6, 0.6, 0.6),
+					})
+					.collect(),
+				cyclic: true,
+			}],
+		)?;
+		let style = || TextStyle {
+			character_height: TEXT_HEIGHT,
+			..Default::default()
+		};
+		let text = Text::create(
+			&root,
+			Transform::from_translation([0.0, TEXT_HEIGHT * 2.0, 0.0]),
+			&tr_with("tombstone", &[("app", &app_id)]),
+			style(),
+		)?;
+		let button = |x: f32| {
+			Button::create(
+				&root,
+				Transform::from_translation([x, -BUTTON_SIZE, 0.0]),
+				[BUTTON_SIZE; 2],
+				ButtonSettings::default(),
+			)
+		};
+		let relaunch = button(-BUTTON_SIZE)?;
+		let dismiss = button(BUTTON_SIZE)?;
+		let label = |button: &Button, key: &str| {
+			Text::create(
+				button.touch_plane().root(),
+				Transform::from_translation([0.0, -BUTTON_SIZE, 0.0]),
+				&tr(key),
+				style(),
+			)
+		};
+		Ok(Tombstone {
+			_labels: [
+				label(&relaunch, "tombstone-relaunch")?,
+				label(&dismiss, "tombstone-dismiss")?,
+			],
+			app_id,
+			entry,
+			_root: root,
+			_outline: outline,
+			text,
+			relaunch,
+			dismiss,
+		})
+	}
+
+	/// Waiting on the app's panel after starting it again.
+	pub fn set_relaunching(&self) {
+		let _ = self
+			.text
+			.set_text(&tr_with("tombstone-relaunching", &[("app", &self.app_id)]));
+	}
+
+	pub fn update(&mut self) -> Option<TombstoneAction> {
+		self.relaunch.update();
+		self.dismiss.update();
+		if self.relaunch.pressed() {
+			Some(TombstoneAction::Relaunch)
+		} else if self.dismiss.pressed() {
+			Some(TombstoneAction::Dismiss)
+		} else {
+			None
+		}
+	}
+}
+
+/// Start the app again from its desktop entry, without any of the files or URLs it was opened with.
+pub fn relaunch(app_id: &str) -> Result<()> {
+	let entry = DesktopEntry::find(app_id).ok_or_else(|| eyre!("No desktop entry for {app_id}"))?;
+	let exec = entry
+		.exec()
+		.ok_or_else(|| eyre!("The desktop entry for {app_id} has no usable Exec line"))?;
+	let mut child = Command::new(&exec[0]).args(&exec[1..]).spawn()?;
+	tokio::spawn(async move {
+		let _ = child.wait().await;
+	});
+	Ok(())
+}
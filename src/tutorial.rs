@@ -0,0 +1,119 @@
+use stardust_xr_fusion::{
+	drawable::{Text, TextAspect, TextStyle},
+	node::NodeError,
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::button::{Button, ButtonSettings};
+
+use crate::i18n::tr;
+
+const TUTORIAL_POSITION: [f32; 3] = [0.0, 0.15, -0.4];
+const TEXT_HEIGHT: f32 = 0.01;
+const BUTTON_SIZE: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TutorialEvent {
+	Grabbed,
+	Captured,
+	Leveled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+	Grab,
+	Accept,
+	Level,
+}
+impl Step {
+	const ALL: [Step; 3] = [Step::Grab, Step::Accept, Step::Level];
+
+	fn text(self) -> String {
+		tr(match self {
+			Step::Grab => "tutorial-grab",
+			Step::Accept => "tutorial-accept",
+			Step::Level => "tutorial-level",
+		})
+	}
+	fn completed_by(self) -> TutorialEvent {
+		match self {
+			Step::Grab => TutorialEvent::Grabbed,
+			Step::Accept => TutorialEvent::Captured,
+			Step::Level => TutorialEvent::Leveled,
+		}
+	}
+}
+
+/// Walks through the basic gestures one prompt at a time, advancing when the user does them.
+pub struct Tutorial {
+	_root: Spatial,
+	text: Text,
+	next: Button,
+	skip: Button,
+	_labels: [Text; 2],
+	step: usize,
+}
+impl Tutorial {
+	pub fn create(reference: &Spatial) -> Result<Self, NodeError> {
+		let root = Spatial::create(
+			reference,
+			Transform::from_translation(TUTORIAL_POSITION),
+			false,
+		)?;
+		let style = || TextStyle {
+			character_height: TEXT_HEIGHT,
+			..Default::default()
+		};
+		let text = Text::create(&root, Transform::identity(), &Step::ALL[0].text(), style())?;
+		let button = |x: f32| {
+			Button::create(
+				&root,
+				Transform::from_translation([x, -0.05, 0.0]),
+				[BUTTON_SIZE; 2],
+				ButtonSettings::default(),
+			)
+		};
+		let next = button(0.03)?;
+		let skip = button(-0.03)?;
+		let label = |button: &Button, key: &str| {
+			Text::create(
+				button.touch_plane().root(),
+				Transform::from_translation([0.0, -BUTTON_SIZE, 0.0]),
+				&tr(key),
+				style(),
+			)
+		};
+		Ok(Tutorial {
+			_labels: [
+				label(&next, "tutorial-next")?,
+				label(&skip, "tutorial-skip")?,
+			],
+			_root: root,
+			text,
+			next,
+			skip,
+			step: 0,
+		})
+	}
+
+	pub fn event(&mut self, event: TutorialEvent) {
+		if Step::ALL.get(self.step).map(|s| s.completed_by()) == Some(event) {
+			self.advance();
+		}
+	}
+	fn advance(&mut self) {
+		self.step += 1;
+		if let Some(step) = Step::ALL.get(self.step) {
+			let _ = self.text.set_text(&step.text());
+		}
+	}
+
+	/// Returns whether the tutorial is over, either finished or skipped.
+	pub fn update(&mut self) -> bool {
+		self.next.update();
+		self.skip.update();
+		if self.next.pressed() {
+			self.advance();
+		}
+		self.skip.pressed() || self.step >= Step::ALL.len()
+	}
+}
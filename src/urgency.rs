@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	core::values::{rgba_linear, ResourceID},
+	drawable::{MaterialParameter, Sound},
+	node::NodeError,
+	spatial::{SpatialAspect, Transform},
+};
+
+/// Seconds between edge flashes
+const FLASH_PERIOD: f32 = 0.8;
+/// Seconds between chimes
+const CHIME_INTERVAL: f32 = 3.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UrgencyConfig {
+	/// Panels whose title contains any of these turn urgent, e.g. "Incoming call"
+	pub titles: Vec<String>,
+	/// Sound played from the panel's direction until it's focused
+	pub chime: Option<PathBuf>,
+}
+impl UrgencyConfig {
+	pub fn matches(&self, title: &str) -> bool {
+		self.titles.iter().any(|t| title.contains(t.as_str()))
+	}
+}
+
+/// A panel asking to be looked at, flashing its edge and chiming until focused.
+pub struct Attention {
+	started_at: Option<f32>,
+	last_chime: Option<f32>,
+	chime: Option<Sound>,
+}
+impl Attention {
+	pub fn create(panel: &impl SpatialAspect, config: &UrgencyConfig) -> Result<Self, NodeError> {
+		let chime = config
+			.chime
+			.as_ref()
+			.and_then(|path| ResourceID::new_direct(path).ok())
+			.map(|resource| Sound::create(panel, Transform::identity(), &resource))
+			.transpose()?;
+		Ok(Attention {
+			started_at: None,
+			last_chime: None,
+			chime,
+		})
+	}
+
+	/// Edge color for this frame.
+	pub fn update(&mut self, now: f32) -> MaterialParameter {
+		let started_at = *self.started_at.get_or_insert(now);
+		if let Some(chime) = &self.chime {
+			if self
+				.last_chime
+				.map_or(true, |last| now - last >= CHIME_INTERVAL)
+			{
+				self.last_chime = Some(now);
+				let _ = chime.play();
+			}
+		}
+		let phase = ((now - started_at) / FLASH_PERIOD).fract();
+		let brightness = 1.0 - (phase * 2.0 - 1.0).abs();
+		MaterialParameter::Color(rgba_linear!(1.0, 0.3 + 0.5 * brightness, 0.1, 1.0))
+	}
+}
+impl Drop for Attention {
+	fn drop(&mut self) {
+		if let Some(chime) = &self.chime {
+			let _ = chime.stop();
+		}
+	}
+}
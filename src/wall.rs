@@ -0,0 +1,576 @@
+use std::future::Future;
+
+use mint::Vector2;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use stardust_xr_fusion::{
+	client::FrameInfo,
+	core::values::rgba_linear,
+	drawable::{Line, LinePoint, Lines},
+	fields::BoxField,
+	node::{NodeError, NodeType},
+	spatial::{Spatial, SpatialAspect, Transform},
+};
+use stardust_xr_molecules::{Grabbable, GrabbableSettings};
+use tokio::sync::mpsc;
+
+/// How far in front of or behind the wall a released panel still snaps onto it
+const WALL_SNAP_DISTANCE: f32 = 0.1;
+/// Seconds panels take to slide into their new slot on reflow
+const REFLOW_DURATION: f32 = 0.3;
+/// Seconds the tiling preview stays up after the last adjustment
+const PREVIEW_DURATION: f32 = 3.0;
+const PREVIEW_THICKNESS: f32 = 0.001;
+const DIVIDER_WIDTH: f32 = 0.01;
+/// The master area never gets squeezed past this fraction of either side
+const MIN_MASTER_RATIO: f32 = 0.2;
+
+struct Slide {
+	from: [f32; 3],
+	to: [f32; 3],
+	started_at: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WallConfig {
+	/// Center of the wall relative to the client root
+	pub position: [f32; 3],
+	/// Quaternion as `[x, y, z, w]`, panels face along the wall's +Z
+	#[serde(default = "identity_rotation")]
+	pub rotation: [f32; 4],
+	pub width: f32,
+	pub height: f32,
+	#[serde(default)]
+	pub tiling: WallTiling,
+}
+fn identity_rotation() -> [f32; 4] {
+	[0.0, 0.0, 0.0, 1.0]
+}
+impl WallConfig {
+	/// Returns the center of each panel in wall space, panels that don't fit are left out.
+	pub fn tile(&self, sizes: &[Vector2<f32>]) -> Vec<Option<[f32; 3]>> {
+		match self.tiling.algorithm {
+			TilingAlgorithm::Shelf => self.tile_shelf(sizes),
+			TilingAlgorithm::MasterStack => self.tile_master_stack(sizes),
+		}
+	}
+	/// X coordinate in wall space where the master area ends and the stack begins.
+	fn master_boundary(&self) -> f32 {
+		let WallTiling {
+			gap,
+			margin,
+			master_ratio,
+			..
+		} = self.tiling;
+		let left = -self.width / 2.0 + margin;
+		let usable = self.width - margin * 2.0;
+		left + usable * master_ratio - gap / 2.0
+	}
+	/// Each panel is centered in its slot, the stack splits its column evenly.
+	fn tile_master_stack(&self, sizes: &[Vector2<f32>]) -> Vec<Option<[f32; 3]>> {
+		let WallTiling { gap, margin, .. } = self.tiling;
+		let left = -self.width / 2.0 + margin;
+		let right = self.width / 2.0 - margin;
+		let top = self.height / 2.0 - margin;
+		let usable_height = self.height - margin * 2.0;
+		let boundary = self.master_boundary();
+		let stacked = sizes.len().saturating_sub(1);
+		let slot_height =
+			(usable_height - gap * stacked.saturating_sub(1) as f32) / stacked.max(1) as f32;
+		sizes
+			.iter()
+			.enumerate()
+			.map(|(i, size)| {
+				let (slot_left, slot_right, slot_top, slot_height) = if i == 0 {
+					let right = if stacked == 0 { right } else { boundary };
+					(left, right, top, usable_height)
+				} else {
+					let slot_top = top - (i - 1) as f32 * (slot_height + gap);
+					(boundary + gap, right, slot_top, slot_height)
+				};
+				if size.x > slot_right - slot_left || size.y > slot_height {
+					return None;
+				}
+				Some([
+					(slot_left + slot_right) / 2.0,
+					slot_top - slot_height / 2.0,
+					0.0,
+				])
+			})
+			.collect()
+	}
+	/// Shelf packing in wall order, left to right then top to bottom.
+	fn tile_shelf(&self, sizes: &[Vector2<f32>]) -> Vec<Option<[f32; 3]>> {
+		let WallTiling {
+			gap,
+			margin,
+			max_columns,
+			..
+		} = self.tiling;
+		let left = -self.width / 2.0 + margin;
+		let right = -left;
+		let bottom = -self.height / 2.0 + margin;
+		let mut x = left;
+		let mut y = self.height / 2.0 - margin;
+		let mut row_height = 0.0_f32;
+		let mut columns = 0;
+		sizes
+			.iter()
+			.map(|size| {
+				let row_full = max_columns.is_some_and(|max| columns >= max);
+				if (x + size.x > right || row_full) && columns > 0 {
+					x = left;
+					y -= row_height + gap;
+					row_height = 0.0;
+					columns = 0;
+				}
+				if x + size.x > right || y - size.y < bottom {
+					return None;
+				}
+				let center = [x + size.x / 2.0, y - size.y / 2.0, 0.0];
+				x += size.x + gap;
+				row_height = row_height.max(size.y);
+				columns += 1;
+				Some(center)
+			})
+			.collect()
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TilingAlgorithm {
+	/// Rows filled left to right, top to bottom
+	#[default]
+	Shelf,
+	/// The first panel fills a master area on the left, the rest stack in a column beside it
+	MasterStack,
+}
+
+/// How panels are packed onto the wall, all lengths in meters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WallTiling {
+	pub algorithm: TilingAlgorithm,
+	/// Space between neighbouring panels
+	pub gap: f32,
+	/// Space kept free along every edge of the wall
+	pub margin: f32,
+	/// Panels per row before wrapping, unlimited when unset
+	pub max_columns: Option<usize>,
+	/// Share of the usable width given to the master area
+	pub master_ratio: f32,
+}
+impl Default for WallTiling {
+	fn default() -> Self {
+		WallTiling {
+			algorithm: TilingAlgorithm::Shelf,
+			gap: 0.01,
+			margin: 0.0,
+			max_columns: None,
+			master_ratio: 0.6,
+		}
+	}
+}
+
+/// A plane that tiles panels dropped onto it.
+pub struct ParkingWall {
+	spatial: Spatial,
+	config: WallConfig,
+	/// Panel uids in tiling order
+	pub panels: Vec<String>,
+	/// Where each panel was last put, in wall space
+	placed: FxHashMap<String, [f32; 3]>,
+	slides: FxHashMap<String, Slide>,
+	/// Set when the tiling needs to be redone
+	pub dirty: bool,
+	/// Outlines of the usable area and every slot, shown while the tiling is being tuned
+	preview: Option<Lines>,
+	preview_requested: bool,
+	preview_until: f32,
+	divider: Option<MasterDivider>,
+	/// Seconds a reflow slide takes, 0 to snap straight into place
+	reflow_duration: f32,
+}
+impl ParkingWall {
+	pub fn create(
+		reference: &Spatial,
+		config: WallConfig,
+		reduce_motion: bool,
+	) -> Result<Self, NodeError> {
+		let spatial = Spatial::create(
+			reference,
+			Transform::from_translation_rotation(config.position, config.rotation),
+			false,
+		)?;
+		let divider = MasterDivider::create_for(&spatial, &config)?;
+		Ok(ParkingWall {
+			spatial,
+			config,
+			panels: Vec::new(),
+			placed: FxHashMap::default(),
+			slides: FxHashMap::default(),
+			dirty: false,
+			preview: None,
+			preview_requested: false,
+			preview_until: 0.0,
+			divider,
+			reflow_duration: if reduce_motion { 0.0 } else { REFLOW_DURATION },
+		})
+	}
+	pub fn spatial(&self) -> &Spatial {
+		&self.spatial
+	}
+
+	/// Retile with new settings and outline the result for a few seconds.
+	pub fn set_tiling(&mut self, tiling: WallTiling) -> Result<(), NodeError> {
+		self.config.tiling = tiling;
+		self.divider = MasterDivider::create_for(&self.spatial, &self.config)?;
+		self.dirty = true;
+		self.preview_requested = true;
+		Ok(())
+	}
+
+	/// Put a panel on the wall where it was dropped, in wall space.
+	/// With master-stack tiling a drop onto the master area promotes the panel to master.
+	pub fn add(&mut self, uid: String, position: [f32; 3]) {
+		let promote = self.config.tiling.algorithm == TilingAlgorithm::MasterStack
+			&& position[0] < self.config.master_boundary();
+		if promote {
+			self.panels.retain(|p| p != &uid);
+			self.panels.insert(0, uid);
+			self.dirty = true;
+		} else {
+			self.push(uid);
+		}
+	}
+	/// Put a panel at the end of the tiling order.
+	pub fn push(&mut self, uid: String) {
+		if !self.panels.contains(&uid) {
+			self.panels.push(uid);
+			self.dirty = true;
+		}
+	}
+	pub fn remove(&mut self, uid: &str) {
+		if !self.panels.iter().any(|p| p == uid) {
+			return;
+		}
+		self.panels.retain(|p| p != uid);
+		self.placed.remove(uid);
+		self.slides.remove(uid);
+		self.dirty = true;
+	}
+
+	/// Retile with the current panel sizes (in `panels` order).
+	/// Panels already on the wall slide to their new slot, new ones snap straight in.
+	pub fn arrange(&mut self, sizes: &[Vector2<f32>], now: f32) {
+		let centers = self.config.tile(sizes);
+		if let Some(divider) = &self.divider {
+			divider.place(&self.spatial, self.config.master_boundary());
+		}
+		if std::mem::take(&mut self.preview_requested) {
+			self.preview_until = now + PREVIEW_DURATION;
+		}
+		if now < self.preview_until {
+			self.draw_preview(sizes, &centers);
+		}
+		let mut overflow = Vec::new();
+		for (uid, center) in self.panels.iter().zip(centers) {
+			let Some(to) = center else {
+				overflow.push(uid.clone());
+				continue;
+			};
+			let from = self.placed.get(uid).copied().unwrap_or(to);
+			self.placed.insert(uid.clone(), to);
+			self.slides.insert(
+				uid.clone(),
+				Slide {
+					from,
+					to,
+					started_at: now,
+				},
+			);
+		}
+		for uid in overflow {
+			self.remove(&uid);
+		}
+		self.dirty = false;
+	}
+	/// Positions for panels still sliding this frame, in wall space.
+	pub fn step(&mut self, info: &FrameInfo) -> Vec<(String, [f32; 3])> {
+		let now = info.elapsed as f32;
+		if let Some(divider) = &mut self.divider {
+			if let Some(x) = divider.update(info, &self.spatial) {
+				let WallTiling { gap, margin, .. } = self.config.tiling;
+				let usable = self.config.width - margin * 2.0;
+				let ratio = (x + gap / 2.0 + self.config.width / 2.0 - margin) / usable;
+				self.config.tiling.master_ratio =
+					ratio.clamp(MIN_MASTER_RATIO, 1.0 - MIN_MASTER_RATIO);
+				self.dirty = true;
+			}
+		}
+		if self.preview.is_some() && now >= self.preview_until {
+			self.preview = None;
+		}
+		let mut positions = Vec::new();
+		self.slides.retain(|uid, slide| {
+			let t = if self.reflow_duration > 0.0 {
+				((now - slide.started_at) / self.reflow_duration).clamp(0.0, 1.0)
+			} else {
+				1.0
+			};
+			let t = t * t * (3.0 - 2.0 * t);
+			let position = [0, 1, 2].map(|i| slide.from[i] + (slide.to[i] - slide.from[i]) * t);
+			positions.push((uid.clone(), position));
+			t < 1.0
+		});
+		positions
+	}
+
+	fn draw_preview(&mut self, sizes: &[Vector2<f32>], centers: &[Option<[f32; 3]>]) {
+		let margin = self.config.tiling.margin;
+		let area = [
+			(self.config.width - margin * 2.0).max(0.0),
+			(self.config.height - margin * 2.0).max(0.0),
+		];
+		let mut lines = vec![outline([0.0; 3], area, [1.0, 1.0, 1.0])];
+		lines.extend(sizes.iter().zip(centers).filter_map(|(size, center)| {
+			Some(outline((*center)?, [size.x, size.y], [0.4, 0.7, 1.0]))
+		}));
+		self.preview = Lines::create(&self.spatial, Transform::identity(), &lines).ok();
+	}
+
+	/// Where on the wall a panel at this spot lands, if it's close enough to snap onto it.
+	async fn is_near(wall: Spatial, width: f32, height: f32, panel: Spatial) -> Option<[f32; 3]> {
+		let position = panel.get_transform(&wall).await.ok()?.translation?;
+		(position.z.abs() < WALL_SNAP_DISTANCE
+			&& position.x.abs() < width / 2.0
+			&& position.y.abs() < height / 2.0)
+			.then_some(position.into())
+	}
+	pub fn near_query(&self, panel: &Spatial) -> impl Future<Output = Option<[f32; 3]>> {
+		Self::is_near(
+			self.spatial.alias(),
+			self.config.width,
+			self.config.height,
+			panel.alias(),
+		)
+	}
+}
+
+fn outline(center: [f32; 3], size: [f32; 2], color: [f32; 3]) -> Line {
+	let [x, y, z] = center;
+	let [w, h] = [size[0] / 2.0, size[1] / 2.0];
+	let points = [[-w, h], [w, h], [w, -h], [-w, -h]]
+		.into_iter()
+		.map(|[dx, dy]| LinePoint {
+			point: [x + dx, y + dy, z].into(),
+			thickness: PREVIEW_THICKNESS,
+			color: rgba_linear!(color[0], color[1], color[2], 1.0),
+		})
+		.collect();
+	Line {
+		points,
+		cyclic: true,
+	}
+}
+
+/// A handle on the seam between the master area and the stack, dragged sideways to resize the master.
+struct MasterDivider {
+	_field: BoxField,
+	grabbable: Grabbable,
+	_line: Lines,
+	position_tx: mpsc::UnboundedSender<f32>,
+	position_rx: mpsc::UnboundedReceiver<f32>,
+}
+impl MasterDivider {
+	fn create_for(wall: &Spatial, config: &WallConfig) -> Result<Option<Self>, NodeError> {
+		if config.tiling.algorithm != TilingAlgorithm::MasterStack {
+			return Ok(None);
+		}
+		let field = BoxField::create(
+			wall,
+			Transform::identity(),
+			[DIVIDER_WIDTH, config.height, DIVIDER_WIDTH],
+		)?;
+		let grabbable = Grabbable::create(
+			wall,
+			Transform::identity(),
+			&field,
+			GrabbableSettings::default(),
+		)?;
+		field.set_spatial_parent_in_place(grabbable.content_parent())?;
+		let half_height = config.height / 2.0;
+		let line = Lines::create(
+			grabbable.content_parent(),
+			Transform::identity(),
+			&[Line {
+				points: [half_height, -half_height]
+					.into_iter()
+					.map(|y| LinePoint {
+						point: [0.0, y, 0.0].into(),
+						thickness: PREVIEW_THICKNESS,
+						color: rgba_linear!(1.0, 1.0, 1.0, 0.5),
+					})
+					.collect(),
+				cyclic: false,
+			}],
+		)?;
+		let (position_tx, position_rx) = mpsc::unbounded_channel();
+		Ok(Some(MasterDivider {
+			_field: field,
+			grabbable,
+			_line: line,
+			position_tx,
+			position_rx,
+		}))
+	}
+
+	fn place(&self, wall: &Spatial, x: f32) {
+		self.grabbable.cancel_linear_velocity();
+		self.grabbable.cancel_angular_velocity();
+		let _ = self
+			.grabbable
+			.content_parent()
+			.set_relative_transform(wall, Transform::from_translation([x, 0.0, 0.0]));
+	}
+	/// Returns the x in wall space the divider was let go at.
+	fn update(&mut self, info: &FrameInfo, wall: &Spatial) -> Option<f32> {
+		let _ = self.grabbable.update(info);
+		if self.grabbable.grab_action().actor_stopped() {
+			let content = self.grabbable.content_parent().alias();
+			let wall = wall.alias();
+			let position_tx = self.position_tx.clone();
+			tokio::spawn(async move {
+				if let Some(translation) = content
+					.get_transform(&wall)
+					.await
+					.ok()
+					.and_then(|t| t.translation)
+				{
+					let _ = position_tx.send(translation.x);
+				}
+			});
+		}
+		self.position_rx.try_recv().ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A 1 by 0.6 m wall with a 1 cm gap and no margin.
+	fn wall(algorithm: TilingAlgorithm) -> WallConfig {
+		WallConfig {
+			position: [0.0; 3],
+			rotation: identity_rotation(),
+			width: 1.0,
+			height: 0.6,
+			tiling: WallTiling {
+				algorithm,
+				..Default::default()
+			},
+		}
+	}
+	fn sizes(sizes: &[[f32; 2]]) -> Vec<Vector2<f32>> {
+		sizes.iter().map(|&size| size.into()).collect()
+	}
+	fn assert_centers(centers: Vec<Option<[f32; 3]>>, expected: &[Option<[f32; 2]>]) {
+		assert_eq!(centers.len(), expected.len(), "{centers:?}");
+		for (center, expected) in centers.iter().zip(expected) {
+			match (center, expected) {
+				(Some([x, y, z]), Some([ex, ey])) => assert!(
+					(x - ex).abs() < 1e-5 && (y - ey).abs() < 1e-5 && *z == 0.0,
+					"{centers:?}"
+				),
+				(None, None) => (),
+				_ => panic!("{centers:?} isn't {expected:?}"),
+			}
+		}
+	}
+
+	#[test]
+	fn empty_walls_tile_nothing() {
+		assert!(wall(TilingAlgorithm::Shelf).tile(&[]).is_empty());
+		assert!(wall(TilingAlgorithm::MasterStack).tile(&[]).is_empty());
+	}
+
+	#[test]
+	fn shelf_starts_in_the_top_left() {
+		let centers = wall(TilingAlgorithm::Shelf).tile(&sizes(&[[0.3, 0.2]]));
+		assert_centers(centers, &[Some([-0.35, 0.2])]);
+	}
+
+	#[test]
+	fn shelf_wraps_rows_and_leaves_out_what_overflows() {
+		let centers = wall(TilingAlgorithm::Shelf).tile(&sizes(&[
+			[0.4, 0.2],
+			[0.4, 0.3],
+			[0.4, 0.2],
+			[1.2, 0.1],
+			[0.4, 0.4],
+		]));
+		assert_centers(
+			centers,
+			&[
+				Some([-0.3, 0.2]),
+				Some([0.11, 0.15]),
+				// The next row starts under the tallest panel of the first
+				Some([-0.3, -0.11]),
+				// Wider than the wall, and then too tall for what's left
+				None,
+				None,
+			],
+		);
+	}
+
+	#[test]
+	fn shelf_wraps_at_max_columns() {
+		let mut wall = wall(TilingAlgorithm::Shelf);
+		wall.tiling.max_columns = Some(1);
+		let centers = wall.tile(&sizes(&[[0.2, 0.2], [0.2, 0.2]]));
+		assert_centers(centers, &[Some([-0.4, 0.2]), Some([-0.4, -0.01])]);
+	}
+
+	#[test]
+	fn a_lone_master_gets_the_whole_wall() {
+		let centers = wall(TilingAlgorithm::MasterStack).tile(&sizes(&[[0.9, 0.5]]));
+		assert_centers(centers, &[Some([0.0, 0.0])]);
+	}
+
+	#[test]
+	fn master_stack_splits_at_the_ratio() {
+		let wall = wall(TilingAlgorithm::MasterStack);
+		assert!((wall.master_boundary() - 0.095).abs() < 1e-5);
+		let centers = wall.tile(&sizes(&[[0.5, 0.5], [0.3, 0.2], [0.3, 0.2]]));
+		assert_centers(
+			centers,
+			&[
+				Some([-0.2025, 0.0]),
+				Some([0.3025, 0.1525]),
+				Some([0.3025, -0.1525]),
+			],
+		);
+	}
+
+	#[test]
+	fn master_stack_leaves_out_what_overflows_its_slot() {
+		let centers = wall(TilingAlgorithm::MasterStack).tile(&sizes(&[
+			[0.7, 0.5],
+			[0.3, 0.1],
+			[0.3, 0.25],
+			[0.5, 0.1],
+		]));
+		assert_centers(
+			centers,
+			&[
+				None,
+				Some([0.3025, 0.203333]),
+				None,
+				// Only 0.395 m wide is left beside the master
+				None,
+			],
+		);
+	}
+}